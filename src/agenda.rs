@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::dates::{format_due, timestamp_to_date};
+use crate::models::DBRow;
+
+
+/// Tasks due on one day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgendaDay {
+    pub date: NaiveDate,
+    pub tasks: Vec<DBRow>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Agenda {
+    /// Tasks due before today, oldest first.
+    pub overdue: Vec<DBRow>,
+    /// Today and the following days of the period, days without tasks
+    /// included.
+    pub days: Vec<AgendaDay>,
+}
+
+/// Groups the tasks with a due date by day, from today up to `to`. Tasks
+/// due later are left out, as are tasks without a due date.
+pub fn agenda(rows: &[DBRow], today: NaiveDate, to: NaiveDate) -> Agenda {
+    let mut due: Vec<&DBRow> = rows.iter().filter(|r| r.due.is_some()).collect();
+    due.sort_by_key(|r| (r.due, r.id));
+    let on = |date: NaiveDate| -> Vec<DBRow> {
+        due.iter()
+            .filter(|r| r.due.map(timestamp_to_date) == Some(date))
+            .map(|r| (*r).clone())
+            .collect()
+    };
+
+    Agenda {
+        overdue: due.iter()
+            .filter(|r| r.due.map(timestamp_to_date).unwrap_or(today) < today)
+            .map(|r| (*r).clone())
+            .collect(),
+        days: today.iter_days()
+            .take_while(|d| *d <= to)
+            .map(|d| AgendaDay { date: d, tasks: on(d) })
+            .collect(),
+    }
+}
+
+/// Last day of the month `date` is in.
+pub fn end_of_month(date: NaiveDate) -> NaiveDate {
+    let first = date.with_day(1).unwrap();
+    let next = if first.month() == 12 {
+        first.with_year(first.year() + 1).unwrap().with_month(1).unwrap()
+    } else {
+        first.with_month(first.month() + 1).unwrap()
+    };
+
+    next - Duration::days(1)
+}
+
+pub fn render_agenda(agenda: &Agenda, today: NaiveDate) -> String {
+    let mut out = String::new();
+
+    if !agenda.overdue.is_empty() {
+        out.push_str("Overdue\n");
+        for r in &agenda.overdue {
+            out.push_str(&format!("  {}  (due {})\n", task_line(r), format_due(r.due.unwrap_or_default())));
+        }
+        out.push('\n');
+    }
+
+    for day in &agenda.days {
+        let title = day.date.format("%a %d %b").to_string();
+        if day.date == today {
+            out.push_str(&format!("Today, {}\n", title));
+        } else if day.date == today + Duration::days(1) {
+            out.push_str(&format!("Tomorrow, {}\n", title));
+        } else if day.tasks.is_empty() {
+            continue;
+        } else {
+            out.push_str(&format!("{}\n", title));
+        }
+
+        if day.tasks.is_empty() {
+            out.push_str("  nothing due\n");
+        }
+        for r in &day.tasks {
+            let due = format_due(r.due.unwrap_or_default());
+            let time = due.split_once(' ').map(|(_, t)| format!("{}  ", t)).unwrap_or_default();
+            out.push_str(&format!("  {}{}\n", time, task_line(r)));
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+fn task_line(r: &DBRow) -> String {
+    let tags: String = r.tags.iter().map(|t| format!(" +{}", t)).collect();
+    let priority = r.priority.map(|p| format!(" !{}", p)).unwrap_or_default();
+
+    format!("#{} {}{}{}", r.id, r.task, tags, priority)
+}
+
+/// Number of tasks due on each day of the month `date` is in.
+pub fn month_counts(rows: &[DBRow], date: NaiveDate) -> BTreeMap<NaiveDate, usize> {
+    let first = date.with_day(1).unwrap();
+    let last = end_of_month(date);
+
+    let mut counts: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    for d in rows.iter().filter_map(|r| r.due).map(timestamp_to_date) {
+        if d >= first && d <= last {
+            *counts.entry(d).or_default() += 1;
+        }
+    }
+
+    counts
+}
+
+/// Calendar of the month `today` is in, weeks starting on Monday. Each day
+/// shows its number of due tasks, today is bracketed.
+pub fn render_month(today: NaiveDate, counts: &BTreeMap<NaiveDate, usize>) -> String {
+    let first = today.with_day(1).unwrap();
+    let last = end_of_month(today);
+
+    let mut out = format!("{}\n", first.format("%B %Y"));
+    let header: Vec<String> = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"].iter()
+        .map(|d| format!("{:>4}   ", d))
+        .collect();
+    out.push_str(&format!("{}\n", header.join(" ").trim_end()));
+
+    let mut cells: Vec<String> = vec![" ".repeat(7); first.weekday().num_days_from_monday() as usize];
+    for day in first.iter_days().take_while(|d| *d <= last) {
+        let label = if day == today { format!("[{}]", day.day()) } else { day.day().to_string() };
+        let count = counts.get(&day).map(|c| c.to_string()).unwrap_or_default();
+        cells.push(format!("{:>4} {:<2}", label, count));
+    }
+
+    for week in cells.chunks(7) {
+        out.push_str(&format!("{}\n", week.join(" ").trim_end()));
+    }
+
+    out
+}
@@ -1,8 +1,21 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::board::GroupBy;
+use crate::bulk::{parse_id_list, IdList};
+use crate::burndown::BurndownFormat;
+use crate::export::ExportFormat;
+use crate::models::{Column, Priority};
+use crate::query::Query;
+use crate::timelog::parse_duration;
 
 #[derive(Parser, Debug)]
 #[command(version, about="A simple TODO list manager")]
 pub struct Cli {
+    /// Explains errors in more detail, with what caused them
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
     #[command(subcommand)]
     pub command: CliCommands,
 }
@@ -12,30 +25,370 @@ pub enum CliCommands {
     /// Adds new task
     #[command(alias="a")]
     Add {
+        /// Tags the task, can be repeated
+        #[arg(short, long="tag")]
+        tags: Vec<String>,
+        /// Due date as YYYY-MM-DD, tomorrow, a weekday such as fri or e.g. 3d
+        #[arg(long)]
+        due: Option<String>,
+        /// Priority: low, medium or high
+        #[arg(short, long)]
+        priority: Option<Priority>,
+        /// Estimated effort in points or hours
+        #[arg(long)]
+        estimate: Option<u32>,
+        /// Takes the text as it is, without picking out dates, +tags, @contexts,
+        /// !priority and recurrence
+        #[arg(long)]
+        raw: bool,
+        /// Task text, e.g. "Call bob tomorrow 3pm +work !high @phone every monday"
         #[arg(trailing_var_arg = true)]
         value: Vec<String>
     },
-    /// Deletes task by ID
+    /// Deletes tasks by ID list (e.g. 1,4,7-12), tag and/or filter
     #[command(alias="d")]
-    Delete { id: u32},
+    Delete {
+        #[command(flatten)]
+        target: TargetArgs,
+    },
     #[command(alias="dc")]
     DeleteCompleted,
-    /// Completes a task by ID
+    /// Completes tasks by ID list (e.g. 1,4,7-12), tag and/or filter
     #[command(alias="c")]
-    Complete { id: u32},
-    /// Uncompletes a task by ID
+    Complete {
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+    /// Reopens done and cancelled tasks by ID list (e.g. 1,4,7-12), tag and/or filter
     #[command(alias="u")]
-    Uncomplete { id: u32},
-    /// Prints all tasks
+    Uncomplete {
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+    /// Marks tasks as waiting on something by ID list, tag and/or filter
+    Wait {
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+    /// Cancels tasks by ID list, tag and/or filter
+    Cancel {
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+    /// Prints all tasks, optionally filtered
     #[command(alias="l")]
-    List,
+    List {
+        #[command(flatten)]
+        list: ListArgs,
+    },
+    /// Prints incomplete tasks, same as `list status:open`
     #[command(alias="li")]
-    ListIncomplete,
+    ListIncomplete {
+        #[command(flatten)]
+        list: ListArgs,
+    },
+    /// Prints completed tasks, same as `list status:done`
     #[command(alias="lc")]
-    ListComplete,
+    ListComplete {
+        #[command(flatten)]
+        list: ListArgs,
+    },
+    /// Exports tasks to todo.txt, iCalendar, JSON or Markdown
+    #[command(alias="e")]
+    Export {
+        #[arg(long, value_enum)]
+        to: ExportFormat,
+        /// Writes to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Exports only completed tasks
+        #[arg(long, conflicts_with="incomplete")]
+        complete: bool,
+        /// Exports only incomplete tasks
+        #[arg(long)]
+        incomplete: bool,
+    },
+    /// Prints the open tasks that can be picked up now, in a context such as @phone
+    Next {
+        context: Option<String>,
+        /// Shows at most this many tasks
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Lists the contexts in use with their open tasks and next actions
+    Contexts,
+    /// Prints tasks as a board with a column per status, tag or priority
+    Board {
+        /// Filter expression, e.g. tag:work
+        query: Vec<String>,
+        #[arg(long, value_enum, default_value="status")]
+        group_by: GroupBy,
+        /// Width to fit the board in, defaults to the terminal width
+        #[arg(long)]
+        width: Option<usize>,
+        /// Also shows tasks that are snoozed
+        #[arg(long)]
+        include_snoozed: bool,
+    },
+    /// Prints open tasks by due date: overdue, today and the days ahead
+    Agenda {
+        /// Filter expression, e.g. tag:work
+        query: Vec<String>,
+        /// Shows the next seven days
+        #[arg(long, conflicts_with="month")]
+        week: bool,
+        /// Shows a calendar of this month and the rest of it
+        #[arg(long)]
+        month: bool,
+        /// Also shows tasks that are snoozed
+        #[arg(long)]
+        include_snoozed: bool,
+    },
+    /// Opens the interactive full-screen task list
+    Tui,
+    /// Serves the tasks over a local HTTP JSON API
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value="127.0.0.1:8080")]
+        bind: String,
+    },
+    /// Answers JSON-RPC 2.0 requests on stdin, one per line
+    Rpc,
+    /// Syncs the tasks with a git remote, merging changes row by row
+    Sync {
+        /// Sets the remote to pull from and push to, e.g. a bare repository
+        #[arg(long)]
+        remote: Option<String>,
+    },
+    /// Merges two copies of the database row by row, usable as a git merge driver
+    Merge {
+        /// Common ancestor, a missing file counts as empty
+        base: PathBuf,
+        /// Our copy, overwritten with the result unless --output is given
+        ours: PathBuf,
+        /// Their copy
+        theirs: PathBuf,
+        /// Writes the result here instead of over OURS
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Writes the conflict report to this file instead of stderr
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+    /// Manages saved views, run a view with `todo <name>`
+    View {
+        #[command(subcommand)]
+        command: ViewCommands,
+    },
+    /// Hides a task from the listings until a date, wakes it up when left out
+    Snooze {
+        id: u32,
+        /// YYYY-MM-DD, tomorrow, a weekday such as mon or e.g. 3d or 2w
+        when: Option<String>,
+    },
+    /// Prints every detail of a task, its notes and history
+    Show { id: u32 },
+    /// Prints who changed what and when, for one task or all
+    History {
+        id: Option<u32>,
+        /// Only changes on or after this day, e.g. 2026-10-01 or yesterday
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// Sets the notes of a task, clears them when left out
+    Note {
+        id: u32,
+        #[arg(trailing_var_arg = true)]
+        text: Vec<String>,
+    },
+    /// Sets the tasks a task waits for, e.g. `depend 5 3,4`, clears them when left out
+    Depend {
+        id: u32,
+        #[arg(value_parser=parse_id_list)]
+        on: Option<IdList>,
+    },
+    /// Sets the estimated effort of a task, clears it when left out
+    Estimate {
+        id: u32,
+        value: Option<u32>,
+    },
+    /// Marks a task in progress and starts its timer, stopping any other running timer
+    Start { id: u32 },
+    /// Stops the running timer
+    Stop,
+    /// Logs time spent on a task, e.g. 1h30m or 45m
+    Log {
+        id: u32,
+        #[arg(value_parser=parse_duration)]
+        duration: i64,
+    },
+    /// Sends reminders for tasks that are due soon or overdue, meant for cron
+    Remind {
+        /// How far ahead to look, e.g. 1d, 2h or 30m
+        #[arg(long, default_value="1d", value_parser=parse_duration)]
+        within: i64,
+        /// Where to deliver the reminders
+        #[arg(long, value_enum, default_value="stdout")]
+        via: NotifyVia,
+        /// Desktop notification command, gets the subject and body as arguments
+        #[arg(long, default_value="notify-send")]
+        command: String,
+        /// URL to post the reminders to with --via webhook
+        #[arg(long)]
+        url: Option<String>,
+        /// Mail address to send the reminders to with --via mail
+        #[arg(long)]
+        to: Option<String>,
+        /// Sendmail compatible program used with --via mail
+        #[arg(long, default_value="sendmail -i")]
+        sendmail: String,
+        /// Also sends reminders that were already sent
+        #[arg(long)]
+        again: bool,
+    },
+    /// Prints counts, completion rates and streaks
+    Stats {
+        /// Prints JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Prints reports
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+    /// Checks the database for rows that don't load, such as duplicate IDs
+    #[command(alias="repair")]
+    Doctor {
+        /// Rewrites the database without those rows, after copying it to a backup
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Makes, lists and restores copies of the database
+    Backup {
+        #[command(subcommand)]
+        command: BackupCommands,
+    },
+    #[command(external_subcommand)]
+    RunView(Vec<String>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum NotifyVia {
+    Stdout,
+    Desktop,
+    Webhook,
+    Mail,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ReportCommands {
+    /// Prints time logged per task
+    Time {
+        /// First day to include, YYYY-MM-DD, today or yesterday
+        #[arg(long)]
+        since: Option<String>,
+        /// Last day to include
+        #[arg(long)]
+        until: Option<String>,
+    },
+    /// Prints the remaining estimated work per day
+    Burndown {
+        /// Filter expression selecting the tasks, e.g. tag:sprint-12
+        query: Vec<String>,
+        /// First day, defaults to two weeks ago
+        #[arg(long)]
+        from: Option<String>,
+        /// Last day, defaults to today
+        #[arg(long)]
+        to: Option<String>,
+        #[arg(long, value_enum, default_value="text")]
+        format: BurndownFormat,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ViewCommands {
+    /// Saves a query with its sort order, limit and columns under a name
+    Save {
+        name: String,
+        #[command(flatten)]
+        list: ListArgs,
+    },
+    /// Prints built-in and saved views
+    #[command(alias="ls")]
+    List,
+    /// Deletes a saved view
+    #[command(alias="rm")]
+    Delete { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BackupCommands {
+    /// Copies the database to a new backup
+    Now,
+    /// Prints the backups, oldest first
+    #[command(alias="ls")]
+    List,
+    /// Replaces the database with a backup, after backing it up
+    Restore { name: String },
+}
+
+/// Selects the rows a bulk command applies to.
+#[derive(Args, Debug)]
+#[group(required = true, multiple = true)]
+pub struct TargetArgs {
+    #[arg(value_parser=parse_id_list)]
+    pub ids: Option<IdList>,
+    #[arg(long)]
+    pub tag: Option<String>,
+    /// Filter expression, e.g. "status:open tag:work due<today"
+    #[arg(long="where", value_parser=parse_query)]
+    pub filter: Option<Query>,
+}
+
+#[derive(Args, Debug)]
+pub struct ListArgs {
+    /// Filter expression, e.g. status:open tag:work due<2026-11-01 "text"
+    pub query: Vec<String>,
+    /// Sort keys, e.g. due,-priority
+    #[arg(long, allow_hyphen_values = true)]
+    pub sort: Option<String>,
+    /// Shows at most this many tasks
+    #[arg(long)]
+    pub limit: Option<usize>,
+    /// Columns to print, e.g. id,state,task
+    #[arg(long, value_enum, value_delimiter=',')]
+    pub columns: Vec<Column>,
+    /// Also shows tasks that are snoozed
+    #[arg(long)]
+    pub include_snoozed: bool,
+}
+
+impl ListArgs {
+    /// Query text combining the free-form filter with `--sort`, `--limit`
+    /// and `--include-snoozed`.
+    pub fn query_string(&self) -> String {
+        let mut parts = vec![crate::query::join_args(&self.query)];
+        if self.include_snoozed {
+            parts.push("snoozed:any".to_string());
+        }
+        if let Some(sort) = &self.sort {
+            parts.push(format!("sort:{}", sort));
+        }
+        if let Some(limit) = self.limit {
+            parts.push(format!("limit:{}", limit));
+        }
+
+        parts.join(" ").trim().to_string()
+    }
+}
+
+fn parse_query(value: &str) -> Result<Query, String> {
+    Query::parse(value).map_err(|e| e.to_string())
 }
 
 
 pub fn parse_arguments() -> Cli {
-    return Cli::parse();
-}
\ No newline at end of file
+    Cli::parse()
+}
@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::dates::format_due;
+use crate::doctor::Problem;
+use crate::models::{DBError, DBRow};
+
+
+/// One field of a row before and after a change, `None` where the row
+/// didn't exist.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// A change to one task.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct AuditEntry {
+    pub at: i64,
+    pub user: String,
+    /// What made the change, e.g. `add`, a bulk `complete` or `repair`.
+    pub operation: String,
+    pub id: u32,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Who changes the tasks: `$TODO_USER`, else `user` in the JSON file at
+/// `config`, else the login name. A config that can't be read is skipped,
+/// it shouldn't stop a change from being made.
+pub fn current_user(config: &Path) -> String {
+    let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let configured = || fs::read_to_string(config).ok()
+        .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+        .and_then(|c| c.get("user").and_then(Value::as_str).map(str::to_string))
+        .filter(|u| !u.is_empty());
+
+    env("TODO_USER")
+        .or_else(configured)
+        .or_else(|| env("USER"))
+        .or_else(|| env("USERNAME"))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Entries for the rows that differ between `before` and `after`, the
+/// update date alone is no change. Values are kept as stored, timestamps
+/// as seconds.
+pub fn diff(before: &[DBRow], after: &[DBRow], operation: &str, user: &str, at: i64) -> Vec<AuditEntry> {
+    let old = fields_by_id(before);
+    let new = fields_by_id(after);
+
+    let mut ids: Vec<&u32> = old.keys().chain(new.keys()).collect();
+    ids.sort();
+    ids.dedup();
+
+    ids.into_iter()
+        .filter_map(|id| {
+            let changes = diff_fields(old.get(id), new.get(id));
+            if changes.is_empty() {
+                return None;
+            }
+            Some(AuditEntry {
+                at,
+                user: user.to_string(),
+                operation: operation.to_string(),
+                id: *id,
+                changes,
+            })
+        })
+        .collect()
+}
+
+/// Entries for the lines a repair dropped that have an ID, with the line
+/// and what was wrong with it as the change.
+pub fn dropped(problems: &[Problem], operation: &str, user: &str, at: i64) -> Vec<AuditEntry> {
+    problems.iter()
+        .filter_map(|p| p.id.map(|id| AuditEntry {
+            at,
+            user: user.to_string(),
+            operation: operation.to_string(),
+            id,
+            changes: vec![FieldChange {
+                field: "line".to_string(),
+                old: Some(format!("{} ({}: {})", p.line, p.kind, p.message)),
+                new: None,
+            }],
+        }))
+        .collect()
+}
+
+fn fields_by_id(rows: &[DBRow]) -> BTreeMap<u32, BTreeMap<String, String>> {
+    rows.iter()
+        .map(|r| {
+            let fields = match serde_json::to_value(r) {
+                Ok(Value::Object(map)) => map.into_iter()
+                    // `completed` follows the status
+                    .filter(|(k, _)| !["id", "updatedate", "completed"].contains(&k.as_str()))
+                    .map(|(k, v)| (k, field_text(&v)))
+                    .collect(),
+                _ => BTreeMap::new(),
+            };
+            (r.id, fields)
+        })
+        .collect()
+}
+
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(field_text).collect::<Vec<String>>().join(","),
+        v => v.to_string(),
+    }
+}
+
+fn diff_fields(old: Option<&BTreeMap<String, String>>, new: Option<&BTreeMap<String, String>>) -> Vec<FieldChange> {
+    let empty = BTreeMap::new();
+    let (old_fields, new_fields) = (old.unwrap_or(&empty), new.unwrap_or(&empty));
+
+    let mut names: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names.into_iter()
+        // a new or deleted row only lists the fields that have a value
+        .map(|name| (
+            name,
+            old.map(|_| old_fields.get(name).cloned().unwrap_or_default()),
+            new.map(|_| new_fields.get(name).cloned().unwrap_or_default()),
+        ))
+        .filter(|(_, o, n)| o != n)
+        .filter(|(_, o, n)| has_value(o) || has_value(n))
+        .map(|(name, old, new)| FieldChange { field: name.clone(), old, new })
+        .collect()
+}
+
+fn has_value(value: &Option<String>) -> bool {
+    value.as_ref().map(|v| !v.is_empty()).unwrap_or(false)
+}
+
+/// Changes made to the tasks, one JSON object per line in a file next to
+/// the database. Entries are only ever appended, so they outlive the rows
+/// they are about.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        AuditLog { path }
+    }
+
+    /// The user changes are logged for, see `current_user`. The config is
+    /// `config.json` next to the log.
+    pub fn user(&self) -> String {
+        current_user(&self.path.with_file_name("config.json"))
+    }
+
+    pub fn append(&self, entries: &[AuditEntry]) -> Result<(), DBError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| DBError::write(parent, e))?;
+        }
+
+        let mut lines = String::new();
+        for e in entries {
+            let line = serde_json::to_string(e)
+                .map_err(|e| DBError::write(&self.path, e))?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| DBError::write(&self.path, e))?;
+        file.write_all(lines.as_bytes())
+            .map_err(|e| DBError::write(&self.path, e))
+    }
+
+    pub fn read_all(&self) -> Result<Vec<AuditEntry>, DBError> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| DBError::read(&self.path, e))?;
+
+        content.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| serde_json::from_str(line)
+                .map_err(|e| DBError::read(&self.path, e).at_line(i as u64 + 1)))
+            .collect()
+    }
+
+    /// Entries about task `id`, or all, made at or after `since`.
+    pub fn history(&self, id: Option<u32>, since: Option<i64>) -> Result<Vec<AuditEntry>, DBError> {
+        Ok(self.read_all()?
+            .into_iter()
+            .filter(|e| id.map(|id| e.id == id).unwrap_or(true))
+            .filter(|e| since.map(|s| e.at >= s).unwrap_or(true))
+            .collect())
+    }
+}
+
+/// One line per entry, e.g. `2026-10-19 09:30  alice  update #3  priority: low -> high`.
+pub fn format_entry(e: &AuditEntry) -> String {
+    format!(
+        "{}  {}  {} #{}  {}",
+        crate::utils::format_timestamp(e.at, "%Y-%m-%d %H:%M"),
+        e.user,
+        e.operation,
+        e.id,
+        format_changes(e),
+    )
+}
+
+/// The fields an entry changed, deleted tasks show their text only, or
+/// the line a repair dropped.
+pub fn format_changes(e: &AuditEntry) -> String {
+    let deleted = e.changes.iter().all(|c| c.new.is_none());
+    let changes: Vec<String> = e.changes.iter()
+        .filter(|c| !deleted || c.field == "task" || c.field == "line")
+        .map(|c| {
+            let (old, new) = (show(&c.field, &c.old), show(&c.field, &c.new));
+            match (&c.old, &c.new) {
+                (None, Some(_)) => format!("{}: {}", c.field, new),
+                (Some(_), None) => format!("{}: {} (deleted)", c.field, old),
+                _ => format!("{}: {} -> {}", c.field, old, new),
+            }
+        })
+        .collect();
+
+    changes.join(", ")
+}
+
+fn show(field: &str, value: &Option<String>) -> String {
+    let timestamp = ["due", "created", "completed_at", "snoozed_until"].contains(&field);
+
+    match value.as_deref() {
+        None | Some("") => "-".to_string(),
+        Some(v) if timestamp => v.parse().map(format_due).unwrap_or_else(|_| v.to_string()),
+        Some(v) => v.to_string(),
+    }
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+
+use crate::models::DBError;
+
+
+/// How many backups are kept without `$TODO_BACKUPS`.
+pub const DEFAULT_KEEP: usize = 10;
+
+/// A copy of the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    /// File name, e.g. `20261019-093000.123-delete_completed.csv`.
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Copies of the database in a directory of their own, named by when and
+/// why they were made. Making one removes all but the newest `keep`.
+#[derive(Debug, Clone)]
+pub struct Backups {
+    dir: PathBuf,
+    keep: usize,
+}
+
+impl Backups {
+    pub fn new(dir: PathBuf, keep: usize) -> Self {
+        Backups { dir, keep }
+    }
+
+    /// `$TODO_BACKUPS`, else `DEFAULT_KEEP`. 0 turns automatic backups off
+    /// and keeps every backup made by hand.
+    pub fn keep_from_env() -> usize {
+        std::env::var("TODO_BACKUPS").ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_KEEP)
+    }
+
+    pub fn keep(&self) -> usize {
+        self.keep
+    }
+
+    /// Copies the database at `db` before `operation`, `None` when there is
+    /// no database to copy.
+    pub fn create(&self, db: &Path, operation: &str, now: NaiveDateTime) -> Result<Option<Backup>, DBError> {
+        if !db.exists() {
+            return Ok(None);
+        }
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| DBError::write(&self.dir, e))?;
+
+        let extension = db.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+        let stamp = |at: NaiveDateTime| at.format("%Y%m%d-%H%M%S%.3f").to_string();
+        let existing = self.list()?;
+        // a later millisecond for operations within the same one, so the
+        // names stay in order
+        let mut at = now;
+        while existing.iter().any(|b| b.name.starts_with(&stamp(at))) {
+            at += chrono::Duration::milliseconds(1);
+        }
+        let name = format!("{}-{}{}", stamp(at), operation, extension);
+
+        let path = self.dir.join(&name);
+        fs::copy(db, &path)
+            .map_err(|e| DBError::write(&path, e))?;
+        self.prune()?;
+
+        Ok(Some(Backup { name, path }))
+    }
+
+    /// The backups, oldest first.
+    pub fn list(&self) -> Result<Vec<Backup>, DBError> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut backups: Vec<Backup> = vec![];
+        for entry in fs::read_dir(&self.dir).map_err(|e| DBError::read(&self.dir, e))? {
+            let path = entry.map_err(|e| DBError::read(&self.dir, e))?.path();
+            if path.is_file() {
+                let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                backups.push(Backup { name, path });
+            }
+        }
+        // the names start with when the backup was made
+        backups.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(backups)
+    }
+
+    pub fn find(&self, name: &str) -> Result<Option<Backup>, DBError> {
+        Ok(self.list()?.into_iter().find(|b| b.name == name))
+    }
+
+    fn prune(&self) -> Result<(), DBError> {
+        if self.keep == 0 {
+            return Ok(());
+        }
+
+        let backups = self.list()?;
+        let old = backups.len().saturating_sub(self.keep);
+        for b in &backups[..old] {
+            fs::remove_file(&b.path)
+                .map_err(|e| DBError::write(&b.path, e))?;
+        }
+
+        Ok(())
+    }
+}
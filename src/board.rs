@@ -0,0 +1,158 @@
+use std::collections::BTreeSet;
+
+use crate::dates::format_due;
+use crate::models::{DBRow, Priority, Status};
+
+
+/// Narrowest lane, more lanes than fit side by side wrap to a new band.
+pub const MIN_LANE_WIDTH: usize = 20;
+const SEPARATOR: &str = " | ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    Status,
+    Tag,
+    Priority,
+}
+
+/// One column of the board.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lane {
+    pub title: String,
+    pub cards: Vec<DBRow>,
+}
+
+/// Sorts `rows` into lanes. By status there is a lane for todo, in
+/// progress, waiting and done, cancelled tasks are left out. By tag a task
+/// shows up under each of its tags, by priority the highest comes first.
+/// Cards are ordered by priority, then due date.
+pub fn lanes(rows: &[DBRow], group_by: GroupBy) -> Vec<Lane> {
+    let lane = |title: &str, keep: &dyn Fn(&DBRow) -> bool| {
+        let mut cards: Vec<DBRow> = rows.iter().filter(|r| keep(r)).cloned().collect();
+        cards.sort_by_key(|r| (std::cmp::Reverse(r.priority), r.due.is_none(), r.due, r.id));
+        Lane { title: title.to_string(), cards }
+    };
+
+    match group_by {
+        GroupBy::Status => [
+            ("Todo", Status::Todo),
+            ("In progress", Status::InProgress),
+            ("Waiting", Status::Waiting),
+            ("Done", Status::Done),
+        ].iter()
+            .map(|(title, status)| lane(title, &|r| r.current_status() == *status))
+            .collect(),
+        GroupBy::Tag => {
+            let tags: BTreeSet<&String> = rows.iter().flat_map(|r| &r.tags).collect();
+            let mut lanes: Vec<Lane> = tags.iter()
+                .map(|t| lane(&format!("+{}", t), &|r| r.tags.contains(*t)))
+                .collect();
+            if rows.iter().any(|r| r.tags.is_empty()) {
+                lanes.push(lane("(no tag)", &|r| r.tags.is_empty()));
+            }
+            lanes
+        }
+        GroupBy::Priority => [
+            ("High", Some(Priority::High)),
+            ("Medium", Some(Priority::Medium)),
+            ("Low", Some(Priority::Low)),
+            ("None", None),
+        ].iter()
+            .map(|(title, priority)| lane(title, &|r| r.priority == *priority))
+            .collect(),
+    }
+}
+
+/// Draws the lanes side by side within `width` characters.
+pub fn render(lanes: &[Lane], width: usize) -> String {
+    if lanes.is_empty() {
+        return "No tasks to show.\n".to_string();
+    }
+
+    let per_band = ((width + SEPARATOR.len()) / (MIN_LANE_WIDTH + SEPARATOR.len())).clamp(1, lanes.len());
+    let lane_width = (width.saturating_sub(SEPARATOR.len() * (per_band - 1)) / per_band).max(MIN_LANE_WIDTH);
+
+    let mut out = String::new();
+    for (i, band) in lanes.chunks(per_band).enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+
+        let columns: Vec<Vec<String>> = band.iter().map(|l| lane_lines(l, lane_width)).collect();
+        let height = columns.iter().map(|c| c.len()).max().unwrap_or(0);
+        for row in 0..height {
+            let cells: Vec<String> = columns.iter()
+                .map(|c| pad(c.get(row).map(|s| s.as_str()).unwrap_or(""), lane_width))
+                .collect();
+            out.push_str(cells.join(SEPARATOR).trim_end());
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+fn lane_lines(lane: &Lane, width: usize) -> Vec<String> {
+    let title = format!("{} ({})", lane.title, lane.cards.len());
+    let mut lines = wrap(&title, width);
+    lines.push("-".repeat(width));
+
+    for (i, r) in lane.cards.iter().enumerate() {
+        if i > 0 {
+            lines.push(String::new());
+        }
+        lines.extend(wrap(&card_text(r), width));
+    }
+
+    lines
+}
+
+fn card_text(r: &DBRow) -> String {
+    let mut text = format!("#{} {}", r.id, r.task);
+    for t in &r.tags {
+        text.push_str(&format!(" +{}", t));
+    }
+    if let Some(p) = r.priority {
+        text.push_str(&format!(" !{}", p));
+    }
+    if let Some(due) = r.due {
+        text.push_str(&format!(" due:{}", format_due(due)));
+    }
+
+    text
+}
+
+/// Breaks `text` into lines of at most `width` characters at spaces, words
+/// that are longer are split.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        let used = line.chars().count();
+        if used > 0 && used + 1 + word.len() <= width {
+            line.push(' ');
+            line.extend(&word);
+            continue;
+        }
+
+        if used > 0 {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > width {
+            lines.push(word.drain(..width).collect());
+        }
+        line.extend(&word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+fn pad(value: &str, width: usize) -> String {
+    let len = value.chars().count();
+    format!("{}{}", value, " ".repeat(width.saturating_sub(len)))
+}
@@ -0,0 +1,256 @@
+use std::fmt;
+
+use chrono::Local;
+
+use crate::models::{DBError, DBReader, DBRow, DBWriter, Status};
+use crate::query::{Condition, Query};
+use crate::workflow::Workflow;
+
+
+/// List of IDs given on the command line, e.g. `1,4,7-12`, as sorted
+/// ranges that don't overlap. A single ID is a range of one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdList(pub Vec<(u32, u32)>);
+
+impl IdList {
+    /// Sorts `ranges` and merges those that overlap or touch.
+    fn from_ranges(mut ranges: Vec<(u32, u32)>) -> Self {
+        ranges.sort();
+
+        let mut merged: Vec<(u32, u32)> = vec![];
+        for (from, to) in ranges {
+            match merged.last_mut() {
+                Some(last) if from <= last.1.saturating_add(1) => last.1 = last.1.max(to),
+                _ => merged.push((from, to)),
+            }
+        }
+
+        IdList(merged)
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.0.iter().any(|(from, to)| (*from..=*to).contains(&id))
+    }
+
+    /// The IDs in the list, with ranges cut at `max`, usually the largest
+    /// ID in use. Each range keeps its first ID.
+    pub fn ids_up_to(&self, max: u32) -> Vec<u32> {
+        self.0.iter()
+            .flat_map(|(from, to)| *from..=(*to).min(max.max(*from)))
+            .collect()
+    }
+}
+
+impl From<Vec<u32>> for IdList {
+    fn from(ids: Vec<u32>) -> Self {
+        IdList::from_ranges(ids.into_iter().map(|id| (id, id)).collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkAction {
+    Delete,
+    Complete,
+    /// Reopens done and cancelled tasks.
+    Uncomplete,
+    SetStatus(Status),
+}
+
+impl BulkAction {
+    /// The name the change is logged and backed up under.
+    pub fn operation(&self) -> &'static str {
+        match self {
+            BulkAction::Delete => "delete",
+            BulkAction::Complete => "complete",
+            BulkAction::Uncomplete => "uncomplete",
+            BulkAction::SetStatus(_) => "set_status",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkOutcome {
+    Deleted,
+    Completed,
+    Uncompleted,
+    Status(Status),
+    /// Completed a recurring task, the next one has this ID.
+    Recurred(u32),
+    Unchanged,
+    /// The workflow doesn't allow the change from this status.
+    NotAllowed(Status),
+    NotFound,
+}
+
+/// Rows an operation applies to, IDs, tag and filter are combined with AND.
+#[derive(Debug, Default)]
+pub struct Selection {
+    pub ids: Option<IdList>,
+    pub tag: Option<String>,
+    pub filter: Option<Query>,
+}
+
+impl fmt::Display for BulkOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BulkOutcome::Deleted => write!(f, "deleted"),
+            BulkOutcome::Completed => write!(f, "completed"),
+            BulkOutcome::Uncompleted => write!(f, "uncompleted"),
+            BulkOutcome::Status(s) => write!(f, "{}", s),
+            BulkOutcome::Recurred(id) => write!(f, "completed, next is #{}", id),
+            BulkOutcome::Unchanged => write!(f, "unchanged"),
+            BulkOutcome::NotAllowed(s) => write!(f, "not allowed from {}", s),
+            BulkOutcome::NotFound => write!(f, "not found"),
+        }
+    }
+}
+
+pub fn parse_id_list(value: &str) -> Result<IdList, String> {
+    let mut ranges: Vec<(u32, u32)> = vec![];
+
+    for part in value.split(',').map(|p| p.trim()) {
+        if part.is_empty() {
+            return Err(format!("empty entry in ID list '{}'", value));
+        }
+
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let from = parse_id(from)?;
+                let to = parse_id(to)?;
+                if from > to {
+                    return Err(format!("range '{}' is reversed", part));
+                }
+                ranges.push((from, to));
+            }
+            None => {
+                let id = parse_id(part)?;
+                ranges.push((id, id));
+            }
+        }
+    }
+
+    Ok(IdList::from_ranges(ranges))
+}
+
+fn parse_id(value: &str) -> Result<u32, String> {
+    value.trim().parse::<u32>()
+        .map_err(|_| format!("'{}' is not a valid ID", value.trim()))
+}
+
+impl Selection {
+    pub fn matches(&self, r: &DBRow) -> bool {
+        if let Some(ids) = &self.ids {
+            if !ids.contains(r.id) { return false }
+        }
+        if let Some(tag) = &self.tag {
+            // as `tag:` in a filter, ignoring case
+            if !Condition::Tag(tag.clone()).matches(r) { return false }
+        }
+        if let Some(filter) = &self.filter {
+            if !filter.matches(r) { return false }
+        }
+
+        true
+    }
+}
+
+/// Applies `action` to every selected row and stores the result in one rewrite.
+/// Status changes the workflow refuses leave the row as it is. Completing a
+/// recurring task adds its next occurrence. Returns an
+/// outcome for every requested ID, or for every matched row when the
+/// selection has no explicit IDs.
+pub fn apply<T: DBReader + DBWriter>(
+    repo: &T,
+    selection: &Selection,
+    action: BulkAction,
+    workflow: &Workflow,
+) -> Result<Vec<(u32, BulkOutcome)>, DBError> {
+    let rows = repo.read_all()?;
+    let now = Local::now().timestamp();
+    let max_id = rows.iter().map(|r| r.id).max().unwrap_or(0);
+    // none left past the largest ID, only a recurring task needs one
+    let mut next_id = max_id.checked_add(1);
+
+    let mut outcomes: Vec<(u32, BulkOutcome)> = vec![];
+    let mut kept: Vec<DBRow> = vec![];
+    let mut changed = false;
+
+    for mut r in rows {
+        if !selection.matches(&r) {
+            kept.push(r);
+            continue;
+        }
+
+        let current = r.current_status();
+        let (status, outcome) = match action {
+            BulkAction::Delete => (current, BulkOutcome::Deleted),
+            BulkAction::Complete => (Status::Done, BulkOutcome::Completed),
+            BulkAction::Uncomplete if current.is_open() => (current, BulkOutcome::Unchanged),
+            BulkAction::Uncomplete => (Status::Todo, BulkOutcome::Uncompleted),
+            BulkAction::SetStatus(s) => (s, BulkOutcome::Status(s)),
+        };
+        let outcome = if outcome == BulkOutcome::Deleted {
+            outcome
+        } else if status == current {
+            BulkOutcome::Unchanged
+        } else if !workflow.allows(current, status) {
+            BulkOutcome::NotAllowed(current)
+        } else {
+            outcome
+        };
+        let next = match (outcome, status) {
+            (BulkOutcome::Completed | BulkOutcome::Status(Status::Done), Status::Done) if r.recurrence.is_some() && r.due.is_some() => {
+                let id = next_id.ok_or_else(|| DBError::new_write_error(
+                    &format!("no ID left for the next occurrence of #{}", r.id),
+                ))?;
+                r.next_occurrence(id, now)
+            },
+            _ => None,
+        };
+        outcomes.push((r.id, next.as_ref().map(|n| BulkOutcome::Recurred(n.id)).unwrap_or(outcome)));
+
+        match outcome {
+            BulkOutcome::Deleted => changed = true,
+            BulkOutcome::Completed | BulkOutcome::Uncompleted | BulkOutcome::Status(_) => {
+                r.set_status(status, now);
+                changed = true;
+                kept.push(r);
+            }
+            _ => kept.push(r),
+        }
+        if let Some(n) = next {
+            next_id = n.id.checked_add(1);
+            kept.push(n);
+        }
+    }
+
+    // IDs past the largest in use can't be found, a range is only reported
+    // up to there
+    if let Some(ids) = &selection.ids {
+        for id in ids.ids_up_to(max_id) {
+            if !outcomes.iter().any(|(o, _)| *o == id) {
+                outcomes.push((id, BulkOutcome::NotFound));
+            }
+        }
+    }
+
+    if changed {
+        repo.write_all_as(&kept, action.operation())?;
+    }
+
+    outcomes.sort_by_key(|(id, _)| *id);
+
+    Ok(outcomes)
+}
+
+pub fn print_summary(outcomes: &[(u32, BulkOutcome)]) {
+    println!();
+    for (id, outcome) in outcomes {
+        println!("{:>4}\t{}", id, outcome);
+    }
+
+    let changed = outcomes.iter()
+        .filter(|(_, o)| !matches!(o, BulkOutcome::Unchanged | BulkOutcome::NotAllowed(_) | BulkOutcome::NotFound))
+        .count();
+    println!("\n{} of {} rows changed.\n", changed, outcomes.len());
+}
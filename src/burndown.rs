@@ -0,0 +1,125 @@
+use chrono::NaiveDate;
+
+use crate::dates::date_to_timestamp;
+use crate::models::{DBError, DBRow, Status};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BurndownFormat {
+    /// ASCII chart
+    Text,
+    Csv,
+    Json,
+}
+
+/// Estimated work at the end of one day.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct BurndownPoint {
+    pub date: NaiveDate,
+    /// Estimate of all tasks created so far, wider than an estimate so
+    /// the sum fits.
+    pub total: u64,
+    /// Estimate of the tasks still open.
+    pub remaining: u64,
+    /// Remaining work if it went down evenly from the first day to zero.
+    pub ideal: f64,
+}
+
+/// Daily remaining work from `from` to `to`, both included. Tasks count from
+/// the day they were created until the day they were completed, weighted by
+/// their estimate. Tasks without an estimate and cancelled tasks are not
+/// counted.
+pub fn burndown(rows: &[DBRow], from: NaiveDate, to: NaiveDate) -> Vec<BurndownPoint> {
+    // `iter_days` stops short of the last date
+    let days: Vec<NaiveDate> = std::iter::successors(Some(from), |d| d.succ_opt())
+        .take_while(|d| *d <= to)
+        .collect();
+
+    let mut points: Vec<BurndownPoint> = days.iter()
+        .map(|day| {
+            let end = day.succ_opt().map(date_to_timestamp).unwrap_or(i64::MAX);
+            let mut point = BurndownPoint { date: *day, total: 0, remaining: 0, ideal: 0.0 };
+
+            for r in rows.iter().filter(|r| r.created_at() < end && r.current_status() != Status::Cancelled) {
+                let estimate = r.estimate.unwrap_or(0) as u64;
+                point.total += estimate;
+                if r.done_at().map(|d| d >= end).unwrap_or(true) {
+                    point.remaining += estimate;
+                }
+            }
+
+            point
+        })
+        .collect();
+
+    let start = points.first().map(|p| p.remaining as f64).unwrap_or(0.0);
+    let steps = (points.len().max(2) - 1) as f64;
+    for (i, p) in points.iter_mut().enumerate() {
+        p.ideal = start - start * i as f64 / steps;
+    }
+
+    points
+}
+
+/// Bar chart of the remaining work per day, `.` marks the ideal line.
+pub fn render_chart(points: &[BurndownPoint], height: usize) -> String {
+    let max = points.iter()
+        .map(|p| (p.remaining as f64).max(p.ideal))
+        .fold(0.0, f64::max);
+    if points.is_empty() || max == 0.0 {
+        return "No estimated work in this period.\n".to_string();
+    }
+
+    let width = max.ceil().to_string().len();
+    let mut out = String::new();
+
+    for row in (1..=height).rev() {
+        let level = max * row as f64 / height as f64;
+        let label = if row == height || row == height / 2 {
+            format!("{:.0}", level)
+        } else {
+            String::new()
+        };
+        out.push_str(&format!("{:>width$} |", label, width = width));
+
+        let step = max / height as f64;
+        for p in points {
+            let cell = if p.remaining as f64 >= level - step / 2.0 {
+                "#"
+            } else if (p.ideal - level).abs() < step / 2.0 {
+                "."
+            } else {
+                " "
+            };
+            out.push(' ');
+            out.push_str(cell);
+        }
+        out.push('\n');
+    }
+
+    out.push_str(&format!("{:>width$} +{}\n", 0, "-".repeat(points.len() * 2), width = width));
+
+    let first = points[0].date.format("%m-%d").to_string();
+    let last = points[points.len() - 1].date.format("%m-%d").to_string();
+    let gap = (points.len() * 2).saturating_sub(first.len() + last.len()).max(1);
+    out.push_str(&format!("{:>width$}  {}{}{}\n", "", first, " ".repeat(gap), last, width = width));
+
+    out
+}
+
+pub fn to_csv(points: &[BurndownPoint]) -> String {
+    let mut out = String::from("date,total,remaining,ideal\n");
+    for p in points {
+        out.push_str(&format!("{},{},{},{:.2}\n", p.date, p.total, p.remaining, p.ideal));
+    }
+
+    out
+}
+
+pub fn to_json(points: &[BurndownPoint]) -> Result<String, DBError> {
+    let mut out = serde_json::to_string_pretty(points)
+        .map_err(|e| DBError::new_write_error(&e.to_string()))?;
+    out.push('\n');
+
+    Ok(out)
+}
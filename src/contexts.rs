@@ -0,0 +1,97 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveDate;
+
+use crate::models::{DBRow, Status};
+
+
+/// Open and next action counts of one context.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextCount {
+    pub open: usize,
+    pub next: usize,
+}
+
+/// Strips the `@` a context is written with on the command line.
+pub fn normalize(context: &str) -> String {
+    context.trim_start_matches('@').to_lowercase()
+}
+
+/// Whether one of the tasks `r` depends on is still open. Dependencies
+/// that were deleted don't block.
+pub fn is_blocked(r: &DBRow, rows: &[DBRow]) -> bool {
+    r.depends_on.iter()
+        .any(|id| rows.iter().any(|d| d.id == *id && d.current_status().is_open()))
+}
+
+/// Tasks that can be picked up now: open and not waiting, not blocked and
+/// not snoozed, within `context` when given. Highest priority first, then
+/// earliest due date.
+pub fn next_actions(rows: &[DBRow], context: Option<&str>, today: NaiveDate) -> Vec<DBRow> {
+    let context = context.map(normalize);
+
+    let mut next: Vec<DBRow> = rows.iter()
+        .filter(|r| is_next(r, rows, today))
+        .filter(|r| context.as_ref().map(|c| r.contexts.contains(c)).unwrap_or(true))
+        .cloned()
+        .collect();
+    next.sort_by_key(|r| (std::cmp::Reverse(r.priority), r.due.is_none(), r.due, r.id));
+
+    next
+}
+
+fn is_next(r: &DBRow, rows: &[DBRow], today: NaiveDate) -> bool {
+    let status = r.current_status();
+
+    status.is_open() && status != Status::Waiting && !r.is_snoozed(today) && !is_blocked(r, rows)
+}
+
+/// Open tasks and next actions per context, by name.
+pub fn context_counts(rows: &[DBRow], today: NaiveDate) -> BTreeMap<String, ContextCount> {
+    let mut counts: BTreeMap<String, ContextCount> = BTreeMap::new();
+
+    for r in rows.iter().filter(|r| r.current_status().is_open()) {
+        let next = is_next(r, rows, today);
+        for c in &r.contexts {
+            let count = counts.entry(c.clone()).or_default();
+            count.open += 1;
+            if next {
+                count.next += 1;
+            }
+        }
+    }
+
+    counts
+}
+
+/// Checks that `r` may depend on `ids`: they exist, and none of them is
+/// `r` itself or depends on it, directly or through others.
+pub fn check_dependencies(r: &DBRow, ids: &[u32], rows: &[DBRow]) -> Result<(), String> {
+    let by_id: HashMap<u32, &DBRow> = rows.iter().map(|row| (row.id, row)).collect();
+
+    for id in ids {
+        if *id == r.id {
+            return Err(format!("#{} cannot depend on itself", r.id));
+        }
+        if !by_id.contains_key(id) {
+            return Err(format!("task #{} was not found", id));
+        }
+
+        let mut pending = vec![*id];
+        let mut seen: Vec<u32> = vec![];
+        while let Some(current) = pending.pop() {
+            if current == r.id {
+                return Err(format!("#{} already depends on #{}", id, r.id));
+            }
+            if seen.contains(&current) {
+                continue;
+            }
+            seen.push(current);
+            if let Some(row) = by_id.get(&current) {
+                pending.extend(&row.depends_on);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use chrono::{Datelike, Days, Local, NaiveDate, NaiveDateTime, TimeZone, Weekday};
+
+use crate::utils::unix_to_datetime;
+
+
+/// Parses a date as typed on the command line: `YYYY-MM-DD`, `today`,
+/// `tomorrow`, `yesterday`, an offset such as `3d` or `2w`, or a weekday
+/// such as `fri`, meaning the next one after today. Relative forms are
+/// resolved against `today`.
+pub fn parse_date(value: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let invalid = || format!("'{}' is not a valid date, expected YYYY-MM-DD, a weekday or e.g. 3d", value);
+
+    match value.to_lowercase().as_str() {
+        "today" => Ok(today),
+        "tomorrow" => Ok(today + Days::new(1)),
+        "yesterday" => Ok(today - Days::new(1)),
+        v => {
+            if let Ok(weekday) = v.parse::<Weekday>() {
+                let ahead = (weekday.num_days_from_monday() + 6 - today.weekday().num_days_from_monday()) % 7 + 1;
+                return Ok(today + Days::new(ahead as u64));
+            }
+
+            let offset = v.strip_prefix('+').unwrap_or(v);
+            let days = match offset.chars().last() {
+                Some('d') => 1,
+                Some('w') => 7,
+                _ => return NaiveDate::parse_from_str(v, "%Y-%m-%d").map_err(|_| invalid()),
+            };
+            let n: u64 = offset[..offset.len() - 1].parse().map_err(|_| invalid())?;
+            n.checked_mul(days)
+                .and_then(|d| today.checked_add_days(Days::new(d)))
+                .ok_or_else(invalid)
+        }
+    }
+}
+
+/// Local midnight of `date` as a unix timestamp.
+pub fn date_to_timestamp(date: NaiveDate) -> i64 {
+    local_timestamp(date.and_hms_opt(0, 0, 0).unwrap())
+}
+
+pub fn local_timestamp(dt: NaiveDateTime) -> i64 {
+    Local.from_local_datetime(&dt)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&dt))
+        .timestamp()
+}
+
+/// Local calendar date of a unix timestamp.
+pub fn timestamp_to_date(timestamp: i64) -> NaiveDate {
+    match unix_to_datetime(timestamp) {
+        Some(dt) => dt.date_naive(),
+        None if timestamp < 0 => NaiveDate::MIN,
+        None => NaiveDate::MAX,
+    }
+}
+
+/// Formats a due timestamp, the time is left out when it is midnight.
+pub fn format_due(timestamp: i64) -> String {
+    let dt = match unix_to_datetime(timestamp) {
+        Some(dt) => dt,
+        None => return timestamp.to_string(),
+    };
+    if dt.format("%H:%M").to_string() == "00:00" {
+        dt.format("%Y-%m-%d").to_string()
+    } else {
+        dt.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
@@ -1,23 +1,158 @@
+use std::collections::HashMap;
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 
+use chrono::Local;
 
-use crate::models::{DBError, DBPrinter, DBReader, DBRow, DBWriter};
-use crate::utils::unix_to_datetime;
+use crate::audit::{self, AuditLog};
+use crate::backup::{Backup, Backups};
+use crate::dates::format_due;
+use crate::doctor::{check_fields, Diagnosis, Problem, ProblemKind};
+use crate::models::{Column, DBError, ErrorContext, DBPrinter, DBReader, DBRow, DBWriter, Priority, Recurrence, Status, DEFAULT_COLUMNS};
+use crate::timelog::format_duration;
+use crate::utils::format_timestamp;
+
+
+const HEADER: [&str; 16] = [
+    "id", "updatedate", "task", "completed", "tags", "due", "priority", "estimate", "created", "completed_at",
+    "snoozed_until", "status", "contexts", "recurrence", "depends_on", "notes",
+];
 
 
 pub struct DBCSV {
     path: PathBuf,
+    audit: Option<AuditLog>,
+    backups: Option<Backups>,
+}
+
+/// On-disk shape of a row, list fields are stored joined by a comma.
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct CsvRow {
+    id: u32,
+    updatedate: i64,
+    task: String,
+    completed: bool,
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    due: Option<i64>,
+    #[serde(default)]
+    priority: String,
+    #[serde(default)]
+    estimate: Option<u32>,
+    #[serde(default)]
+    created: Option<i64>,
+    #[serde(default)]
+    completed_at: Option<i64>,
+    #[serde(default)]
+    snoozed_until: Option<i64>,
+    /// Empty in files written before statuses, `completed` tells then.
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    contexts: String,
+    #[serde(default)]
+    recurrence: String,
+    #[serde(default)]
+    depends_on: String,
+    #[serde(default)]
+    notes: String,
+}
+
+impl CsvRow {
+    fn is_done(&self) -> bool {
+        match self.status.as_str() {
+            "" => self.completed,
+            s => s.parse::<Status>() == Ok(Status::Done),
+        }
+    }
+}
+
+impl TryFrom<CsvRow> for DBRow {
+    type Error = String;
+
+    fn try_from(r: CsvRow) -> Result<Self, Self::Error> {
+        let priority = match r.priority.as_str() {
+            "" => None,
+            p => Some(p.parse::<Priority>()?),
+        };
+        let status = match r.status.as_str() {
+            "" => Status::Todo.with_completed(r.completed),
+            s => s.parse::<Status>()?,
+        };
+        let recurrence = match r.recurrence.as_str() {
+            "" => None,
+            s => Some(s.parse::<Recurrence>()?),
+        };
+        let depends_on = split_list(&r.depends_on).iter()
+            .map(|id| id.parse::<u32>().map_err(|_| format!("'{}' is not a valid ID", id)))
+            .collect::<Result<Vec<u32>, String>>()?;
+
+        Ok(DBRow {
+            id: r.id,
+            updatedate: r.updatedate,
+            task: r.task,
+            completed: status == Status::Done,
+            status,
+            tags: split_list(&r.tags),
+            contexts: split_list(&r.contexts),
+            due: r.due,
+            priority,
+            estimate: r.estimate,
+            created: r.created,
+            completed_at: r.completed_at,
+            snoozed_until: r.snoozed_until,
+            recurrence,
+            depends_on,
+            notes: r.notes,
+            tracked: None,
+        })
+    }
+}
+
+impl From<&DBRow> for CsvRow {
+    fn from(r: &DBRow) -> Self {
+        CsvRow {
+            id: r.id,
+            updatedate: r.updatedate,
+            task: r.task.clone(),
+            completed: r.completed,
+            tags: r.tags.join(","),
+            due: r.due,
+            priority: r.priority.map(|p| p.to_string()).unwrap_or_default(),
+            estimate: r.estimate,
+            created: r.created,
+            completed_at: r.completed_at,
+            snoozed_until: r.snoozed_until,
+            status: r.current_status().to_string(),
+            contexts: r.contexts.join(","),
+            recurrence: r.recurrence.map(|rec| rec.to_string()).unwrap_or_default(),
+            depends_on: r.depends_on.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(","),
+            notes: r.notes.clone(),
+        }
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',')
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string())
+        .collect()
 }
 
 
 impl DBReader for DBCSV {
     fn read_all(&self) -> Result<Vec<DBRow>, DBError> {
-        let mut reader = self.get_reader()?;
-    
-        let mut data = reader.deserialize()
-            .collect::<Result<Vec<DBRow>, csv::Error>>()
-            .map_err(|e| DBError::new_read_error(&e.to_string()))?;
+        let records = self.read_records()?;
+        self.check_unique(records.iter().map(|(line, r)| (*line, r.id)))?;
+
+        let mut data = records.into_iter()
+            .map(|(line, r)| {
+                let id = r.id;
+                DBRow::try_from(r).map_err(|e| self.corrupt_row(Some(line), Some(id), e))
+            })
+            .collect::<Result<Vec<DBRow>, DBError>>()?;
 
         data.sort();
 
@@ -25,36 +160,21 @@ impl DBReader for DBCSV {
     }
 
     fn read_one(&self, id: u32) -> Result<Option<DBRow>, DBError> {
-        let mut reader = self.get_reader()?;
-
-        for row in reader.deserialize() {
-            let record: DBRow = row
-                .map_err(|e| DBError::new_read_error(&e.to_string()))?;
-            
-            if record.id == id {
-                return Ok(Some(record))
-            }
+        match self.read_records()?.into_iter().find(|(_, r)| r.id == id) {
+            Some((line, record)) => DBRow::try_from(record)
+                .map(Some)
+                .map_err(|e| self.corrupt_row(Some(line), Some(id), e)),
+            None => Ok(None),
         }
-
-        Ok(None)
     }
 
+    /// The first ID not used on any line, also the lines that don't load.
     fn get_next_id(&self) -> u32 {
-        let mut reader = match self.get_reader() {
-            Ok(r) => r,
+        let existing = match self.read_lenient() {
+            Ok(d) => d.used_ids(),
             Err(_) => return 1,
         };
 
-        // gather all existing IDs
-        let mut existing: Vec<u32> = vec![];
-        for result in reader.deserialize() {
-            let record: DBRow = match result {
-                Ok(v) => v,
-                Err(_) => continue,
-            };
-            existing.push(record.id);
-        }
-
         for i in 1..1000 {
             if !existing.contains(&i) {
                 return i;
@@ -67,179 +187,391 @@ impl DBReader for DBCSV {
 
 impl DBWriter for DBCSV {
     fn add(&self, r: &DBRow) -> Result<(), crate::models::DBError> {
-        if self.db_is_empty() { 
-            self.create_db()?;
-        }
-
-        let mut writer = match self.get_writer(true) {
-            Ok(r) => r,
-            Err(e) => panic!("{}", e),
-        };
-
-        writer.serialize(r)
-            .map_err(|e| DBError::new_write_error(&e.to_string()))?;
-
-        writer.flush()
-            .map_err(|e| DBError::new_write_error(&e.to_string()))?;
-
-        Ok(())
+        self.audited("add", || self.append_row(r))
     }
 
     fn create_db(&self) -> Result<(), DBError> {
-        fs::create_dir_all(self.path.parent().unwrap())
-            .map_err(|_| DBError::new_write_error("cannot create folder structure for DB"))?;
+        fs::create_dir_all(self.path.parent().unwrap_or(Path::new(".")))
+            .map_err(|e| self.write_error(e))?;
 
         File::create(&self.path)
-            .map_err(|_| DBError::new_write_error("cannot create folder structure for DB"))?;
+            .map_err(|e| self.write_error(e))?;
 
         let mut writer = self.get_writer(false)?;
 
-        let header = vec!["id", "updatedate", "task", "completed"];
-        writer.write_record(&header)
-            .map_err(|e| DBError::new_write_error(&e.to_string()))?;
+        writer.write_record(HEADER)
+            .map_err(|e| self.write_error(e))?;
 
         writer.flush()
-            .map_err(|e| DBError::new_write_error(&e.to_string()))?;
+            .map_err(|e| self.write_error(e))?;
         
         Ok(())
     }
     
     fn delete(&self, id: u32) -> Result<(), DBError> {
-        // get reader and writer to the temp file
-        let mut reader = self.get_reader()?;
-        let (mut temp_writer, temp_path) = self.get_temp_writer()?;
-
-        // work
-        let mut removed  = 0;
-        for result in reader.deserialize() {
-            let record: DBRow = result
-                .map_err(|e| DBError::new_write_error(&e.to_string()))?;
-
-            if record.id != id {
-                temp_writer.serialize(record)
-                    .map_err(|e| DBError::new_write_error(&e.to_string()))?;
-            } else {
-                removed += 1;
-            }
-        }
-
-        temp_writer.flush()
-            .map_err(|e| DBError::new_write_error(&e.to_string()))?;
-        
-        // rename temp
-        fs::remove_file(&self.path)
-            .map_err(|e| DBError::new_write_error(&e.to_string()))?;
-        fs::rename(temp_path, &self.path)
-            .map_err(|e| DBError::new_write_error(&e.to_string()))?;
-
-        if removed == 0 {
-            return Err(DBError::new_idnotfound_error())
-        }
-
-        Ok(())
+        self.audited("delete", || self.remove_rows("delete", |r| r.id == id).and_then(|removed| match removed {
+            0 => Err(DBError::new_idnotfound_error()),
+            _ => Ok(()),
+        }))
     }
     
-    fn mark_completion(&self, id: u32, complete: bool) -> Result<(), DBError> {
-        match self.read_one(id)? {
-            Some(mut v) => {
-                v.completed = complete;
-
-                self.delete(id)?;
-
-                self.add(&v)?;
-            }
-            None => {
-                return Err(DBError::new_idnotfound_error());
+    fn set_status(&self, id: u32, status: Status) -> Result<(), DBError> {
+        self.audited("set_status", || {
+            let mut rows = self.read_all()?;
+            match rows.iter_mut().find(|row| row.id == id) {
+                Some(v) => v.set_status(status, Local::now().timestamp()),
+                None => return Err(DBError::new_idnotfound_error()),
             }
-        };
 
-        Ok(())
+            self.write_rows(&rows)
+        })
     }
     
     fn delete_completed(&self) -> Result<u32, DBError> {
-        let mut reader = self.get_reader()?;
-        let (mut temp_writer, temp_path) = self.get_temp_writer()?;
+        self.audited("delete_completed", || self.remove_rows("delete_completed", |r| r.is_done()))
+    }
 
-        // work
-        let mut removed  = 0;
-        for result in reader.deserialize() {
-            let record: DBRow = result
-                .map_err(|e| DBError::new_write_error(&e.to_string()))?;
+    fn write_all_as(&self, rows: &[DBRow], operation: &str) -> Result<(), DBError> {
+        self.audited(operation, || {
+            self.back_up(operation)?;
+            self.write_rows(rows)
+        })
+    }
 
-            if record.completed == false {
-                temp_writer.serialize(record)
-                    .map_err(|e| DBError::new_write_error(&e.to_string()))?;
-            } else {
-                removed += 1;
-            }
-        }
+    fn update(&self, r: &DBRow) -> Result<(), DBError> {
+        self.audited("update", || {
+            let mut rows = self.read_all()?;
 
-        temp_writer.flush()
-            .map_err(|e| DBError::new_write_error(&e.to_string()))?;
-        
-        // rename temp
-        fs::remove_file(&self.path)
-            .map_err(|e| DBError::new_write_error(&e.to_string()))?;
-        fs::rename(temp_path, &self.path)
-            .map_err(|e| DBError::new_write_error(&e.to_string()))?;
+            match rows.iter_mut().find(|row| row.id == r.id) {
+                Some(row) => *row = r.clone(),
+                None => return Err(DBError::new_idnotfound_error()),
+            }
 
-        Ok(removed)
+            self.write_rows(&rows)
+        })
     }
 }
 
 impl DBPrinter for DBCSV {
     fn print_header(&self) {
-        println!();
-        println!("{:>4}\t{:^5}\t{:20}\t{}", "ID", "State", "Date updated", "Task");
-        println!("{:>4}\t{:^5}\t{:20}\t{}", "--", "-----", "------------", "----");
+        self.print_header_columns(&DEFAULT_COLUMNS);
     }
 
     fn print_row(&self, r: &DBRow) {
-        let done = if r.completed { "[X]" }  else { "[ ]" };
-        let dt = unix_to_datetime(r.updatedate);
-        println!("{:>4}\t{:^5}\t{:20}\t{}", r.id, done, dt.format("%Y-%m-%d %H:%M:%S"), r.task);
+        self.print_row_columns(r, &DEFAULT_COLUMNS);
+    }
+
+    fn print_header_columns(&self, columns: &[Column]) {
+        let titles: Vec<String> = columns.iter()
+            .map(|c| {
+                let title = match c {
+                    Column::Id => "ID",
+                    Column::State => "State",
+                    Column::Updated => "Date updated",
+                    Column::Due => "Due",
+                    Column::Time => "Time",
+                    Column::Estimate => "Est",
+                    Column::Snoozed => "Snoozed",
+                    Column::Task => "Task",
+                };
+                pad_cell(*c, title)
+            })
+            .collect();
+        let rules: Vec<String> = columns.iter()
+            .zip(&titles)
+            .map(|(c, t)| pad_cell(*c, &"-".repeat(t.trim().len())))
+            .collect();
+
+        println!();
+        println!("{}", titles.join("\t"));
+        println!("{}", rules.join("\t"));
+    }
+
+    fn print_row_columns(&self, r: &DBRow, columns: &[Column]) {
+        let cells: Vec<String> = columns.iter()
+            .map(|c| {
+                let value = match c {
+                    Column::Id => r.id.to_string(),
+                    Column::State => r.current_status().marker().to_string(),
+                    Column::Updated => format_timestamp(r.updatedate, "%Y-%m-%d %H:%M:%S"),
+                    Column::Due => r.due.map(format_due).unwrap_or_default(),
+                    Column::Time => r.tracked.map(format_duration).unwrap_or_default(),
+                    Column::Estimate => r.estimate.map(|e| e.to_string()).unwrap_or_default(),
+                    Column::Snoozed => r.snoozed_until.map(format_due).unwrap_or_default(),
+                    Column::Task => {
+                        let tags: String = r.tags.iter().map(|t| format!(" +{}", t)).collect();
+                        let contexts: String = r.contexts.iter().map(|c| format!(" @{}", c)).collect();
+                        let priority = r.priority.map(|p| format!(" !{}", p)).unwrap_or_default();
+                        let recurrence = r.recurrence.map(|rec| format!(" ({})", rec)).unwrap_or_default();
+                        let after: Vec<String> = r.depends_on.iter().map(|d| format!("#{}", d)).collect();
+                        let after = if after.is_empty() { String::new() } else { format!(" (after {})", after.join(", ")) };
+                        format!("{}{}{}{}{}{}", r.task, tags, contexts, priority, recurrence, after)
+                    }
+                };
+                pad_cell(*c, &value)
+            })
+            .collect();
+
+        println!("{}", cells.join("\t"));
+    }
+}
+
+fn pad_cell(column: Column, value: &str) -> String {
+    match column {
+        Column::Id => format!("{:>4}", value),
+        Column::State => format!("{:^5}", value),
+        Column::Updated => format!("{:20}", value),
+        Column::Due => format!("{:10}", value),
+        Column::Time => format!("{:>7}", value),
+        Column::Estimate => format!("{:>4}", value),
+        Column::Snoozed => format!("{:10}", value),
+        Column::Task => value.to_string(),
     }
 }
 
 impl DBCSV {
     pub fn new(path: PathBuf) -> Self {
-        return DBCSV{
-            path: path,
-            
+        DBCSV{
+            path,
+            audit: None,
+            backups: None,
+        }
+    }
+
+    /// Records every change made through `DBWriter` in `audit`.
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Copies the database to `backups` before deleting or rewriting rows.
+    pub fn with_backups(mut self, backups: Backups) -> Self {
+        self.backups = Some(backups);
+        self
+    }
+
+    /// Runs `operation` and logs the rows it changed, when auditing.
+    fn audited<R>(&self, operation: &str, f: impl FnOnce() -> Result<R, DBError>) -> Result<R, DBError> {
+        if self.audit.is_none() {
+            return f();
+        }
+
+        let before = self.rows_or_empty()?;
+        let result = f()?;
+        let after = self.rows_or_empty()?;
+        self.log_changes(operation, &before, &after)?;
+
+        Ok(result)
+    }
+
+    fn log_changes(&self, operation: &str, before: &[DBRow], after: &[DBRow]) -> Result<(), DBError> {
+        match &self.audit {
+            Some(audit) => audit.append(&audit::diff(before, after, operation, &audit.user(), Local::now().timestamp())),
+            None => Ok(()),
+        }
+    }
+
+    /// Makes an automatic backup before `operation`, unless they are off.
+    fn back_up(&self, operation: &str) -> Result<(), DBError> {
+        match &self.backups {
+            Some(b) if b.keep() > 0 => b.create(&self.path, operation, Local::now().naive_local()).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Replaces the database with the copy at `backup` through a temp file,
+    /// as rewrites do. The database it replaces is backed up first.
+    pub fn restore(&self, backup: &Path) -> Result<(), DBError> {
+        // a broken database is what a restore is for
+        let before = match self.db_is_empty()? {
+            true => vec![],
+            false => self.read_lenient()?.rows,
         };
+
+        let temp_path = self.path.with_extension("tmp");
+        fs::create_dir_all(self.path.parent().unwrap_or(Path::new(".")))
+            .map_err(|e| self.write_error(e))?;
+        fs::copy(backup, &temp_path)
+            .map_err(|e| DBError::read(backup, e))?;
+        // only now, pruning may remove `backup`
+        self.back_up("restore")?;
+        self.replace_with(&temp_path)?;
+
+        let after = self.read_lenient()?.rows;
+        self.log_changes("restore", &before, &after)
+    }
+
+    /// Loads what can be loaded, with the lines that can't as problems
+    /// instead of failing on the first.
+    pub fn read_lenient(&self) -> Result<Diagnosis, DBError> {
+        let mut reader = self.get_reader()?;
+        let headers = reader.headers()
+            .map_err(|e| self.read_error(e))?
+            .clone();
+        let header_names: Vec<&str> = headers.iter().collect();
+
+        let mut diagnosis = Diagnosis::default();
+        let mut seen: HashMap<u32, u64> = HashMap::new();
+        for result in reader.records() {
+            let record = match result {
+                Ok(r) => r,
+                Err(e) if e.is_io_error() => return Err(self.read_error(e)),
+                Err(e) => {
+                    let line = e.position().map(|p| p.line()).unwrap_or_default();
+                    diagnosis.problems.push(Problem { line, id: None, kind: ProblemKind::Unparsable, message: e.to_string() });
+                    continue;
+                },
+            };
+            let line = record.position().map(|p| p.line()).unwrap_or_default();
+            let fields: Vec<&str> = record.iter().collect();
+            if let Some(problem) = check_fields(&header_names, &fields, line) {
+                diagnosis.problems.push(problem);
+                continue;
+            }
+
+            let row = record.deserialize::<CsvRow>(Some(&headers))
+                .map_err(|e| e.to_string())
+                .and_then(DBRow::try_from);
+            let id = record.get(0).and_then(|id| id.parse().ok());
+            let problem = |kind: ProblemKind, message: String| Problem { line, id, kind, message };
+            match row {
+                Err(e) => diagnosis.problems.push(problem(ProblemKind::Unparsable, e)),
+                Ok(r) => match seen.get(&r.id) {
+                    Some(first) => diagnosis.problems.push(
+                        problem(ProblemKind::DuplicateId, format!("ID {} is also used on line {}", r.id, first))),
+                    None => {
+                        seen.insert(r.id, line);
+                        diagnosis.rows.push(r);
+                    },
+                },
+            }
+        }
+
+        Ok(diagnosis)
+    }
+
+    /// Rewrites the database with only the rows that load, after backing
+    /// it up to `backups`, also when automatic backups are off. Leaves a
+    /// healthy database alone and returns no backup then.
+    pub fn repair(&self, backups: &Backups) -> Result<(Diagnosis, Option<Backup>), DBError> {
+        let diagnosis = self.read_lenient()?;
+        if diagnosis.is_healthy() {
+            return Ok((diagnosis, None));
+        }
+
+        let backup = backups.create(&self.path, "repair", Local::now().naive_local())?;
+        self.audited("repair", || self.write_rows(&diagnosis.rows))?;
+        // lines that don't load are no rows to diff
+        if let Some(audit) = &self.audit {
+            audit.append(&audit::dropped(&diagnosis.problems, "repair", &audit.user(), Local::now().timestamp()))?;
+        }
+
+        Ok((diagnosis, backup))
+    }
+
+    /// The rows to diff for the audit log. Operations check the database
+    /// themselves, the rows that load are enough here, and all there is to
+    /// a repair.
+    fn rows_or_empty(&self) -> Result<Vec<DBRow>, DBError> {
+        if self.db_is_empty()? {
+            return Ok(vec![]);
+        }
+
+        Ok(self.read_lenient()?.rows)
     }
 
     fn get_reader(&self) -> Result<csv::Reader<File>, DBError> {
         if !Path::exists(&self.path) {
-            return Err(DBError::new_dbnotexist_error());
+            return Err(DBError::new_dbnotexist_error(&self.path));
         }
 
         let reader = csv::ReaderBuilder::new()
-            .delimiter(';' as u8)
+            .delimiter(b';')
             .has_headers(true)
+            // `check_fields` reports lines with too few or many fields
+            .flexible(true)
             .from_path(&self.path)
-            .map_err(|e| DBError::new_read_error(&e.to_string()))?;
+            .map_err(|e| self.read_error(e))?;
         
-        return Ok(reader)
+        Ok(reader)
+    }
+
+    /// Whether there is no database yet, or only an empty file.
+    fn db_is_empty(&self) -> Result<bool, DBError> {
+        match fs::metadata(&self.path) {
+            Ok(m) => Ok(m.len() == 0),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(true),
+            Err(e) => Err(self.read_error(e)),
+        }
+    }
+
+    fn read_error(&self, e: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> DBError {
+        DBError::read(&self.path, e)
+    }
+
+    fn write_error(&self, e: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> DBError {
+        DBError::write(&self.path, e)
+    }
+
+    fn corrupt_row(&self, line: Option<u64>, id: Option<u32>, e: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> DBError {
+        let source = e.into();
+        DBError::CorruptRow(ErrorContext {
+            message: source.to_string(),
+            path: Some(self.path.clone()),
+            line,
+            id,
+            source: Some(source),
+        })
     }
 
-    fn db_is_empty(&self) -> bool {
-        let file = match fs::OpenOptions::new()
-            .read(true)
-            .open(&self.path) {
-                Ok(f) => f,
-                Err(_) => { return true },
+    /// The rows as stored, with the line of the file each is on.
+    fn read_records(&self) -> Result<Vec<(u64, CsvRow)>, DBError> {
+        let mut reader = self.get_reader()?;
+        let headers = reader.headers()
+            .map_err(|e| self.read_error(e))?
+            .clone();
+        let header_names: Vec<&str> = headers.iter().collect();
+
+        let mut rows: Vec<(u64, CsvRow)> = vec![];
+        for result in reader.records() {
+            let record = match result {
+                Ok(r) => r,
+                Err(e) if e.is_io_error() => return Err(self.read_error(e)),
+                Err(e) => return Err(self.corrupt_row(e.position().map(|p| p.line()), None, e)),
             };
+            let line = record.position().map(|p| p.line()).unwrap_or_default();
+            let fields: Vec<&str> = record.iter().collect();
+            if let Some(problem) = check_fields(&header_names, &fields, line) {
+                return Err(self.corrupt_row(Some(line), problem.id, format!("{}: {}", problem.kind, problem.message)));
+            }
+            let row = record.deserialize::<CsvRow>(Some(&headers))
+                .map_err(|e| self.corrupt_row(Some(line), record.get(0).and_then(|id| id.parse().ok()), e))?;
+            rows.push((line, row));
+        }
 
-        let size = file.metadata()
-            .unwrap()
-            .len();
+        Ok(rows)
+    }
 
-        if size == 0 {
-            return true;
+    /// Fails on the first ID that is on more than one line.
+    fn check_unique(&self, ids: impl Iterator<Item = (u64, u32)>) -> Result<(), DBError> {
+        let mut seen: HashMap<u32, u64> = HashMap::new();
+        for (line, id) in ids {
+            if let Some(first) = seen.insert(id, line) {
+                return Err(self.duplicate_id(id, line, format!("ID {} is also used on line {}", id, first)));
+            }
         }
 
-        false
+        Ok(())
+    }
+
+    fn duplicate_id(&self, id: u32, line: u64, message: String) -> DBError {
+        DBError::DuplicateId(ErrorContext {
+            message,
+            path: Some(self.path.clone()),
+            line: Some(line),
+            id: Some(id),
+            source: None,
+        })
     }
 
     fn get_writer(&self, append: bool) -> Result<csv::Writer<File>, DBError> {
@@ -247,26 +579,123 @@ impl DBCSV {
             .write(true)
             .append(append)
             .open(&self.path)
-            .map_err(|_| DBError::new_dbnotexist_error())?;
+            .map_err(|e| self.write_error(e))?;
 
         let writer = csv::WriterBuilder::new()
             .has_headers(false)
-            .delimiter(';' as u8)
+            .delimiter(b';')
             .from_writer(file);
 
-        return Ok(writer)
+        Ok(writer)
+    }
+
+    fn append_row(&self, r: &DBRow) -> Result<(), DBError> {
+        if self.db_is_empty()? {
+            self.create_db()?;
+        } else {
+            self.upgrade_schema()?;
+            if let Some((line, _)) = self.read_records()?.iter().find(|(_, row)| row.id == r.id) {
+                return Err(self.duplicate_id(r.id, *line, format!("ID {} is already used", r.id)));
+            }
+        }
+
+        let mut writer = self.get_writer(true)?;
+
+        writer.serialize(CsvRow::from(r))
+            .map_err(|e| self.write_error(e))?;
+
+        writer.flush()
+            .map_err(|e| self.write_error(e))?;
+
+        Ok(())
+    }
+
+    /// Drops the rows `remove` matches, returns how many. The database is
+    /// backed up before `operation` changes it.
+    fn remove_rows(&self, operation: &str, remove: impl Fn(&CsvRow) -> bool) -> Result<u32, DBError> {
+        let records = self.read_records()?;
+        let (mut temp_writer, temp_path) = self.get_temp_writer()?;
+
+        // work
+        let mut removed  = 0;
+        for (_, record) in records {
+            if !remove(&record) {
+                temp_writer.serialize(record)
+                    .map_err(|e| self.write_error(e))?;
+            } else {
+                removed += 1;
+            }
+        }
+
+        temp_writer.flush()
+            .map_err(|e| self.write_error(e))?;
+
+        if removed == 0 {
+            fs::remove_file(&temp_path)
+                .map_err(|e| DBError::write(&temp_path, e))?;
+            return Ok(0);
+        }
+        self.back_up(operation)?;
+        self.replace_with(&temp_path)?;
+
+        Ok(removed)
+    }
+
+    fn write_rows(&self, rows: &[DBRow]) -> Result<(), DBError> {
+        // the line each row is going to be on
+        self.check_unique(rows.iter().enumerate().map(|(i, r)| (i as u64 + 2, r.id)))?;
+        if !Path::exists(&self.path) {
+            self.create_db()?;
+        }
+
+        let (mut temp_writer, temp_path) = self.get_temp_writer()?;
+
+        for r in rows {
+            temp_writer.serialize(CsvRow::from(r))
+                .map_err(|e| self.write_error(e))?;
+        }
+
+        temp_writer.flush()
+            .map_err(|e| self.write_error(e))?;
+
+        self.replace_with(&temp_path)
     }
 
     fn get_temp_writer(&self) -> Result<(csv::Writer<File>, PathBuf), DBError> {
         let temp_path = self.path.with_extension("tmp");
 
-        let writer = csv::WriterBuilder::new()
-            .has_headers(true)
-            .delimiter(';' as u8)
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .delimiter(b';')
             .from_path(&temp_path)
-            .map_err(|_| DBError::new_dbnotexist_error())?;
+            .map_err(|e| DBError::write(&temp_path, e))?;
+
+        writer.write_record(HEADER)
+            .map_err(|e| self.write_error(e))?;
 
         Ok((writer, temp_path))
     }
 
+    /// Moves the temp file over the database in one rename, so a failure
+    /// leaves either the old or the new file.
+    fn replace_with(&self, temp_path: &Path) -> Result<(), DBError> {
+        fs::rename(temp_path, &self.path)
+            .map_err(|e| self.write_error(e))
+    }
+
+    /// Rewrites a database created by an older version so its header
+    /// matches the current columns before new rows are appended.
+    fn upgrade_schema(&self) -> Result<(), DBError> {
+        let mut reader = self.get_reader()?;
+        let header = reader.headers()
+            .map_err(|e| self.read_error(e))?;
+
+        if header.iter().eq(HEADER) {
+            return Ok(());
+        }
+
+        let rows = self.read_all()?;
+        self.write_rows(&rows)
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,114 @@
+use crate::audit::{format_changes, AuditEntry};
+use crate::contexts::is_blocked;
+use crate::dates::format_due;
+use crate::models::DBRow;
+use crate::timelog::{format_duration, TimeEntry};
+use crate::utils;
+
+
+/// Something that happened to a task, for its history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub at: i64,
+    pub text: String,
+}
+
+/// The past of `r`, oldest first: its changes from the audit log and the
+/// work in the time log. Without audit entries, e.g. for tasks from before
+/// the log, the row's own timestamps tell what happened.
+pub fn events(r: &DBRow, entries: &[TimeEntry], audit: &[AuditEntry], now: i64) -> Vec<Event> {
+    let event = |at: i64, text: String| Event { at, text };
+    let mut events: Vec<Event> = vec![];
+
+    for e in entries.iter().filter(|e| e.id == r.id) {
+        match e.end {
+            Some(_) => events.push(event(e.start, format!("worked {}", format_duration(e.seconds(now))))),
+            None => events.push(event(e.start, "started the timer".to_string())),
+        }
+    }
+
+    let audit: Vec<&AuditEntry> = audit.iter().filter(|e| e.id == r.id).collect();
+    if audit.is_empty() {
+        events.push(event(r.created_at(), "created".to_string()));
+        if let Some(done) = r.done_at() {
+            events.push(event(done, "completed".to_string()));
+        }
+        if r.updatedate > events.iter().map(|e| e.at).max().unwrap_or(0) {
+            events.push(event(r.updatedate, "last changed".to_string()));
+        }
+    }
+    for e in audit {
+        let text = match e.changes.iter().all(|c| c.old.is_none()) {
+            true => format!("created by {}", e.user),
+            false => format!("{}: {}", e.user, format_changes(e)),
+        };
+        events.push(event(e.at, text));
+    }
+    // stable, so what happened at the same second keeps its order
+    events.sort_by_key(|e| e.at);
+
+    events
+}
+
+/// Every field of `r` in a layout of its own, with the tasks it depends on
+/// and blocks looked up in `rows`.
+pub fn render(r: &DBRow, rows: &[DBRow], history: &[Event]) -> String {
+    let title = format!("#{} {}", r.id, r.task);
+    let mut out = format!("{}\n{}\n", title, "-".repeat(title.chars().count()));
+
+    let or_none = |value: String| if value.is_empty() { "-".to_string() } else { value };
+    let list = |values: Vec<String>| or_none(values.join(" "));
+    let task_ref = |id: u32| match rows.iter().find(|d| d.id == id) {
+        Some(d) => format!("#{} {} ({})", id, d.task, d.current_status()),
+        None => format!("#{} (deleted)", id),
+    };
+
+    let status = match is_blocked(r, rows) {
+        true => format!("{}, blocked", r.current_status()),
+        false => r.current_status().to_string(),
+    };
+    let blocks: Vec<String> = rows.iter()
+        .filter(|d| d.depends_on.contains(&r.id))
+        .map(|d| task_ref(d.id))
+        .collect();
+
+    let fields: Vec<(&str, String)> = vec![
+        ("Status", status),
+        ("Priority", or_none(r.priority.map(|p| p.to_string()).unwrap_or_default())),
+        ("Tags", list(r.tags.iter().map(|t| format!("+{}", t)).collect())),
+        ("Contexts", list(r.contexts.iter().map(|c| format!("@{}", c)).collect())),
+        ("Due", or_none(r.due.map(format_due).unwrap_or_default())),
+        ("Recurrence", or_none(r.recurrence.map(|rec| rec.to_string()).unwrap_or_default())),
+        ("Estimate", or_none(r.estimate.map(|e| e.to_string()).unwrap_or_default())),
+        ("Time logged", or_none(r.tracked.map(format_duration).unwrap_or_default())),
+        ("Snoozed until", or_none(r.snoozed_until.map(format_due).unwrap_or_default())),
+        ("Depends on", or_none(r.depends_on.iter().map(|d| task_ref(*d)).collect::<Vec<String>>().join(", "))),
+        ("Blocks", or_none(blocks.join(", "))),
+        ("Created", format_timestamp(r.created_at())),
+        ("Updated", format_timestamp(r.updatedate)),
+        ("Completed", or_none(r.done_at().map(format_timestamp).unwrap_or_default())),
+    ];
+    for (name, value) in fields {
+        out.push_str(&format!("{:<15}{}\n", format!("{}:", name), value));
+    }
+
+    out.push_str("\nNotes\n");
+    if r.notes.trim().is_empty() {
+        out.push_str("  -\n");
+    }
+    for line in r.notes.lines() {
+        out.push_str(format!("  {}", line).trim_end());
+        out.push('\n');
+    }
+
+    out.push_str("\nHistory\n");
+    for e in history {
+        out.push_str(&format!("  {}  {}\n", format_timestamp(e.at), e.text));
+    }
+
+    out
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    utils::format_timestamp(timestamp, "%Y-%m-%d %H:%M")
+}
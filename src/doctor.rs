@@ -0,0 +1,123 @@
+use std::fmt;
+
+use crate::models::DBRow;
+use crate::utils::unix_to_datetime;
+
+
+/// What is wrong with a line of the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProblemKind {
+    /// The ID is already used on an earlier line.
+    DuplicateId,
+    /// The line or one of its fields cannot be read as a row.
+    Unparsable,
+    /// `completed` is neither `true` nor `false`.
+    BadBoolean,
+    /// A date field is not a number of seconds, or one too large for a date.
+    BadTimestamp,
+    /// The line doesn't have as many fields as the header, usually because
+    /// of another delimiter than `;` or an unquoted `;` in the text.
+    Delimiter,
+}
+
+impl fmt::Display for ProblemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ProblemKind::DuplicateId => "duplicate ID",
+            ProblemKind::Unparsable => "unparsable row",
+            ProblemKind::BadBoolean => "bad boolean",
+            ProblemKind::BadTimestamp => "bad timestamp",
+            ProblemKind::Delimiter => "wrong delimiter",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A line of the database that cannot be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// Line of the file, counting from 1 with the header.
+    pub line: u64,
+    /// ID of the row, when it can be read.
+    pub id: Option<u32>,
+    pub kind: ProblemKind,
+    pub message: String,
+}
+
+/// The database as far as it can be loaded.
+#[derive(Debug, Default)]
+pub struct Diagnosis {
+    /// The rows that load, in the order of the file. Of rows with the same
+    /// ID only the first is kept.
+    pub rows: Vec<DBRow>,
+    /// The lines that were left out, in the order of the file.
+    pub problems: Vec<Problem>,
+}
+
+impl Diagnosis {
+    pub fn is_healthy(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Every ID in the file, also those of lines that don't load, so a new
+    /// task doesn't take the ID of a row that is only broken.
+    pub fn used_ids(&self) -> Vec<u32> {
+        self.rows.iter().map(|r| r.id)
+            .chain(self.problems.iter().filter_map(|p| p.id))
+            .collect()
+    }
+}
+
+/// Checks the fields of a line against `headers`: their number, the ID,
+/// `completed` and the timestamps. Other fields are left to parsing.
+pub fn check_fields(headers: &[&str], fields: &[&str], line: u64) -> Option<Problem> {
+    let id = field(headers, fields, "id").and_then(|id| id.parse::<u32>().ok());
+    let problem = |kind: ProblemKind, message: String| Some(Problem { line, id, kind, message });
+
+    if fields.len() != headers.len() {
+        let other = [',', '\t', '|'].into_iter()
+            .find(|d| fields.len() == 1 && fields[0].contains(*d));
+        let message = match other {
+            Some(d) => format!("fields are separated by {:?} instead of ';'", d),
+            None => format!("{} fields instead of {}", fields.len(), headers.len()),
+        };
+        return problem(ProblemKind::Delimiter, message);
+    }
+
+    match field(headers, fields, "id") {
+        Some(value) if id.is_none() => return problem(ProblemKind::Unparsable, format!("ID '{}' is not a number", value)),
+        _ => {},
+    }
+    match field(headers, fields, "completed") {
+        Some("true") | Some("false") | None => {},
+        Some(value) => return problem(ProblemKind::BadBoolean, format!("completed '{}' is not true or false", value)),
+    }
+    for name in ["updatedate", "due", "created", "completed_at", "snoozed_until"] {
+        match field(headers, fields, name) {
+            // only the update date is required
+            Some("") if name != "updatedate" => {},
+            Some(value) => match value.parse::<i64>() {
+                Err(_) => return problem(ProblemKind::BadTimestamp, format!("{} '{}' is not a timestamp", name, value)),
+                Ok(t) if unix_to_datetime(t).is_none() => {
+                    return problem(ProblemKind::BadTimestamp, format!("{} {} is out of range", name, value));
+                },
+                Ok(_) => {},
+            },
+            _ => {},
+        }
+    }
+
+    None
+}
+
+fn field<'a>(headers: &[&str], fields: &[&'a str], name: &str) -> Option<&'a str> {
+    headers.iter().position(|h| *h == name).and_then(|i| fields.get(i).copied())
+}
+
+/// e.g. `line 3 (#2): bad timestamp: due 'soon' is not a timestamp`.
+pub fn format_problem(p: &Problem) -> String {
+    match p.id {
+        Some(id) => format!("line {} (#{}): {}: {}", p.line, id, p.kind, p.message),
+        None => format!("line {}: {}: {}", p.line, p.kind, p.message),
+    }
+}
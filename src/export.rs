@@ -0,0 +1,180 @@
+use chrono::Utc;
+use clap::ValueEnum;
+
+use crate::dates::format_due;
+use crate::models::{DBError, DBRow, Priority, Status};
+use crate::utils::{format_timestamp, unix_to_datetime};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// todo.txt, one task per line
+    #[value(name="todotxt")]
+    TodoTxt,
+    /// iCalendar with one VTODO per task
+    Ics,
+    /// JSON array of rows
+    Json,
+    /// Markdown checklist
+    Markdown,
+}
+
+pub fn export(rows: &[DBRow], format: ExportFormat) -> Result<String, DBError> {
+    match format {
+        ExportFormat::TodoTxt => Ok(to_todotxt(rows)),
+        ExportFormat::Ics => Ok(to_ics(rows)),
+        ExportFormat::Json => to_json(rows),
+        ExportFormat::Markdown => Ok(to_markdown(rows)),
+    }
+}
+
+fn to_todotxt(rows: &[DBRow]) -> String {
+    let mut out = String::new();
+
+    for r in rows {
+        if let Some(done) = r.done_at() {
+            out.push_str(&format!("x {} ", format_timestamp(done, "%Y-%m-%d")));
+        } else if let Some(p) = r.priority {
+            let letter = match p {
+                Priority::High => 'A',
+                Priority::Medium => 'B',
+                Priority::Low => 'C',
+            };
+            out.push_str(&format!("({}) ", letter));
+        }
+        if let Some(created) = r.created {
+            out.push_str(&format!("{} ", format_timestamp(created, "%Y-%m-%d")));
+        }
+        out.push_str(&format!("{}{}", r.task, tag_suffix(r)));
+        for c in &r.contexts {
+            out.push_str(&format!(" @{}", c));
+        }
+        if let Some(due) = r.due {
+            out.push_str(&format!(" due:{}", format_timestamp(due, "%Y-%m-%d")));
+        }
+        if let Some(until) = r.snoozed_until {
+            out.push_str(&format!(" t:{}", format_timestamp(until, "%Y-%m-%d")));
+        }
+        out.push_str(&format!(" id:{}\n", r.id));
+    }
+
+    out
+}
+
+fn to_ics(rows: &[DBRow]) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//todo-app//EN".to_string(),
+    ];
+
+    for r in rows {
+        let stamp = ics_timestamp(r.updatedate);
+
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!("UID:todo-app-{}", r.id));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("LAST-MODIFIED:{}", stamp));
+        if let Some(created) = r.created {
+            lines.push(format!("CREATED:{}", ics_timestamp(created)));
+        }
+        lines.push(format!("SUMMARY:{}", ics_escape(&r.task)));
+        if !r.tags.is_empty() {
+            let tags: Vec<String> = r.tags.iter().map(|t| ics_escape(t)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        if let Some(due) = r.due {
+            lines.push(format!("DUE:{}", ics_timestamp(due)));
+        }
+        if let Some(p) = r.priority {
+            let level = match p {
+                Priority::High => 1,
+                Priority::Medium => 5,
+                Priority::Low => 9,
+            };
+            lines.push(format!("PRIORITY:{}", level));
+        }
+        match r.current_status() {
+            Status::Done => {
+                lines.push("STATUS:COMPLETED".to_string());
+                lines.push(format!("COMPLETED:{}", ics_timestamp(r.done_at().unwrap_or(r.updatedate))));
+            }
+            Status::InProgress => lines.push("STATUS:IN-PROCESS".to_string()),
+            Status::Cancelled => lines.push("STATUS:CANCELLED".to_string()),
+            Status::Todo | Status::Waiting => lines.push("STATUS:NEEDS-ACTION".to_string()),
+        }
+        lines.push("END:VTODO".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = String::new();
+    for line in lines {
+        out.push_str(&ics_fold(&line));
+        out.push_str("\r\n");
+    }
+
+    out
+}
+
+fn to_json(rows: &[DBRow]) -> Result<String, DBError> {
+    let mut out = serde_json::to_string_pretty(rows)
+        .map_err(|e| DBError::new_write_error(&e.to_string()))?;
+    out.push('\n');
+
+    Ok(out)
+}
+
+fn to_markdown(rows: &[DBRow]) -> String {
+    let mut out = String::new();
+
+    for r in rows {
+        let done = if r.completed { "x" } else { " " };
+        out.push_str(&format!("- [{}] {}{}", done, r.task, tag_suffix(r)));
+        if let Some(p) = r.priority {
+            out.push_str(&format!(" !{}", p));
+        }
+        if let Some(due) = r.due {
+            out.push_str(&format!(" (due {})", format_due(due)));
+        }
+        out.push_str(&format!(" (#{})\n", r.id));
+    }
+
+    out
+}
+
+fn tag_suffix(r: &DBRow) -> String {
+    r.tags.iter().map(|t| format!(" +{}", t)).collect()
+}
+
+fn ics_timestamp(timestamp: i64) -> String {
+    match unix_to_datetime(timestamp) {
+        Some(dt) => dt.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+fn ics_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds content lines longer than 75 octets as required by RFC 5545.
+fn ics_fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut len = 0;
+
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            len = 1;
+        }
+        out.push(c);
+        len += c.len_utf8();
+    }
+
+    out
+}
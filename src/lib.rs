@@ -2,6 +2,51 @@ pub mod arguments;
 pub mod models;
 pub mod db_csv;
 pub mod utils;
+pub mod export;
+pub mod bulk;
+pub mod dates;
+pub mod query;
+pub mod views;
+pub mod tui;
+pub mod server;
+pub mod rpc;
+pub mod merge;
+pub mod sync;
+pub mod timelog;
+pub mod burndown;
+pub mod stats;
+pub mod remind;
+pub mod workflow;
+pub mod board;
+pub mod agenda;
+pub mod quickadd;
+pub mod contexts;
+pub mod detail;
+pub mod audit;
+pub mod doctor;
+pub mod backup;
 
 pub mod test_utils;
-pub mod test_db_csv;
\ No newline at end of file
+pub mod test_db_csv;
+pub mod test_export;
+pub mod test_bulk;
+pub mod test_query;
+pub mod test_views;
+pub mod test_tui;
+pub mod test_server;
+pub mod test_rpc;
+pub mod test_merge;
+pub mod test_sync;
+pub mod test_timelog;
+pub mod test_burndown;
+pub mod test_stats;
+pub mod test_remind;
+pub mod test_workflow;
+pub mod test_board;
+pub mod test_agenda;
+pub mod test_quickadd;
+pub mod test_contexts;
+pub mod test_detail;
+pub mod test_audit;
+pub mod test_doctor;
+pub mod test_backup;
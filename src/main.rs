@@ -1,18 +1,54 @@
+use std::fs;
 use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
-use todo_app::arguments::{CliCommands, parse_arguments};
-use todo_app::models::{DBError, DBPrinter, DBReader, DBRow, DBWriter};
-use todo_app::{db_csv, utils};
+use clap::CommandFactory;
+
+use todo_app::arguments::{BackupCommands, Cli, CliCommands, ListArgs, NotifyVia, ReportCommands, TargetArgs, ViewCommands, parse_arguments};
+use todo_app::models::{Column, DBError, DBPrinter, DBReader, DBRow, DBWriter, Status, DEFAULT_COLUMNS};
+use todo_app::models::{EXIT_CORRUPT_ROW, EXIT_FAILURE, EXIT_NOT_FOUND, EXIT_USAGE};
+use todo_app::bulk::{self, BulkAction, Selection};
+use todo_app::burndown::{self, BurndownFormat};
+use todo_app::query::{self, Condition, Expr, Query};
+use todo_app::views::{builtin_views, View, ViewStore};
+use todo_app::workflow::Workflow;
+use todo_app::{agenda, audit, backup, board, contexts, dates, detail, db_csv, doctor, export, merge, quickadd, remind, rpc, server, stats, sync, timelog, utils};
 
 
 fn main() {
     let cli = parse_arguments();
+    VERBOSE.store(cli.verbose, Ordering::Relaxed);
+    debug_assert!(!builtin_views().keys().any(|name| is_command(name)), "a built-in view is named like a command");
 
     let db_path: PathBuf = utils::get_db_storage_path();
-    let repo = db_csv::DBCSV::new(db_path);
+    let views = ViewStore::new(db_path.with_file_name("views.json"));
+    let sync_dir = db_path.with_file_name("sync");
+    let repo = db_csv::DBCSV::new(db_path.clone())
+        .with_audit_log(audit_log())
+        .with_backups(backups());
 
     match &cli.command {
-        CliCommands::Add { value} => {
+        CliCommands::Add { tags, due, priority, estimate, raw, value} => {
+            let mut quick = if *raw {
+                quickadd::QuickAdd { task: value.join(" ").to_string(), ..Default::default() }
+            } else {
+                match quickadd::parse(value, chrono::Local::now().naive_local()) {
+                    Ok(q) => q,
+                    Err(e) => exit_with_error(&e),
+                }
+            };
+            // options win over what was picked out of the text
+            if let Some(d) = due {
+                match dates::parse_date(d, dates::today()) {
+                    Ok(date) => quick.due = Some(dates::date_to_timestamp(date)),
+                    Err(e) => exit_with_error(&e),
+                }
+            }
+            quick.tags.extend(tags.iter().cloned());
+            quick.priority = priority.or(quick.priority);
+
             let last_id = repo.get_next_id();
             let timestamp = chrono::Local::now().timestamp();
 
@@ -20,23 +56,25 @@ fn main() {
                 id: last_id,
                 updatedate: timestamp,
                 completed: false,
-                task: value.join(" ").to_string(),
+                task: quick.task,
+                tags: quick.tags,
+                contexts: quick.contexts,
+                due: quick.due,
+                priority: quick.priority,
+                estimate: *estimate,
+                recurrence: quick.recurrence,
+                created: Some(timestamp),
+                ..Default::default()
             };
             match repo.add(&r) {
                 Ok(()) => (),
-                Err(e) => panic!("{}", e),
+                Err(e) => fail(&e),
             }
             repo.print_one_row(&r);
         },
 
-        CliCommands::Delete { id} => {
-            match repo.delete(*id) {
-                Ok(_) => {
-                    println!("\nRow with ID {} deleted.\n", id);
-                }
-                Err(DBError::IDNotFound) => println!("\nID was not found.\n"),
-                Err(e) => panic!("{}", e),
-            }
+        CliCommands::Delete { target } => {
+            run_bulk(&repo, target, BulkAction::Delete);
         },
 
         CliCommands::DeleteCompleted => {
@@ -45,74 +83,885 @@ fn main() {
                     println!("\n{} rows deleted.\n", c);
                 }
                 Err(DBError::IDNotFound) => println!("\nID was not found.\n"),
-                Err(e) => panic!("{}", e),
+                Err(e) => fail(&e),
             }
         },
 
-        CliCommands::Complete { id} => {
-            match repo.mark_completion(*id, true) {
-                Ok(_) => {
-                    let r = repo.read_one(*id).unwrap().unwrap();
-                    repo.print_one_row(&r);
+        CliCommands::Complete { target } => {
+            run_bulk(&repo, target, BulkAction::Complete);
+        },
+
+        CliCommands::Uncomplete { target } => {
+            run_bulk(&repo, target, BulkAction::Uncomplete);
+        },
+
+        CliCommands::Wait { target } => {
+            run_bulk(&repo, target, BulkAction::SetStatus(Status::Waiting));
+        },
+
+        CliCommands::Cancel { target } => {
+            run_bulk(&repo, target, BulkAction::SetStatus(Status::Cancelled));
+        },
+
+        CliCommands::List { list } => {
+            run_list(&repo, None, list);
+        },
+
+        CliCommands::ListIncomplete { list } => {
+            run_list(&repo, Some(Condition::Open), list);
+        },
+
+        CliCommands::ListComplete { list } => {
+            run_list(&repo, Some(Condition::Status(Status::Done)), list);
+        },
+
+        CliCommands::Export { to, output, complete, incomplete } => {
+            let rows = match repo.read_all() {
+                Ok(rows) => rows,
+                Err(DBError::MissingDatabase(_)) => vec![],
+                Err(e) => fail(&e),
+            };
+            let rows: Vec<DBRow> = rows.into_iter()
+                .filter(|r| !(*complete || *incomplete) || r.completed == *complete)
+                .collect();
+
+            let content = match export::export(&rows, *to) {
+                Ok(c) => c,
+                Err(e) => fail(&e),
+            };
+
+            match output {
+                Some(path) => {
+                    if let Err(e) = fs::write(path, content) {
+                        fail(&DBError::write(path, e));
+                    }
+                    println!("\n{} rows exported to {}.\n", rows.len(), path.display());
                 }
-                Err(DBError::EmptyDB) => println!("\nNo items stored yet.\n"),
-                Err(DBError::IDNotFound) => println!("\nID was not found.\n"),
-                Err(e) => panic!("{}", e),
+                None => print!("{}", content),
             }
         },
 
-        CliCommands::Uncomplete { id} => {
-            match repo.mark_completion(*id, false) {
-                Ok(_) => {
-                    let r = repo.read_one(*id).unwrap().unwrap();
-                    repo.print_one_row(&r);
+        CliCommands::Next { context, limit } => {
+            let rows = match repo.read_all() {
+                Ok(rows) => rows,
+                Err(DBError::MissingDatabase(_)) => vec![],
+                Err(e) => fail(&e),
+            };
+
+            let mut next = contexts::next_actions(&rows, context.as_deref(), dates::today());
+            if let Some(n) = limit {
+                next.truncate(*n);
+            }
+            if next.is_empty() {
+                match context {
+                    Some(c) => println!("\nNothing to do @{}.\n", contexts::normalize(c)),
+                    None => println!("\nNothing to do.\n"),
                 }
-                Err(DBError::EmptyDB) => println!("\nNo items stored yet.\n"),
-                Err(DBError::IDNotFound) => println!("\nID was not found.\n"),
-                Err(e) => panic!("{}", e),
+                return;
             }
+            repo.print_columns(next, &DEFAULT_COLUMNS);
         },
 
-        CliCommands::List => {
-            match repo.read_all() {
-                Ok(rows) => {
-                    if rows.len() == 0 {
-                        println!("\nNo items stored yet.\n");
-                        return;
-                    }
-                    repo.print_all_rows(rows);
+        CliCommands::Contexts => {
+            let rows = match repo.read_all() {
+                Ok(rows) => rows,
+                Err(DBError::MissingDatabase(_)) => vec![],
+                Err(e) => fail(&e),
+            };
+
+            let counts = contexts::context_counts(&rows, dates::today());
+            if counts.is_empty() {
+                println!("\nNo open tasks have a context.\n");
+                return;
+            }
+            println!("\n  {:<16}\t{:>4}\t{:>4}", "Context", "Open", "Next");
+            println!("  {:<16}\t{:>4}\t{:>4}", "-------", "----", "----");
+            for (context, count) in counts {
+                println!("  {:<16}\t{:>4}\t{:>4}", format!("@{}", context), count.open, count.next);
+            }
+            println!();
+        },
+
+        CliCommands::Board { query, group_by, width, include_snoozed } => {
+            let mut query = query::join_args(query);
+            if *include_snoozed {
+                query.push_str(" snoozed:any");
+            }
+            let query = listing_query(&query, None);
+
+            let rows = match repo.read_all() {
+                Ok(rows) => query.apply(rows),
+                Err(DBError::MissingDatabase(_)) => vec![],
+                Err(e) => fail(&e),
+            };
+
+            let lanes = board::lanes(&rows, *group_by);
+            print!("\n{}\n", board::render(&lanes, width.unwrap_or_else(terminal_width)));
+        },
+
+        CliCommands::Agenda { query, week, month, include_snoozed } => {
+            let mut query = query::join_args(query);
+            if *include_snoozed {
+                query.push_str(" snoozed:any");
+            }
+            let query = listing_query(&query, Some(Condition::Open));
+
+            let rows = match repo.read_all() {
+                Ok(rows) => query.apply(rows),
+                Err(DBError::MissingDatabase(_)) => vec![],
+                Err(e) => fail(&e),
+            };
+
+            let today = dates::today();
+            let to = if *month {
+                agenda::end_of_month(today)
+            } else if *week {
+                today + chrono::Duration::days(6)
+            } else {
+                today
+            };
+
+            println!();
+            if *month {
+                println!("{}", agenda::render_month(today, &agenda::month_counts(&rows, today)));
+            }
+            print!("{}", agenda::render_agenda(&agenda::agenda(&rows, today, to), today));
+        },
+
+        CliCommands::Tui => {
+            run_tui(repo);
+        },
+
+        CliCommands::Serve { bind } => {
+            let server = match tiny_http::Server::http(bind) {
+                Ok(s) => s,
+                Err(e) => exit_failed(&format!("cannot listen on {}: {}", bind, e)),
+            };
+            println!("\nListening on http://{}/tasks\n", server.server_addr());
+            server::serve(&repo, &server);
+        },
+
+        CliCommands::Rpc => {
+            let session = rpc::RpcSession::new(repo).with_workflow(workflow());
+            session.run(std::io::stdin().lock(), std::io::stdout(), Duration::from_secs(1));
+        },
+
+        CliCommands::Sync { remote } => {
+            let git = sync::GitSync::new(sync_dir);
+
+            if let Some(url) = remote {
+                if let Err(e) = git.set_remote(url) {
+                    exit_failed(&e);
                 }
-                Err(DBError::EmptyDB) => println!("\nNo items stored yet.\n"),
-                Err(e) => panic!("{}", e),
             }
+
+            let report = match git.sync(&repo) {
+                Ok(r) => r,
+                Err(e) => exit_failed(&e),
+            };
+
+            if git.remote().is_none() {
+                println!("\nNo remote set, committed locally in {}", git.dir().display());
+                println!("Set one with: todo sync --remote URL\n");
+                return;
+            }
+
+            let mut done: Vec<&str> = vec![];
+            if report.committed {
+                done.push("committed local changes");
+            }
+            if report.merged {
+                done.push("merged remote changes");
+            } else if report.pulled {
+                done.push("pulled remote changes");
+            }
+            if report.conflicts > 0 {
+                done.push("resolved conflicting edits by latest update");
+            }
+            done.push("pushed");
+            println!("\nSynced: {}\n", done.join(", "));
+        },
+
+        CliCommands::Merge { base, ours, theirs, output, report } => {
+            let result = match merge::merge_files(base, ours, theirs) {
+                Ok(r) => r,
+                Err(e) => fail(&e),
+            };
+
+            let target = db_csv::DBCSV::new(output.clone().unwrap_or(ours.clone()));
+            if let Err(e) = target.write_all_as(&result.rows, "merge") {
+                fail(&e);
+            }
+
+            let lines: String = result.conflicts.iter()
+                .map(|c| format!("{}\n", c))
+                .collect();
+            match report {
+                Some(path) => if let Err(e) = fs::write(path, &lines) {
+                    exit_failed(&format!("cannot write {}: {}", path.display(), e));
+                },
+                None => eprint!("{}", lines),
+            }
+
+            println!("\nMerged {} tasks, {} conflicts\n", result.rows.len(), result.conflicts.len());
+        },
+
+        CliCommands::View { command } => {
+            run_view_command(&views, command);
+        },
+
+        CliCommands::Snooze { id, when } => {
+            let mut r = match repo.read_one(*id) {
+                Ok(Some(r)) => r,
+                Ok(None) | Err(DBError::MissingDatabase(_)) => not_found(*id),
+                Err(e) => fail(&e),
+            };
+
+            let today = dates::today();
+            r.snoozed_until = when.as_ref().map(|w| match dates::parse_date(w, today) {
+                Ok(date) if date > today => dates::date_to_timestamp(date),
+                Ok(date) => exit_with_error(&format!("cannot snooze until {}, pick a day after today", date)),
+                Err(e) => exit_with_error(&e),
+            });
+            r.updatedate = chrono::Local::now().timestamp();
+            if let Err(e) = repo.update(&r) {
+                fail(&e);
+            }
+
+            match r.snoozed_until {
+                Some(until) => println!("\nSnoozed #{} until {}\n", id, dates::format_due(until)),
+                None => println!("\nWoke up #{}\n", id),
+            }
+        },
+
+        CliCommands::Show { id } => {
+            let mut r = match repo.get(*id) {
+                Ok(r) => r,
+                Err(DBError::IDNotFound) => not_found(*id),
+                Err(e) => fail(&e),
+            };
+            let rows = match repo.read_all() {
+                Ok(rows) => rows,
+                Err(e) => fail(&e),
+            };
+            let entries = match time_log().read_all() {
+                Ok(e) => e,
+                Err(e) => fail(&e),
+            };
+
+            let now = chrono::Local::now().timestamp();
+            if let Err(e) = time_log().annotate(std::slice::from_mut(&mut r), now) {
+                fail(&e);
+            }
+            let audit = match audit_log().history(Some(*id), None) {
+                Ok(a) => a,
+                Err(e) => fail(&e),
+            };
+            print!("\n{}\n", detail::render(&r, &rows, &detail::events(&r, &entries, &audit, now)));
+        },
+
+        CliCommands::History { id, since } => {
+            let since = since.as_ref().map(|s| match dates::parse_date(s, dates::today()) {
+                Ok(date) => dates::date_to_timestamp(date),
+                Err(e) => exit_with_error(&e),
+            });
+            let entries = match audit_log().history(*id, since) {
+                Ok(e) => e,
+                Err(e) => fail(&e),
+            };
+
+            if entries.is_empty() {
+                println!("\nNo changes recorded.\n");
+                return;
+            }
+            println!();
+            for e in entries {
+                println!("{}", audit::format_entry(&e));
+            }
+            println!();
         },
 
-        CliCommands::ListIncomplete => {
-            match repo.read_all() {
-                Ok(rows) => {
-                    if rows.len() == 0 {
-                        println!("\nNo items stored yet.\n");
-                        return;
+        CliCommands::Note { id, text } => {
+            let mut r = require_task(&repo, *id);
+
+            r.notes = text.join(" ");
+            r.updatedate = chrono::Local::now().timestamp();
+            if let Err(e) = repo.update(&r) {
+                fail(&e);
+            }
+
+            match r.notes.is_empty() {
+                true => println!("\nCleared the notes of #{}\n", id),
+                false => println!("\nSet the notes of #{}\n", id),
+            }
+        },
+
+        CliCommands::Depend { id, on } => {
+            let rows = match repo.read_all() {
+                Ok(rows) => rows,
+                Err(DBError::MissingDatabase(_)) => vec![],
+                Err(e) => fail(&e),
+            };
+            let mut r = match rows.iter().find(|r| r.id == *id) {
+                Some(r) => r.clone(),
+                None => not_found(*id),
+            };
+
+            let max_id = rows.iter().map(|r| r.id).max().unwrap_or(0);
+            let ids = on.as_ref().map(|l| l.ids_up_to(max_id)).unwrap_or_default();
+            if let Err(e) = contexts::check_dependencies(&r, &ids, &rows) {
+                exit_with_error(&e);
+            }
+            r.depends_on = ids;
+            r.updatedate = chrono::Local::now().timestamp();
+            if let Err(e) = repo.update(&r) {
+                fail(&e);
+            }
+
+            let ids: Vec<String> = r.depends_on.iter().map(|d| format!("#{}", d)).collect();
+            match ids.len() {
+                0 => println!("\n#{} depends on nothing\n", id),
+                _ => println!("\n#{} depends on {}\n", id, ids.join(", ")),
+            }
+        },
+
+        CliCommands::Estimate { id, value } => {
+            let mut r = match repo.read_one(*id) {
+                Ok(Some(r)) => r,
+                Ok(None) | Err(DBError::MissingDatabase(_)) => not_found(*id),
+                Err(e) => fail(&e),
+            };
+
+            r.estimate = *value;
+            r.updatedate = chrono::Local::now().timestamp();
+            if let Err(e) = repo.update(&r) {
+                fail(&e);
+            }
+
+            repo.print_one_row(&r);
+        },
+
+        CliCommands::Start { id } => {
+            let status = require_task(&repo, *id).current_status();
+            if let Err(e) = workflow().check(status, Status::InProgress) {
+                exit_with_error(&format!("#{}: {}", id, e));
+            }
+            if status != Status::InProgress {
+                if let Err(e) = repo.set_status(*id, Status::InProgress) {
+                    fail(&e);
+                }
+            }
+            let now = chrono::Local::now().timestamp();
+
+            match time_log().start(*id, now) {
+                Ok(stopped) => {
+                    if let Some(e) = stopped {
+                        println!("\nStopped #{} after {}", e.id, timelog::format_duration(e.seconds(now)));
                     }
-                    repo.print_complete_filter(rows, false);
+                    println!("\nStarted #{}\n", id);
                 }
-                Err(DBError::EmptyDB) => println!("\nNo items stored yet.\n"),
-                Err(e) => panic!("{}", e),
+                Err(e) => fail(&e),
+            }
+        },
+
+        CliCommands::Stop => {
+            let now = chrono::Local::now().timestamp();
+
+            match time_log().stop(now) {
+                Ok(Some(e)) => println!("\nStopped #{} after {}\n", e.id, timelog::format_duration(e.seconds(now))),
+                Ok(None) => println!("\nNo timer running.\n"),
+                Err(e) => fail(&e),
+            }
+        },
+
+        CliCommands::Log { id, duration } => {
+            require_task(&repo, *id);
+
+            match time_log().log(*id, *duration, chrono::Local::now().timestamp()) {
+                Ok(_) => println!("\nLogged {} on #{}\n", timelog::format_duration(*duration), id),
+                Err(e) => fail(&e),
             }
         },
 
-        CliCommands::ListComplete => {
-            match repo.read_all() {
-                Ok(rows) => {
-                    if rows.len() == 0 {
-                        println!("\nNo items stored yet.\n");
-                        return;
+        CliCommands::Remind { within, via, command, url, to, sendmail, again } => {
+            let mut notifier: Box<dyn remind::Notifier> = match via {
+                NotifyVia::Stdout => Box::new(remind::StdoutNotifier { out: std::io::stdout() }),
+                NotifyVia::Desktop => Box::new(remind::CommandNotifier { command: command.clone() }),
+                NotifyVia::Webhook => match url {
+                    Some(url) => Box::new(remind::WebhookNotifier { url: url.clone() }),
+                    None => exit_with_error("--via webhook needs --url"),
+                },
+                NotifyVia::Mail => match to {
+                    Some(to) => Box::new(remind::MailNotifier { sendmail: sendmail.clone(), to: to.clone() }),
+                    None => exit_with_error("--via mail needs --to"),
+                },
+            };
+
+            let rows = match repo.read_all() {
+                Ok(rows) => rows,
+                Err(DBError::MissingDatabase(_)) => vec![],
+                Err(e) => fail(&e),
+            };
+            let now = chrono::Local::now().timestamp();
+            let due = remind::due_within(&rows, now, *within);
+
+            let sent_log = remind::SentLog::new(db_path.with_file_name("reminders.json"));
+            let pending = if *again {
+                due.clone()
+            } else {
+                match sent_log.unsent(&due) {
+                    Ok(p) => p,
+                    Err(e) => fail(&e),
+                }
+            };
+
+            if !pending.is_empty() {
+                if let Err(e) = notifier.notify(&pending, now) {
+                    exit_failed(&format!("cannot send reminders: {}", e));
+                }
+            }
+            if let Err(e) = sent_log.record(&pending, &due) {
+                fail(&e);
+            }
+        },
+
+        CliCommands::Stats { json } => {
+            let rows = match repo.read_all() {
+                Ok(rows) => rows,
+                Err(DBError::MissingDatabase(_)) => vec![],
+                Err(e) => fail(&e),
+            };
+
+            let stats = stats::compute(&rows, dates::today());
+            if *json {
+                match stats::to_json(&stats) {
+                    Ok(out) => print!("{}", out),
+                    Err(e) => fail(&e),
+                }
+            } else {
+                println!("\n{}", stats::render_text(&stats));
+            }
+        },
+
+        CliCommands::Report { command } => match command {
+            ReportCommands::Time { since, until } => {
+                let day = |d: &Option<String>| d.as_ref().map(|d| match dates::parse_date(d, dates::today()) {
+                    Ok(date) => date,
+                    Err(e) => exit_with_error(&e),
+                });
+                let from = day(since).map(dates::date_to_timestamp).unwrap_or(i64::MIN);
+                let to = day(until)
+                    .map(|d| match d.succ_opt() {
+                        Some(next) => dates::date_to_timestamp(next),
+                        None => exit_with_error("--until is too far in the future"),
+                    })
+                    .unwrap_or(i64::MAX);
+                report_time(&repo, from, to);
+            }
+            ReportCommands::Burndown { query, from, to, format } => {
+                let day = |d: &Option<String>| d.as_ref().map(|d| match dates::parse_date(d, dates::today()) {
+                    Ok(date) => date,
+                    Err(e) => exit_with_error(&e),
+                });
+                let to = day(to).unwrap_or(dates::today());
+                let from = match day(from) {
+                    Some(from) => from,
+                    None => to.checked_sub_signed(chrono::Duration::days(14))
+                        .unwrap_or_else(|| exit_with_error("--to is too far in the past to start two weeks before")),
+                };
+                if from > to {
+                    exit_with_error("--from must not be after --to");
+                }
+
+                let query = match Query::parse(&query::join_args(query)) {
+                    Ok(q) => q,
+                    Err(e) => exit_with_error(&e.to_string()),
+                };
+                let rows = match repo.read_all() {
+                    Ok(rows) => query.apply(rows),
+                    Err(DBError::MissingDatabase(_)) => vec![],
+                    Err(e) => fail(&e),
+                };
+
+                let points = burndown::burndown(&rows, from, to);
+                match format {
+                    BurndownFormat::Text => {
+                        println!();
+                        print!("{}", burndown::render_chart(&points, 10));
+                        let unestimated = rows.iter().filter(|r| r.estimate.is_none()).count();
+                        if unestimated > 0 {
+                            println!("\n{} tasks without an estimate are not counted.", unestimated);
+                        }
+                        println!();
                     }
-                    repo.print_complete_filter(rows, true);
+                    BurndownFormat::Csv => print!("{}", burndown::to_csv(&points)),
+                    BurndownFormat::Json => match burndown::to_json(&points) {
+                        Ok(json) => print!("{}", json),
+                        Err(e) => fail(&e),
+                    },
                 }
-                Err(DBError::EmptyDB) => println!("\nNo items stored yet.\n"),
-                Err(e) => panic!("{}", e),
             }
         },
+
+        CliCommands::Doctor { fix } => {
+            let result = match fix {
+                true => repo.repair(&backups()),
+                false => repo.read_lenient().map(|d| (d, None)),
+            };
+            let (diagnosis, backup) = match result {
+                Ok(d) => d,
+                Err(e) => fail(&e),
+            };
+
+            if diagnosis.is_healthy() {
+                println!("\nNo problems in {}, {} rows.\n", db_path.display(), diagnosis.rows.len());
+                return;
+            }
+            println!();
+            for p in &diagnosis.problems {
+                println!("{}", doctor::format_problem(p));
+            }
+            if let Some(b) = backup {
+                println!(
+                    "\nDropped {} lines, kept {} rows. The old file is backup {}.\n",
+                    diagnosis.problems.len(), diagnosis.rows.len(), b.name,
+                );
+                return;
+            }
+            println!(
+                "\n{} problems in {}, {} rows load. `todo doctor --fix` drops the lines after a backup.\n",
+                diagnosis.problems.len(), db_path.display(), diagnosis.rows.len(),
+            );
+            process::exit(EXIT_CORRUPT_ROW);
+        },
+
+        CliCommands::Backup { command } => {
+            run_backup_command(&repo, command);
+        },
+
+        CliCommands::RunView(args) => {
+            let view = match views.get(&args[0]) {
+                Ok(Some(v)) => v,
+                Ok(None) => exit_with_error(&format!("unrecognized subcommand or view '{}'", args[0])),
+                Err(e) => fail(&e),
+            };
+
+            let mut query = view.query_string();
+            if args.len() > 1 {
+                query = format!("{} {}", query::join_args(&args[1..]), query);
+            }
+            run_query(&repo, None, &query, &view.columns);
+        },
+    }
+}
+
+fn run_bulk(repo: &db_csv::DBCSV, target: &TargetArgs, action: BulkAction) {
+    let selection = Selection {
+        ids: target.ids.clone(),
+        tag: target.tag.clone(),
+        filter: target.filter.clone(),
+    };
+
+    match bulk::apply(repo, &selection, action, &workflow()) {
+        Ok(outcomes) if outcomes.is_empty() => println!("\nNo matching items.\n"),
+        Ok(outcomes) => bulk::print_summary(&outcomes),
+        Err(DBError::MissingDatabase(_)) => println!("\nNo items stored yet.\n"),
+        Err(e) => fail(&e),
+    }
+}
+
+fn run_list(repo: &db_csv::DBCSV, base: Option<Condition>, list: &ListArgs) {
+    let columns = if list.columns.is_empty() { DEFAULT_COLUMNS.to_vec() } else { list.columns.clone() };
+    run_query(repo, base, &list.query_string(), &columns);
+}
+
+/// Parses the query of a listing, which hides snoozed tasks unless the
+/// query asks about them.
+fn listing_query(query: &str, base: Option<Condition>) -> Query {
+    let mut query = match Query::parse(query) {
+        Ok(q) => q,
+        Err(e) => exit_with_error(&e.to_string()),
+    };
+    if let Some(c) = base {
+        query = query.and(Expr::Cond(c));
+    }
+    if !query.include_snoozed {
+        query = query.and(Expr::Not(Box::new(Expr::Cond(Condition::Snoozed(dates::today())))));
+    }
+
+    query
+}
+
+fn run_query(repo: &db_csv::DBCSV, base: Option<Condition>, query: &str, columns: &[Column]) {
+    let query = listing_query(query, base);
+
+    match repo.read_all() {
+        Ok(rows) => {
+            if rows.is_empty() {
+                println!("\nNo items stored yet.\n");
+                return;
+            }
+
+            let mut rows = query.apply(rows);
+            if let Err(e) = time_log().annotate(&mut rows, chrono::Local::now().timestamp()) {
+                fail(&e);
+            }
+
+            // the default listing shows logged time and snoozes once there are any
+            let mut columns = columns.to_vec();
+            if columns == DEFAULT_COLUMNS {
+                let today = dates::today();
+                if rows.iter().any(|r| r.is_snoozed(today)) {
+                    columns.insert(columns.len() - 1, Column::Snoozed);
+                }
+                if rows.iter().any(|r| r.tracked.is_some()) {
+                    columns.insert(columns.len() - 1, Column::Time);
+                }
+            }
+            repo.print_columns(rows, &columns);
+        }
+        Err(DBError::MissingDatabase(_)) => println!("\nNo items stored yet.\n"),
+        Err(e) => fail(&e),
     }
 }
+
+fn workflow() -> Workflow {
+    match Workflow::load(&utils::get_db_storage_path().with_file_name("workflow.json")) {
+        Ok(w) => w,
+        Err(e) => fail(&e),
+    }
+}
+
+fn audit_log() -> audit::AuditLog {
+    audit::AuditLog::new(utils::get_db_storage_path().with_file_name("audit.jsonl"))
+}
+
+fn backups() -> backup::Backups {
+    backup::Backups::new(utils::get_db_storage_path().with_file_name("backups"), backup::Backups::keep_from_env())
+}
+
+fn time_log() -> timelog::TimeLog {
+    timelog::TimeLog::new(utils::get_db_storage_path().with_file_name("timelog.csv"))
+}
+
+fn require_task(repo: &db_csv::DBCSV, id: u32) -> DBRow {
+    match repo.get(id) {
+        Ok(r) => r,
+        Err(DBError::IDNotFound) => not_found(id),
+        Err(e) => fail(&e),
+    }
+}
+
+fn report_time(repo: &db_csv::DBCSV, from: i64, to: i64) {
+    let now = chrono::Local::now().timestamp();
+    let entries = match time_log().read_all() {
+        Ok(e) => e,
+        Err(e) => fail(&e),
+    };
+    let rows = match repo.read_all() {
+        Ok(rows) => rows,
+        Err(DBError::MissingDatabase(_)) => vec![],
+        Err(e) => fail(&e),
+    };
+
+    let mut totals: std::collections::BTreeMap<u32, i64> = std::collections::BTreeMap::new();
+    for e in &entries {
+        let seconds = e.seconds_between(from, to, now);
+        if seconds > 0 {
+            let total = totals.entry(e.id).or_default();
+            *total = total.saturating_add(seconds);
+        }
+    }
+
+    if totals.is_empty() {
+        println!("\nNo time logged.\n");
+        return;
+    }
+
+    println!();
+    println!("{:>4}\t{:>7}\tTask", "ID", "Time");
+    println!("{:>4}\t{:>7}\t----", "--", "----");
+    for (id, seconds) in &totals {
+        let task = rows.iter()
+            .find(|r| r.id == *id)
+            .map(|r| r.task.as_str())
+            .unwrap_or("(deleted)");
+        println!("{:>4}\t{:>7}\t{}", id, timelog::format_duration(*seconds), task);
+    }
+    let total = totals.values().fold(0i64, |sum, s| sum.saturating_add(*s));
+    println!("{:>4}\t{:>7}", "", timelog::format_duration(total));
+    println!();
+}
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+fn exit_with_error(msg: &str) -> ! {
+    eprintln!("error: {}", msg);
+    process::exit(EXIT_USAGE);
+}
+
+fn exit_failed(msg: &str) -> ! {
+    eprintln!("error: {}", msg);
+    process::exit(EXIT_FAILURE);
+}
+
+/// Reports `e` and exits with its exit code, with `--verbose` followed by
+/// what caused it.
+fn fail(e: &DBError) -> ! {
+    eprintln!("error: {}", e);
+    if VERBOSE.load(Ordering::Relaxed) {
+        let mut source = std::error::Error::source(e);
+        while let Some(cause) = source {
+            eprintln!("  caused by: {}", cause);
+            source = cause.source();
+        }
+        eprintln!("  exit code {}", e.exit_code());
+    }
+
+    process::exit(e.exit_code());
+}
+
+/// Reports a task that doesn't exist, with the exit code of `IDNotFound`.
+fn not_found(id: u32) -> ! {
+    eprintln!("error: task #{} was not found", id);
+    process::exit(EXIT_NOT_FOUND);
+}
+
+/// Whether `name` is a command or alias, which a view of that name would
+/// never be run instead of.
+fn is_command(name: &str) -> bool {
+    Cli::command().get_subcommands()
+        .any(|c| c.get_name() == name || c.get_all_aliases().any(|a| a == name))
+}
+
+fn run_view_command(views: &ViewStore, command: &ViewCommands) {
+    match command {
+        ViewCommands::Save { name, list } => {
+            if is_command(name) {
+                exit_with_error(&format!("'{}' is already a command name", name));
+            }
+            if let Err(e) = Query::parse(&list.query_string()) {
+                exit_with_error(&e.to_string());
+            }
+
+            let mut query = query::join_args(&list.query);
+            if list.include_snoozed {
+                query = format!("{} snoozed:any", query).trim().to_string();
+            }
+            let view = View {
+                query,
+                sort: list.sort.clone(),
+                limit: list.limit,
+                columns: if list.columns.is_empty() { DEFAULT_COLUMNS.to_vec() } else { list.columns.clone() },
+            };
+            match views.save(name, view) {
+                Ok(()) => println!("\nView '{}' saved.\n", name),
+                Err(e) => fail(&e),
+            }
+        }
+
+        ViewCommands::List => {
+            let saved = match views.load() {
+                Ok(v) => v,
+                Err(e) => fail(&e),
+            };
+
+            println!();
+            println!("{:12}\tQuery", "Name");
+            println!("{:12}\t-----", "----");
+            for (name, view) in builtin_views() {
+                println!("{:12}\t{} (built-in)", name, view.query_string());
+            }
+            for (name, view) in saved {
+                println!("{:12}\t{}", name, view.query_string());
+            }
+            println!();
+        }
+
+        ViewCommands::Delete { name } => {
+            match views.delete(name) {
+                Ok(()) => println!("\nView '{}' deleted.\n", name),
+                Err(DBError::IDNotFound) => {
+                    eprintln!("error: view '{}' was not found", name);
+                    process::exit(EXIT_NOT_FOUND);
+                }
+                Err(e) => fail(&e),
+            }
+        }
+    }
+}
+
+fn run_backup_command(repo: &db_csv::DBCSV, command: &BackupCommands) {
+    let backups = backups();
+
+    match command {
+        BackupCommands::Now => {
+            match backups.create(&utils::get_db_storage_path(), "manual", chrono::Local::now().naive_local()) {
+                Ok(Some(b)) => println!("\nBacked up to {}.\n", b.path.display()),
+                Ok(None) => println!("\nNo items stored yet.\n"),
+                Err(e) => fail(&e),
+            }
+        }
+
+        BackupCommands::List => {
+            let list = match backups.list() {
+                Ok(l) => l,
+                Err(e) => fail(&e),
+            };
+
+            if list.is_empty() {
+                println!("\nNo backups yet.\n");
+                return;
+            }
+            println!();
+            for b in list {
+                // broken backups are listed too, with the rows that load
+                match db_csv::DBCSV::new(b.path.clone()).read_lenient() {
+                    Ok(d) => println!("{}\t{} rows", b.name, d.rows.len()),
+                    Err(_) => println!("{}\tunreadable", b.name),
+                }
+            }
+            println!();
+        }
+
+        BackupCommands::Restore { name } => {
+            let found = match backups.find(name) {
+                Ok(Some(b)) => b,
+                Ok(None) => {
+                    eprintln!("error: backup '{}' was not found", name);
+                    process::exit(EXIT_NOT_FOUND);
+                }
+                Err(e) => fail(&e),
+            };
+
+            match repo.restore(&found.path) {
+                Ok(()) => println!("\nRestored {}.\n", found.name),
+                Err(e) => fail(&e),
+            }
+        }
+    }
+}
+
+fn terminal_width() -> usize {
+    if let Some(width) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return width;
+    }
+    #[cfg(unix)]
+    if let Ok((width, _)) = termion::terminal_size() {
+        return width as usize;
+    }
+
+    80
+}
+
+#[cfg(unix)]
+fn run_tui(repo: db_csv::DBCSV) {
+    if let Err(e) = todo_app::tui::run(repo) {
+        exit_failed(&e.to_string());
+    }
+}
+
+#[cfg(not(unix))]
+fn run_tui(_repo: db_csv::DBCSV) {
+    exit_with_error("the interactive mode is not supported on this platform");
+}
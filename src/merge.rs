@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::db_csv::DBCSV;
+use crate::models::{DBError, DBReader, DBRow};
+
+/// Fields that only make sense together and are merged as one, reported
+/// as `status` when they conflict.
+const STATUS_FIELDS: [&str; 3] = ["status", "completed", "completed_at"];
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Ours => write!(f, "ours"),
+            Side::Theirs => write!(f, "theirs"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// Both sides changed a field to different values, the later update won.
+    Field { id: u32, field: String, ours: String, theirs: String, took: Side },
+    /// One side deleted a task the other one changed, the task was kept.
+    DeletedChanged { id: u32, deleted_by: Side },
+    /// Both sides added different tasks under the same ID, theirs got a new one.
+    IdCollision { id: u32, new_id: u32 },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::Field { id, field, ours, theirs, took } => write!(f,
+                "#{} {}: ours {}, theirs {}, took {} (later update)", id, field, ours, theirs, took),
+            Conflict::DeletedChanged { id, deleted_by } => write!(f,
+                "#{} deleted on {} but changed on the other side, kept the change", id, deleted_by),
+            Conflict::IdCollision { id, new_id } => write!(f,
+                "#{} added on both sides, theirs renumbered to #{}", id, new_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MergeResult {
+    pub rows: Vec<DBRow>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// True when both rows hold the same values, `DBRow`'s `PartialEq` only
+/// compares IDs.
+pub fn same_row(a: &DBRow, b: &DBRow) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Three-way merge of two copies of the store that both started from `base`.
+///
+/// Rows are matched by ID and merged field by field: a field changed on one
+/// side takes that change, a field changed on both sides takes the value from
+/// the row with the later `updatedate` (ours on a tie). A deletion wins over
+/// an unchanged row and loses against a changed one. Rows added on both
+/// sides under one ID are merged when their task text matches, otherwise
+/// theirs is moved to a free ID.
+pub fn merge_rows(base: &[DBRow], ours: &[DBRow], theirs: &[DBRow]) -> MergeResult {
+    let base: BTreeMap<u32, &DBRow> = base.iter().map(|r| (r.id, r)).collect();
+    let ours: BTreeMap<u32, &DBRow> = ours.iter().map(|r| (r.id, r)).collect();
+    let theirs: BTreeMap<u32, &DBRow> = theirs.iter().map(|r| (r.id, r)).collect();
+
+    let mut ids: Vec<u32> = base.keys().chain(ours.keys()).chain(theirs.keys()).copied().collect();
+    ids.sort();
+    ids.dedup();
+    let mut next_id = ids.last().map(|id| id + 1).unwrap_or(1);
+
+    let mut result = MergeResult::default();
+    let mut renumbered: Vec<DBRow> = vec![];
+
+    for id in ids {
+        let b = base.get(&id).copied();
+        let unchanged = |r: &DBRow| b.map(|b| same_row(b, r)).unwrap_or(false);
+
+        match (ours.get(&id).copied(), theirs.get(&id).copied()) {
+            (Some(o), Some(t)) if same_row(o, t) => result.rows.push(o.clone()),
+            (Some(o), Some(t)) if b.is_none() && o.task != t.task => {
+                result.rows.push(o.clone());
+                let mut moved = t.clone();
+                moved.id = next_id;
+                result.conflicts.push(Conflict::IdCollision { id, new_id: next_id });
+                renumbered.push(moved);
+                next_id += 1;
+            }
+            (Some(o), Some(t)) => {
+                let (row, conflicts) = merge_fields(b, o, t);
+                result.rows.push(row);
+                result.conflicts.extend(conflicts);
+            }
+            (Some(r), None) | (None, Some(r)) => {
+                // present on one side: added there, or deleted on the other
+                if b.is_none() {
+                    result.rows.push(r.clone());
+                } else if !unchanged(r) {
+                    let deleted_by = if ours.contains_key(&id) { Side::Theirs } else { Side::Ours };
+                    result.conflicts.push(Conflict::DeletedChanged { id, deleted_by });
+                    result.rows.push(r.clone());
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    result.rows.extend(renumbered);
+    result
+}
+
+fn merge_fields(base: Option<&DBRow>, ours: &DBRow, theirs: &DBRow) -> (DBRow, Vec<Conflict>) {
+    let fields = |r: &DBRow| match serde_json::to_value(r) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    let base_fields = base.map(fields);
+    let ours_fields = fields(ours);
+    let theirs_fields = fields(theirs);
+
+    let later = if theirs.updatedate > ours.updatedate { Side::Theirs } else { Side::Ours };
+    let mut merged = ours_fields.clone();
+    let mut conflicts: Vec<Conflict> = vec![];
+
+    let status = |f: &Map<String, Value>| Value::Array(
+        STATUS_FIELDS.iter().map(|n| f.get(*n).cloned().unwrap_or(Value::Null)).collect()
+    );
+    let mut pick = |name: &str, o: &Value, t: &Value, b: Option<&Value>, shown: (&Value, &Value)| {
+        if b == Some(o) {
+            t.clone()
+        } else if b == Some(t) {
+            o.clone()
+        } else {
+            conflicts.push(Conflict::Field {
+                id: ours.id,
+                field: name.to_string(),
+                ours: shown.0.to_string(),
+                theirs: shown.1.to_string(),
+                took: later,
+            });
+            if later == Side::Theirs { t.clone() } else { o.clone() }
+        }
+    };
+
+    for (name, o) in &ours_fields {
+        let t = theirs_fields.get(name).unwrap_or(&Value::Null);
+        if o == t || name == "id" || name == "updatedate" || STATUS_FIELDS.contains(&name.as_str()) {
+            continue;
+        }
+
+        let b = base_fields.as_ref().and_then(|f| f.get(name));
+        let value = pick(name, o, t, b, (o, t));
+        merged.insert(name.clone(), value);
+    }
+
+    let (o, t) = (status(&ours_fields), status(&theirs_fields));
+    if o != t {
+        let b = base_fields.as_ref().map(status);
+        let shown = (ours_fields.get("status").unwrap_or(&Value::Null), theirs_fields.get("status").unwrap_or(&Value::Null));
+        if let Value::Array(values) = pick("status", &o, &t, b.as_ref(), shown) {
+            for (name, value) in STATUS_FIELDS.iter().zip(values) {
+                merged.insert(name.to_string(), value);
+            }
+        }
+    }
+
+    let mut row: DBRow = serde_json::from_value(Value::Object(merged)).unwrap_or_else(|_| ours.clone());
+    row.updatedate = ours.updatedate.max(theirs.updatedate);
+
+    (row, conflicts)
+}
+
+/// Reads all rows of a store file, a missing or empty file has none.
+pub fn read_rows<T: DBReader>(repo: &T) -> Result<Vec<DBRow>, DBError> {
+    match repo.read_all() {
+        Ok(mut rows) => {
+            rows.sort_by_key(|r| r.id);
+            Ok(rows)
+        }
+        Err(DBError::MissingDatabase(_)) => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+/// Merges three store files, e.g. as handed over by git to a merge driver.
+pub fn merge_files(base: &Path, ours: &Path, theirs: &Path) -> Result<MergeResult, DBError> {
+    for path in [ours, theirs] {
+        if !path.exists() {
+            return Err(DBError::new_dbnotexist_error(path));
+        }
+    }
+
+    let base = read_rows(&DBCSV::new(base.to_path_buf()))?;
+    let ours = read_rows(&DBCSV::new(ours.to_path_buf()))?;
+    let theirs = read_rows(&DBCSV::new(theirs.to_path_buf()))?;
+
+    Ok(merge_rows(&base, &ours, &theirs))
+}
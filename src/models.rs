@@ -1,8 +1,17 @@
-use std::{cmp::Ordering, fmt::{self}};
+use std::{cmp::Ordering, fmt::{self}, path::{Path, PathBuf}, str::FromStr};
 
 pub trait DBReader {
     fn read_all(&self) -> Result<Vec<DBRow>, DBError>;
     fn read_one(&self, id: u32) -> Result<Option<DBRow>, DBError>;
+
+    /// Like `read_one`, but a missing row, or database, is `IDNotFound`.
+    fn get(&self, id: u32) -> Result<DBRow, DBError> {
+        match self.read_one(id) {
+            Ok(Some(r)) => Ok(r),
+            Ok(None) | Err(DBError::MissingDatabase(_)) => Err(DBError::IDNotFound),
+            Err(e) => Err(e),
+        }
+    }
     fn get_next_id(&self) -> u32;
 }
 
@@ -11,12 +20,36 @@ pub trait DBWriter {
     fn create_db(&self) -> Result<(), DBError>;
     fn delete(&self, id: u32) -> Result<(), DBError>;
     fn delete_completed(&self) -> Result<u32, DBError>;
-    fn mark_completion(&self, id: u32, complete: bool) -> Result<(), DBError>;
+    /// Moves the task to `status`, see `workflow::Workflow` for the
+    /// transitions the commands allow.
+    fn set_status(&self, id: u32, status: Status) -> Result<(), DBError>;
+    fn mark_completion(&self, id: u32, complete: bool) -> Result<(), DBError> {
+        self.set_status(id, if complete { Status::Done } else { Status::Todo })
+    }
+    /// Replaces the whole store with `rows` in a single rewrite.
+    fn write_all(&self, rows: &[DBRow]) -> Result<(), DBError> {
+        self.write_all_as(rows, "write_all")
+    }
+    /// `write_all` for `operation`, e.g. a bulk `delete`, which is the name
+    /// the rewrite is logged and backed up under.
+    fn write_all_as(&self, rows: &[DBRow], operation: &str) -> Result<(), DBError>;
+    /// Replaces the stored row that has the same ID as `r`.
+    fn update(&self, r: &DBRow) -> Result<(), DBError>;
 }
 
 pub trait DBPrinter {
     fn print_header(&self);
     fn print_row(&self, r: &DBRow);
+    fn print_header_columns(&self, columns: &[Column]);
+    fn print_row_columns(&self, r: &DBRow, columns: &[Column]);
+
+    fn print_columns(&self, v: Vec<DBRow>, columns: &[Column]) {
+        self.print_header_columns(columns);
+        for r in v {
+            self.print_row_columns(&r, columns);
+        }
+        println!()
+    }
 
     fn print_all_rows(&self, v: Vec<DBRow>) {
         self.print_header();
@@ -28,7 +61,7 @@ pub trait DBPrinter {
 
     fn print_one_row(&self, r: &DBRow) {
         self.print_header();
-        self.print_row(&r);
+        self.print_row(r);
         println!()
     }
 
@@ -42,12 +75,373 @@ pub trait DBPrinter {
     }
 }
 
-#[derive(Debug, Eq, serde::Deserialize, serde::Serialize)]
+/// Column of the task table, see `DBPrinter::print_columns`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    Id,
+    State,
+    Updated,
+    Due,
+    /// Time logged on the task, see `timelog::TimeLog`.
+    Time,
+    /// Estimated effort.
+    Estimate,
+    /// Day the task is snoozed until.
+    Snoozed,
+    Task,
+}
+
+pub const DEFAULT_COLUMNS: [Column; 5] = [Column::Id, Column::State, Column::Updated, Column::Due, Column::Task];
+
+#[derive(Debug, Clone, Default, Eq, serde::Deserialize, serde::Serialize)]
 pub struct DBRow {
     pub id: u32,
     pub updatedate: i64,
     pub task: String,
+    /// Set while the status is `Status::Done`, kept for older readers.
     pub completed: bool,
+    #[serde(default)]
+    pub status: Status,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Where the task can be done, e.g. `phone` for `@phone`.
+    #[serde(default)]
+    pub contexts: Vec<String>,
+    #[serde(default)]
+    pub due: Option<i64>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    /// Estimated effort, in points or hours, whichever the team plans in.
+    #[serde(default)]
+    pub estimate: Option<u32>,
+    #[serde(default)]
+    pub created: Option<i64>,
+    #[serde(default)]
+    pub completed_at: Option<i64>,
+    /// Hides the task from the default listings until this day starts.
+    #[serde(default)]
+    pub snoozed_until: Option<i64>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// Free text kept with the task, e.g. links or a checklist.
+    #[serde(default)]
+    pub notes: String,
+    /// IDs of the tasks that have to be closed before this one can start.
+    #[serde(default)]
+    pub depends_on: Vec<u32>,
+    /// Seconds logged in the time log. Filled in for listings, not stored
+    /// with the row.
+    #[serde(skip)]
+    pub tracked: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Priority::Low => write!(f, "low"),
+            Priority::Medium => write!(f, "medium"),
+            Priority::High => write!(f, "high"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "l" | "low" => Ok(Priority::Low),
+            "m" | "med" | "medium" => Ok(Priority::Medium),
+            "h" | "high" => Ok(Priority::High),
+            _ => Err(format!("'{}' is not a valid priority, expected low, medium or high", s)),
+        }
+    }
+}
+
+/// Where a task stands. Todo, in progress and waiting tasks are open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Waiting,
+    Done,
+    Cancelled,
+}
+
+impl Status {
+    pub const ALL: [Status; 5] = [Status::Todo, Status::InProgress, Status::Waiting, Status::Done, Status::Cancelled];
+
+    pub fn is_open(&self) -> bool {
+        matches!(self, Status::Todo | Status::InProgress | Status::Waiting)
+    }
+
+    /// Status after setting the completion flag, reopening a done task
+    /// makes it todo again.
+    pub fn with_completed(self, completed: bool) -> Status {
+        match (completed, self) {
+            (true, _) => Status::Done,
+            (false, Status::Done) => Status::Todo,
+            (false, s) => s,
+        }
+    }
+
+    /// Short marker for the state column.
+    pub fn marker(&self) -> &'static str {
+        match self {
+            Status::Todo => "[ ]",
+            Status::InProgress => "[>]",
+            Status::Waiting => "[~]",
+            Status::Done => "[X]",
+            Status::Cancelled => "[-]",
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Todo => write!(f, "todo"),
+            Status::InProgress => write!(f, "in-progress"),
+            Status::Waiting => write!(f, "waiting"),
+            Status::Done => write!(f, "done"),
+            Status::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "todo" => Ok(Status::Todo),
+            "in-progress" | "inprogress" | "started" => Ok(Status::InProgress),
+            "waiting" | "wait" => Ok(Status::Waiting),
+            "done" | "complete" | "completed" => Ok(Status::Done),
+            "cancelled" | "canceled" => Ok(Status::Cancelled),
+            _ => Err(format!("'{}' is not a valid status, expected todo, in-progress, waiting, done or cancelled", s)),
+        }
+    }
+}
+
+/// How often a task comes back, see `bulk::apply` for where the next one
+/// is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Recurrence {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+    Weekday(chrono::Weekday),
+}
+
+impl Recurrence {
+    /// The first day after `date` the task is due again, `None` when that
+    /// is beyond the last date there is.
+    pub fn next(&self, date: chrono::NaiveDate) -> Option<chrono::NaiveDate> {
+        use chrono::{Datelike, Days, Months};
+
+        match self {
+            Recurrence::Days(n) => date.checked_add_days(Days::new(*n as u64)),
+            Recurrence::Weeks(n) => date.checked_add_days(Days::new(*n as u64 * 7)),
+            Recurrence::Months(n) => date.checked_add_months(Months::new(*n)),
+            Recurrence::Years(n) => date.checked_add_months(Months::new(n.checked_mul(12)?)),
+            Recurrence::Weekday(w) => {
+                let ahead = (w.num_days_from_monday() + 6 - date.weekday().num_days_from_monday()) % 7 + 1;
+                date.checked_add_days(Days::new(ahead as u64))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (n, unit) = match self {
+            Recurrence::Days(n) => (*n, "day"),
+            Recurrence::Weeks(n) => (*n, "week"),
+            Recurrence::Months(n) => (*n, "month"),
+            Recurrence::Years(n) => (*n, "year"),
+            Recurrence::Weekday(w) => {
+                let name = chrono::NaiveDate::from_isoywd_opt(2026, 1, *w).unwrap().format("%A").to_string();
+                return write!(f, "every {}", name.to_lowercase());
+            }
+        };
+
+        match n {
+            1 => write!(f, "every {}", unit),
+            n => write!(f, "every {} {}s", n, unit),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    /// Parses `every day`, `every 2 weeks`, `every monday`, `daily`,
+    /// `weekly`, `monthly` or `yearly`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{}' is not a valid recurrence, expected e.g. every day, every 2 weeks or every monday", s);
+        let lower = s.trim().to_lowercase();
+        let words: Vec<&str> = lower.strip_prefix("every ").unwrap_or(&lower).split_whitespace().collect();
+
+        let (n, unit) = match words.as_slice() {
+            ["daily"] => (1, "day"),
+            ["weekly"] => (1, "week"),
+            ["monthly"] => (1, "month"),
+            ["yearly"] => (1, "year"),
+            [unit] => (1, *unit),
+            [n, unit] => (n.parse::<u32>().ok().filter(|n| *n > 0).ok_or_else(invalid)?, *unit),
+            _ => return Err(invalid()),
+        };
+
+        match unit.trim_end_matches('s') {
+            "day" => Ok(Recurrence::Days(n)),
+            "week" => Ok(Recurrence::Weeks(n)),
+            "month" => Ok(Recurrence::Months(n)),
+            "year" => Ok(Recurrence::Years(n)),
+            _ if n == 1 && words.len() == 1 => {
+                let weekday = chrono::Weekday::from_str(unit).map_err(|_| invalid())?;
+                // only full names, "every sun" reads like text
+                if unit.len() < 6 {
+                    return Err(invalid());
+                }
+                Ok(Recurrence::Weekday(weekday))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(r: Recurrence) -> Self {
+        r.to_string()
+    }
+}
+
+impl DBRow {
+    /// Moves the row to `status` and keeps `completed` and `completed_at`
+    /// in step.
+    pub fn set_status(&mut self, status: Status, now: i64) {
+        let completed = status == Status::Done;
+        if completed != self.completed {
+            self.completed_at = if completed { Some(now) } else { None };
+        }
+        self.completed = completed;
+        self.status = status;
+    }
+
+    /// Marks the row as done or open, see `Status::with_completed`.
+    pub fn set_completed(&mut self, completed: bool, now: i64) {
+        self.set_status(self.current_status().with_completed(completed), now);
+    }
+
+    /// The status, where `completed` wins for rows that only set the flag.
+    pub fn current_status(&self) -> Status {
+        self.status.with_completed(self.completed)
+    }
+
+    /// When the task was created, the update date for rows stored before
+    /// creation was recorded.
+    pub fn created_at(&self) -> i64 {
+        self.created.unwrap_or(self.updatedate)
+    }
+
+    /// Whether the task is still hidden by a snooze on `today`.
+    pub fn is_snoozed(&self, today: chrono::NaiveDate) -> bool {
+        self.snoozed_until
+            .map(|s| crate::dates::timestamp_to_date(s) > today)
+            .unwrap_or(false)
+    }
+
+    /// The task coming back after this one is done: due on the next date of
+    /// its recurrence at the same time of day, with everything else carried
+    /// over. `None` unless the task recurs and has a due date.
+    pub fn next_occurrence(&self, id: u32, now: i64) -> Option<DBRow> {
+        let recurrence = self.recurrence?;
+        let due = crate::utils::unix_to_datetime(self.due?)?.naive_local();
+        let next = recurrence.next(due.date())?.and_time(due.time());
+
+        Some(DBRow {
+            id,
+            updatedate: now,
+            completed: false,
+            status: Status::Todo,
+            due: Some(crate::dates::local_timestamp(next)),
+            created: Some(now),
+            completed_at: None,
+            snoozed_until: None,
+            tracked: None,
+            ..self.clone()
+        })
+    }
+
+    /// When the task was completed, `None` while it is open.
+    pub fn done_at(&self) -> Option<i64> {
+        if !self.completed {
+            return None;
+        }
+
+        Some(self.completed_at.unwrap_or(self.updatedate))
+    }
+
+    /// Copy of the row with the given JSON fields overlaid. The ID and update
+    /// date are kept, they are managed by the store. A change of `status` or
+    /// `completed` updates the other one, and `completed_at` unless that is
+    /// given too.
+    pub fn with_fields(&self, fields: serde_json::Map<String, serde_json::Value>, now: i64) -> Result<DBRow, String> {
+        let completion_given = fields.contains_key("completed_at");
+        let status_given = fields.contains_key("status");
+
+        let mut merged = serde_json::json!(self);
+        for (key, value) in fields {
+            if key == "id" || key == "updatedate" {
+                continue;
+            }
+            merged[key] = value;
+        }
+
+        let mut r: DBRow = serde_json::from_value(merged).map_err(|e| e.to_string())?;
+        if r.task.trim().is_empty() {
+            return Err("task must not be empty".to_string());
+        }
+        let timestamps = [("due", r.due), ("created", r.created), ("completed_at", r.completed_at), ("snoozed_until", r.snoozed_until)];
+        for (name, value) in timestamps {
+            if let Some(t) = value.filter(|t| crate::utils::unix_to_datetime(*t).is_none()) {
+                return Err(format!("{} {} is out of range", name, t));
+            }
+        }
+        let status = if status_given { r.status } else { self.current_status().with_completed(r.completed) };
+        if completion_given {
+            r.status = status;
+            r.completed = status == Status::Done;
+        } else {
+            r.completed = self.completed;
+            r.status = self.status;
+            r.set_status(status, now);
+        }
+
+        Ok(r)
+    }
 }
 
 impl PartialEq for DBRow {
@@ -68,22 +462,84 @@ impl PartialOrd for DBRow {
     }
 }
 
+/// Exit status for failures outside the database, e.g. of git or the network.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit status for invalid input, the same clap uses for bad arguments.
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_READ_ERROR: i32 = 3;
+pub const EXIT_WRITE_ERROR: i32 = 4;
+pub const EXIT_MISSING_DATABASE: i32 = 5;
+/// A task, view or backup that was asked for doesn't exist.
+pub const EXIT_NOT_FOUND: i32 = 6;
+pub const EXIT_PERMISSION_DENIED: i32 = 7;
+pub const EXIT_CORRUPT_ROW: i32 = 8;
+pub const EXIT_DUPLICATE_ID: i32 = 9;
+
+/// Where and why a store operation failed.
+#[derive(Debug, Default)]
+pub struct ErrorContext {
+    pub message: String,
+    pub path: Option<PathBuf>,
+    /// Line of the file, counting from 1 with the header.
+    pub line: Option<u64>,
+    /// ID of the row the error is about.
+    pub id: Option<u32>,
+    /// The `std::io::Error`, `csv::Error` or other error behind this one.
+    pub source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
 #[derive(Debug)]
 pub enum DBError {
-    ReadError(String),
-    WriteError(String),
-    EmptyDB,
+    ReadError(ErrorContext),
+    WriteError(ErrorContext),
+    /// The operating system refused to open or replace a file.
+    PermissionDenied(ErrorContext),
+    /// A row of the database cannot be parsed.
+    CorruptRow(ErrorContext),
+    /// Two rows have the same ID.
+    DuplicateId(ErrorContext),
+    /// There is no database file, nothing was stored yet.
+    MissingDatabase(PathBuf),
     IDNotFound,
 }
 
-impl std::error::Error for DBError {}
+impl std::error::Error for DBError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        let source = self.context()?.source.as_ref()?;
+        Some(source.as_ref() as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    /// `path:line: message (#id)`, leaving out what isn't known.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}", path.display())?;
+            if let Some(line) = self.line {
+                write!(f, ":{}", line)?;
+            }
+            write!(f, ": ")?;
+        } else if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(id) = self.id {
+            write!(f, " (#{})", id)?;
+        }
+
+        Ok(())
+    }
+}
 
 impl fmt::Display for DBError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            DBError::ReadError(msg) => write!(f, "read error: {}", msg),
-            DBError::WriteError(msg) => write!(f, "write error: {}", msg),
-            DBError::EmptyDB => write!(f, "database empty"),
+            DBError::ReadError(c) => write!(f, "read error: {}", c),
+            DBError::WriteError(c) => write!(f, "write error: {}", c),
+            DBError::PermissionDenied(c) => write!(f, "permission denied: {}", c),
+            DBError::CorruptRow(c) => write!(f, "corrupt row: {}", c),
+            DBError::DuplicateId(c) => write!(f, "duplicate ID: {}", c),
+            DBError::MissingDatabase(path) => write!(f, "no database at {}, nothing stored yet", path.display()),
             DBError::IDNotFound => write!(f, "id was not found"),
         }
     }
@@ -91,18 +547,94 @@ impl fmt::Display for DBError {
 
 impl DBError {
     pub fn new_read_error(msg: &str) -> DBError {
-        return DBError::ReadError(msg.to_string())
+        DBError::ReadError(ErrorContext { message: msg.to_string(), ..Default::default() })
     }
 
     pub fn new_write_error(msg: &str) -> DBError {
-        return DBError::WriteError(msg.to_string())
+        DBError::WriteError(ErrorContext { message: msg.to_string(), ..Default::default() })
     }
 
-    pub fn new_dbnotexist_error() -> DBError {
-        return DBError::EmptyDB
+    pub fn new_dbnotexist_error(path: &Path) -> DBError {
+        DBError::MissingDatabase(path.to_path_buf())
     }
 
     pub fn new_idnotfound_error() -> DBError {
-        return DBError::IDNotFound
+        DBError::IDNotFound
+    }
+
+    /// Reading `path` failed because of `source`, an `std::io::Error` that
+    /// is about permissions gives `PermissionDenied`.
+    pub fn read(path: &Path, source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> DBError {
+        let context = ErrorContext::caused_by(path, source.into());
+        match context.is_permission_denied() {
+            true => DBError::PermissionDenied(context),
+            false => DBError::ReadError(context),
+        }
+    }
+
+    /// Writing `path` failed because of `source`, see `read`.
+    pub fn write(path: &Path, source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> DBError {
+        let context = ErrorContext::caused_by(path, source.into());
+        match context.is_permission_denied() {
+            true => DBError::PermissionDenied(context),
+            false => DBError::WriteError(context),
+        }
+    }
+
+    /// The error about line `line` of its file.
+    pub fn at_line(mut self, line: u64) -> DBError {
+        if let DBError::ReadError(c) | DBError::WriteError(c) | DBError::PermissionDenied(c)
+            | DBError::CorruptRow(c) | DBError::DuplicateId(c) = &mut self {
+            c.line = Some(line);
+        }
+        self
+    }
+
+    /// The context of errors about a file.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            DBError::ReadError(c) | DBError::WriteError(c) | DBError::PermissionDenied(c)
+            | DBError::CorruptRow(c) | DBError::DuplicateId(c) => Some(c),
+            DBError::MissingDatabase(_) | DBError::IDNotFound => None,
+        }
+    }
+
+    /// Exit status of the command line tool on this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            DBError::ReadError(_) => EXIT_READ_ERROR,
+            DBError::WriteError(_) => EXIT_WRITE_ERROR,
+            DBError::MissingDatabase(_) => EXIT_MISSING_DATABASE,
+            DBError::IDNotFound => EXIT_NOT_FOUND,
+            DBError::PermissionDenied(_) => EXIT_PERMISSION_DENIED,
+            DBError::CorruptRow(_) => EXIT_CORRUPT_ROW,
+            DBError::DuplicateId(_) => EXIT_DUPLICATE_ID,
+        }
+    }
+}
+
+impl ErrorContext {
+    fn caused_by(path: &Path, source: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        ErrorContext {
+            message: source.to_string(),
+            path: Some(path.to_path_buf()),
+            source: Some(source),
+            ..Default::default()
+        }
+    }
+
+    fn is_permission_denied(&self) -> bool {
+        let mut cause: Option<&(dyn std::error::Error + 'static)> = self.source.as_deref().map(|s| s as _);
+        while let Some(e) = cause {
+            if let Some(io) = e.downcast_ref::<std::io::Error>() {
+                return io.kind() == std::io::ErrorKind::PermissionDenied;
+            }
+            if let Some(csv::ErrorKind::Io(io)) = e.downcast_ref::<csv::Error>().map(|c| c.kind()) {
+                return io.kind() == std::io::ErrorKind::PermissionDenied;
+            }
+            cause = e.source();
+        }
+
+        false
     }
 }
\ No newline at end of file
@@ -0,0 +1,620 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use chrono::NaiveDate;
+
+use crate::dates::{parse_date, timestamp_to_date, today};
+use crate::models::{DBRow, Priority, Status};
+
+
+/// Parsed filter expression with optional sort and limit clauses, e.g.
+/// `status:open (tag:work or tag:home) due<2026-11-01 "milk" sort:due limit:5`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub filter: Option<Expr>,
+    pub sort: Vec<SortKey>,
+    pub limit: Option<usize>,
+    /// Set when the query asks about snoozed tasks, listings then don't
+    /// hide them.
+    pub include_snoozed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Cond(Condition),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// Todo, in progress or waiting.
+    Open,
+    Status(Status),
+    Tag(String),
+    Context(String),
+    Id(crate::bulk::IdList),
+    Text(String),
+    HasDue(bool),
+    Due(Cmp, NaiveDate),
+    HasPriority(bool),
+    Priority(Cmp, Priority),
+    Updated(Cmp, NaiveDate),
+    /// Still snoozed on the given day.
+    Snoozed(NaiveDate),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Id,
+    Due,
+    Priority,
+    Updated,
+    Task,
+    Status,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub query: String,
+    pub position: usize,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // a position inside a character points at the character
+        let mut position = self.position.min(self.query.len());
+        while !self.query.is_char_boundary(position) {
+            position -= 1;
+        }
+        let column = self.query[..position].chars().count();
+        write!(f, "{}\n  {}\n  {}^", self.message, self.query, " ".repeat(column))
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, ParseError> {
+        Query::parse_with_today(input, today())
+    }
+
+    /// Parses `input` resolving relative dates such as `today` against `today`.
+    pub fn parse_with_today(input: &str, today: NaiveDate) -> Result<Query, ParseError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { input, tokens, pos: 0, today, query: Query::default() };
+
+        let filter = if parser.at_end() { None } else { Some(parser.parse_or()?) };
+        if let Some(t) = parser.peek() {
+            return Err(parser.error("unexpected ')'", t.position));
+        }
+
+        let mut query = parser.query;
+        query.filter = filter.and_then(|f| f);
+
+        Ok(query)
+    }
+
+    /// Builds a query from command line words, quoting words the shell
+    /// already split on whitespace so they stay a single text term.
+    pub fn from_args(args: &[String]) -> Result<Query, ParseError> {
+        Query::parse(&join_args(args))
+    }
+
+    pub fn matches(&self, r: &DBRow) -> bool {
+        match &self.filter {
+            Some(f) => f.matches(r),
+            None => true,
+        }
+    }
+
+    /// Filters, sorts and limits `rows`. Rows keep their incoming order for
+    /// equal sort keys.
+    pub fn apply(&self, rows: Vec<DBRow>) -> Vec<DBRow> {
+        let mut rows: Vec<DBRow> = rows.into_iter().filter(|r| self.matches(r)).collect();
+
+        if !self.sort.is_empty() {
+            rows.sort_by(|a, b| {
+                self.sort.iter()
+                    .map(|k| k.compare(a, b))
+                    .find(|o| *o != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+
+        if let Some(limit) = self.limit {
+            rows.truncate(limit);
+        }
+
+        rows
+    }
+
+    /// Narrows the query with another filter expression.
+    pub fn and(mut self, other: Expr) -> Query {
+        self.filter = Some(match self.filter {
+            Some(f) => Expr::And(Box::new(other), Box::new(f)),
+            None => other,
+        });
+
+        self
+    }
+}
+
+pub fn join_args(args: &[String]) -> String {
+    args.iter()
+        .map(|a| {
+            if a.contains(char::is_whitespace) && !a.contains('"') {
+                format!("\"{}\"", a)
+            } else {
+                a.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Parses a comma separated sort specification such as `due,-priority`.
+pub fn parse_sort(value: &str) -> Result<Vec<SortKey>, String> {
+    value.split(',')
+        .map(|part| {
+            let part = part.trim();
+            let (descending, name) = match part.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, part),
+            };
+            let field = match name.to_lowercase().as_str() {
+                "id" => SortField::Id,
+                "due" => SortField::Due,
+                "priority" | "pri" => SortField::Priority,
+                "updated" | "date" => SortField::Updated,
+                "task" | "text" => SortField::Task,
+                "status" => SortField::Status,
+                _ => return Err(format!("cannot sort by '{}', expected id, due, priority, updated, task or status", name)),
+            };
+
+            Ok(SortKey { field, descending })
+        })
+        .collect()
+}
+
+impl Expr {
+    pub fn matches(&self, r: &DBRow) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(r) && b.matches(r),
+            Expr::Or(a, b) => a.matches(r) || b.matches(r),
+            Expr::Not(e) => !e.matches(r),
+            Expr::Cond(c) => c.matches(r),
+        }
+    }
+}
+
+impl Condition {
+    pub fn matches(&self, r: &DBRow) -> bool {
+        match self {
+            Condition::Open => r.current_status().is_open(),
+            Condition::Status(s) => r.current_status() == *s,
+            Condition::Tag(t) => r.tags.iter().any(|rt| rt.eq_ignore_ascii_case(t)),
+            Condition::Context(c) => r.contexts.iter().any(|rc| rc.eq_ignore_ascii_case(c)),
+            Condition::Id(ids) => ids.contains(r.id),
+            Condition::Text(t) => r.task.to_lowercase().contains(&t.to_lowercase()),
+            Condition::HasDue(has) => r.due.is_some() == *has,
+            Condition::Due(cmp, date) => match r.due {
+                Some(d) => cmp.test(timestamp_to_date(d).cmp(date)),
+                None => false,
+            },
+            Condition::HasPriority(has) => r.priority.is_some() == *has,
+            Condition::Priority(cmp, p) => match r.priority {
+                Some(rp) => cmp.test(rp.cmp(p)),
+                None => false,
+            },
+            Condition::Updated(cmp, date) => cmp.test(timestamp_to_date(r.updatedate).cmp(date)),
+            Condition::Snoozed(date) => r.is_snoozed(*date),
+        }
+    }
+}
+
+impl Cmp {
+    fn test(&self, o: Ordering) -> bool {
+        match self {
+            Cmp::Eq => o == Ordering::Equal,
+            Cmp::Lt => o == Ordering::Less,
+            Cmp::Le => o != Ordering::Greater,
+            Cmp::Gt => o == Ordering::Greater,
+            Cmp::Ge => o != Ordering::Less,
+        }
+    }
+}
+
+impl SortKey {
+    fn compare(&self, a: &DBRow, b: &DBRow) -> Ordering {
+        // rows without a value always go last, whatever the direction
+        let (a_val, b_val) = match self.field {
+            SortField::Due => (a.due, b.due),
+            SortField::Priority => (a.priority.map(|p| p as i64), b.priority.map(|p| p as i64)),
+            _ => (Some(0), Some(0)),
+        };
+        match (a_val, b_val) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Greater,
+            (Some(_), None) => return Ordering::Less,
+            _ => (),
+        }
+
+        let o = match self.field {
+            SortField::Id => a.id.cmp(&b.id),
+            SortField::Due => a.due.cmp(&b.due),
+            SortField::Priority => a.priority.cmp(&b.priority),
+            SortField::Updated => a.updatedate.cmp(&b.updatedate),
+            SortField::Task => a.task.to_lowercase().cmp(&b.task.to_lowercase()),
+            SortField::Status => a.current_status().cmp(&b.current_status()),
+        };
+
+        if self.descending { o.reverse() } else { o }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Quoted(String),
+    LParen,
+    RParen,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+    /// Where each byte of a word's text is in the input, and then where
+    /// the word ends. Quotes make the two differ.
+    offsets: Vec<usize>,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens: Vec<Token> = vec![];
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(i, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        match c {
+            '(' => {
+                chars.next();
+                tokens.push(Token { kind: TokenKind::LParen, position: i, offsets: vec![] });
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token { kind: TokenKind::RParen, position: i, offsets: vec![] });
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                read_quoted(input, i, &mut chars, &mut text, &mut vec![])?;
+                tokens.push(Token { kind: TokenKind::Quoted(text), position: i, offsets: vec![] });
+            }
+            _ => {
+                let mut word = String::new();
+                let mut offsets: Vec<usize> = vec![];
+                while let Some(&(j, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' { break }
+                    chars.next();
+                    if c == '"' {
+                        read_quoted(input, j, &mut chars, &mut word, &mut offsets)?;
+                    } else {
+                        push_char(&mut word, &mut offsets, j, c);
+                    }
+                }
+                offsets.push(chars.peek().map(|&(j, _)| j).unwrap_or(input.len()));
+                tokens.push(Token { kind: TokenKind::Word(word), position: i, offsets });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn push_char(text: &mut String, offsets: &mut Vec<usize>, at: usize, c: char) {
+    text.push(c);
+    offsets.extend((0..c.len_utf8()).map(|k| at + k));
+}
+
+/// Reads up to the closing quote into `text`, with where each byte was
+/// in `offsets`.
+fn read_quoted(
+    input: &str,
+    start: usize,
+    chars: &mut std::iter::Peekable<std::str::CharIndices>,
+    text: &mut String,
+    offsets: &mut Vec<usize>,
+) -> Result<(), ParseError> {
+    for (j, c) in chars.by_ref() {
+        if c == '"' {
+            return Ok(());
+        }
+        push_char(text, offsets, j, c);
+    }
+
+    Err(ParseError {
+        message: "unterminated quote".to_string(),
+        query: input.to_string(),
+        position: start,
+    })
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    today: NaiveDate,
+    query: Query,
+}
+
+/// Expressions are `None` when a term only carried a sort, limit or
+/// `snoozed:any` clause.
+type Parsed = Option<Expr>;
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token { kind: TokenKind::Word(w), .. }) => w.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+
+    fn error(&self, message: &str, position: usize) -> ParseError {
+        ParseError {
+            message: message.to_string(),
+            query: self.input.to_string(),
+            position,
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Parsed, ParseError> {
+        let mut left = self.parse_and()?;
+
+        while self.is_keyword("or") {
+            let position = self.peek().unwrap().position;
+            self.pos += 1;
+            if self.at_end() {
+                return Err(self.error("expected a condition after 'or'", position));
+            }
+            let right = self.parse_and()?;
+            left = match (left, right) {
+                (Some(l), Some(r)) => Some(Expr::Or(Box::new(l), Box::new(r))),
+                _ => return Err(self.error("'or' needs a condition on both sides", position)),
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Parsed, ParseError> {
+        let mut left = self.parse_unary()?;
+
+        loop {
+            match self.peek() {
+                None => break,
+                Some(Token { kind: TokenKind::RParen, .. }) => break,
+                _ if self.is_keyword("or") => break,
+                _ => (),
+            }
+            if self.is_keyword("and") {
+                let position = self.peek().unwrap().position;
+                self.pos += 1;
+                if self.at_end() {
+                    return Err(self.error("expected a condition after 'and'", position));
+                }
+            }
+
+            let right = self.parse_unary()?;
+            left = match (left, right) {
+                (Some(l), Some(r)) => Some(Expr::And(Box::new(l), Box::new(r))),
+                (l, None) => l,
+                (None, r) => r,
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Parsed, ParseError> {
+        let token = match self.peek() {
+            Some(t) => t.clone(),
+            None => return Err(self.error("expected a condition", self.input.len())),
+        };
+
+        match token.kind {
+            TokenKind::LParen => {
+                self.pos += 1;
+                let inner = self.parse_or()?;
+                match self.peek() {
+                    Some(Token { kind: TokenKind::RParen, .. }) => self.pos += 1,
+                    _ => return Err(self.error("missing closing ')'", token.position)),
+                }
+                Ok(inner)
+            }
+            TokenKind::RParen => Err(self.error("unexpected ')'", token.position)),
+            TokenKind::Quoted(text) => {
+                self.pos += 1;
+                Ok(Some(Expr::Cond(Condition::Text(text))))
+            }
+            TokenKind::Word(w) if w.eq_ignore_ascii_case("not") => {
+                self.pos += 1;
+                if self.at_end() {
+                    return Err(self.error("expected a condition after 'not'", token.position));
+                }
+                self.negate(token.position)
+            }
+            TokenKind::Word(w) if w.eq_ignore_ascii_case("and") || w.eq_ignore_ascii_case("or") => {
+                Err(self.error(&format!("'{}' needs a condition on both sides", w), token.position))
+            }
+            TokenKind::Word(w) if w.len() > 1 && w.starts_with('-') => {
+                self.tokens[self.pos].kind = TokenKind::Word(w[1..].to_string());
+                self.tokens[self.pos].position += 1;
+                self.tokens[self.pos].offsets.remove(0);
+                self.negate(token.position)
+            }
+            TokenKind::Word(w) => {
+                self.pos += 1;
+                self.parse_term(&w, &token.offsets)
+            }
+        }
+    }
+
+    fn negate(&mut self, position: usize) -> Result<Parsed, ParseError> {
+        match self.parse_unary()? {
+            Some(e) => Ok(Some(Expr::Not(Box::new(e)))),
+            None => Err(self.error("sort, limit and snoozed:any clauses cannot be negated", position)),
+        }
+    }
+
+    /// `offsets` are those of the word's token, positions in `word` are
+    /// mapped through them to the input.
+    fn parse_term(&mut self, word: &str, offsets: &[usize]) -> Result<Parsed, ParseError> {
+        let at = |i: usize| offsets.get(i).or(offsets.last()).copied().unwrap_or_default();
+        let position = at(0);
+        if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+            return Ok(Some(Expr::Cond(Condition::Context(context.to_string()))));
+        }
+
+        let split = match word.find([':', '<', '>', '=']) {
+            Some(i) if i > 0 => i,
+            _ => return Ok(Some(Expr::Cond(Condition::Text(word.to_string())))),
+        };
+
+        let field = word[..split].to_lowercase();
+        let rest = &word[split..];
+        let (cmp, value) = if let Some(v) = rest.strip_prefix("<=") {
+            (Cmp::Le, v)
+        } else if let Some(v) = rest.strip_prefix(">=") {
+            (Cmp::Ge, v)
+        } else if let Some(v) = rest.strip_prefix('<') {
+            (Cmp::Lt, v)
+        } else if let Some(v) = rest.strip_prefix('>') {
+            (Cmp::Gt, v)
+        } else {
+            (Cmp::Eq, &rest[1..])
+        };
+
+        let value_position = at(word.len() - value.len());
+        if value.is_empty() {
+            return Err(self.error(&format!("missing value for '{}'", field), value_position));
+        }
+
+        let only_eq = |p: &Parser, field: &str| -> Result<(), ParseError> {
+            match cmp {
+                Cmp::Eq => Ok(()),
+                _ => Err(p.error(&format!("'{}' only supports ':'", field), at(split))),
+            }
+        };
+
+        let cond = match field.as_str() {
+            "status" | "is" => {
+                only_eq(self, &field)?;
+                match value.to_lowercase().as_str() {
+                    "open" | "incomplete" => Condition::Open,
+                    v => Condition::Status(v.parse::<Status>().map_err(|_| self.error(
+                        &format!("unknown status '{}', expected open, todo, in-progress, waiting, done or cancelled", value),
+                        value_position,
+                    ))?),
+                }
+            }
+            "tag" => {
+                only_eq(self, &field)?;
+                Condition::Tag(value.to_string())
+            }
+            "context" | "ctx" => {
+                only_eq(self, &field)?;
+                Condition::Context(value.trim_start_matches('@').to_string())
+            }
+            "id" => {
+                only_eq(self, &field)?;
+                let ids = crate::bulk::parse_id_list(value)
+                    .map_err(|e| self.error(&e, value_position))?;
+                Condition::Id(ids)
+            }
+            "text" => {
+                only_eq(self, &field)?;
+                Condition::Text(value.to_string())
+            }
+            "due" => match value.to_lowercase().as_str() {
+                "none" | "any" => {
+                    only_eq(self, &field)?;
+                    Condition::HasDue(value.eq_ignore_ascii_case("any"))
+                }
+                _ => Condition::Due(cmp, self.parse_date(value, value_position)?),
+            },
+            "updated" => Condition::Updated(cmp, self.parse_date(value, value_position)?),
+            "priority" | "pri" => match value.to_lowercase().as_str() {
+                "none" | "any" => {
+                    only_eq(self, &field)?;
+                    Condition::HasPriority(value.eq_ignore_ascii_case("any"))
+                }
+                _ => Condition::Priority(cmp, value.parse::<Priority>()
+                    .map_err(|e| self.error(&e, value_position))?),
+            },
+            "snoozed" => {
+                only_eq(self, &field)?;
+                self.query.include_snoozed = true;
+                let snoozed = Expr::Cond(Condition::Snoozed(self.today));
+                return match value.to_lowercase().as_str() {
+                    "yes" => Ok(Some(snoozed)),
+                    "no" => Ok(Some(Expr::Not(Box::new(snoozed)))),
+                    "any" => Ok(None),
+                    _ => Err(self.error(&format!("unknown value '{}', expected yes, no or any", value), value_position)),
+                };
+            }
+            "sort" => {
+                only_eq(self, &field)?;
+                self.query.sort = parse_sort(value)
+                    .map_err(|e| self.error(&e, value_position))?;
+                return Ok(None);
+            }
+            "limit" => {
+                only_eq(self, &field)?;
+                self.query.limit = Some(value.parse::<usize>()
+                    .map_err(|_| self.error(&format!("'{}' is not a valid limit", value), value_position))?);
+                return Ok(None);
+            }
+            _ if field.chars().all(|c| c.is_ascii_alphabetic()) => {
+                return Err(self.error(&format!("unknown field '{}'", field), position));
+            }
+            _ => Condition::Text(word.to_string()),
+        };
+
+        Ok(Some(Expr::Cond(cond)))
+    }
+
+    fn parse_date(&self, value: &str, position: usize) -> Result<NaiveDate, ParseError> {
+        parse_date(value, self.today).map_err(|e| self.error(&e, position))
+    }
+}
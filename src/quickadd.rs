@@ -0,0 +1,211 @@
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+use crate::dates::{date_to_timestamp, local_timestamp, parse_date};
+use crate::models::{Priority, Recurrence};
+
+
+/// Fields pulled out of a quick-add line such as
+/// `Call bob tomorrow 3pm +work !high @phone every monday`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuickAdd {
+    pub task: String,
+    pub tags: Vec<String>,
+    pub contexts: Vec<String>,
+    pub priority: Option<Priority>,
+    pub due: Option<i64>,
+    pub recurrence: Option<Recurrence>,
+}
+
+/// Parses the words of a quick-add line. Recognised are:
+///
+/// - `+tag`, `@context` and `!priority`, e.g. `!high` or `!h`
+/// - a due date: `today`, `tomorrow`, a weekday name, `next week`,
+///   `in 3 days`, `in 2 weeks`, `YYYY-MM-DD` or `due:WHEN` taking any date
+///   `--due` takes, optionally after `on`, `by` or `due`
+/// - a time: `3pm`, `3:30pm` or `15:00`, optionally after `at`, on the due
+///   date or today
+/// - a recurrence: `every day`, `every 2 weeks`, `every monday`, which
+///   also sets the due date when none is given
+///
+/// Only the first date, time and recurrence are taken, everything else
+/// stays in the task text in its order.
+pub fn parse(words: &[String], now: NaiveDateTime) -> Result<QuickAdd, String> {
+    let today = now.date();
+    let tokens: Vec<&str> = words.iter().flat_map(|w| w.split_whitespace()).collect();
+
+    let mut result = QuickAdd::default();
+    let mut text: Vec<&str> = vec![];
+    let mut date: Option<NaiveDate> = None;
+    let mut time: Option<NaiveTime> = None;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        let lower = token.to_lowercase();
+
+        if lower == "every" && result.recurrence.is_none() {
+            if let Some((recurrence, used)) = parse_every(&tokens[i + 1..]) {
+                result.recurrence = Some(recurrence);
+                i += 1 + used;
+                continue;
+            }
+        }
+        if let Some(tag) = token.strip_prefix('+').filter(|t| is_name(t)) {
+            result.tags.push(tag.to_string());
+            i += 1;
+            continue;
+        }
+        if let Some(context) = token.strip_prefix('@').filter(|c| is_name(c)) {
+            result.contexts.push(context.to_lowercase());
+            i += 1;
+            continue;
+        }
+        if let Some(priority) = token.strip_prefix('!').and_then(|p| p.parse::<Priority>().ok()) {
+            result.priority = Some(priority);
+            i += 1;
+            continue;
+        }
+        if let Some(value) = lower.strip_prefix("due:") {
+            date = Some(parse_date(value, today)?);
+            i += 1;
+            continue;
+        }
+
+        // a connecting word only goes when what follows is taken
+        let skip = match lower.as_str() {
+            "on" | "by" | "due" if date.is_none() => 1,
+            "at" if time.is_none() => 1,
+            _ => 0,
+        };
+        let rest = &tokens[i + skip..];
+        if date.is_none() && lower != "at" {
+            if let Some((d, used)) = parse_date_words(rest, today) {
+                date = Some(d);
+                i += skip + used;
+                continue;
+            }
+        }
+        if time.is_none() && !matches!(lower.as_str(), "on" | "by" | "due") {
+            if let Some(t) = rest.first().and_then(|t| parse_time(t)) {
+                time = Some(t);
+                i += skip + 1;
+                continue;
+            }
+        }
+
+        text.push(token);
+        i += 1;
+    }
+
+    result.task = text.join(" ");
+    if result.task.is_empty() {
+        return Err("the task text is empty".to_string());
+    }
+
+    if date.is_none() {
+        date = match result.recurrence {
+            Some(Recurrence::Weekday(w)) => next_weekday(today, w, true),
+            Some(_) => Some(today),
+            None if time.is_some() => Some(today),
+            None => None,
+        };
+    }
+    result.due = date.map(|d| match time {
+        Some(t) => local_timestamp(d.and_time(t)),
+        None => date_to_timestamp(d),
+    });
+
+    Ok(result)
+}
+
+fn is_name(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '/')
+}
+
+/// Date at the start of `tokens` and the number of tokens it took.
+fn parse_date_words(tokens: &[&str], today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    let word = |i: usize| tokens.get(i).map(|t| t.to_lowercase());
+
+    match word(0)?.as_str() {
+        "today" | "tonight" => Some((today, 1)),
+        "tomorrow" => Some((today + Days::new(1), 1)),
+        "next" => match word(1)?.as_str() {
+            "week" => Some((next_weekday(today, Weekday::Mon, false)?, 2)),
+            w => Some((next_weekday(today, full_weekday(w)?, false)?, 2)),
+        },
+        "in" => {
+            let n: u64 = word(1)?.parse().ok()?;
+            let days = match word(2)?.trim_end_matches('s') {
+                "day" => n,
+                "week" => n.checked_mul(7)?,
+                _ => return None,
+            };
+            Some((today.checked_add_days(Days::new(days))?, 3))
+        }
+        w => {
+            if let Some(weekday) = full_weekday(w) {
+                return Some((next_weekday(today, weekday, false)?, 1));
+            }
+            NaiveDate::parse_from_str(w, "%Y-%m-%d").ok().map(|d| (d, 1))
+        }
+    }
+}
+
+/// Weekday from its full name only, `sat` or `sun` are too likely words.
+fn full_weekday(word: &str) -> Option<Weekday> {
+    if word.len() < 6 {
+        return None;
+    }
+    word.parse::<Weekday>().ok()
+}
+
+/// The next `weekday` after `today`, or today itself when `or_today`.
+fn next_weekday(today: NaiveDate, weekday: Weekday, or_today: bool) -> Option<NaiveDate> {
+    if or_today && today.weekday() == weekday {
+        return Some(today);
+    }
+    Recurrence::Weekday(weekday).next(today)
+}
+
+/// Parses `3pm`, `3:30pm`, `12am` or `15:00`. Bare numbers are not times.
+fn parse_time(token: &str) -> Option<NaiveTime> {
+    let lower = token.to_lowercase();
+
+    let (clock, offset) = if let Some(c) = lower.strip_suffix("am") {
+        (c, 0)
+    } else if let Some(c) = lower.strip_suffix("pm") {
+        (c, 12)
+    } else if lower.contains(':') {
+        return NaiveTime::parse_from_str(&lower, "%H:%M").ok();
+    } else {
+        return None;
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((h, m)) if m.len() == 2 => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        Some(_) => return None,
+        None => (clock.parse::<u32>().ok()?, 0),
+    };
+    if !(1..=12).contains(&hour) {
+        return None;
+    }
+
+    NaiveTime::from_hms_opt(hour % 12 + offset, minute, 0)
+}
+
+/// Recurrence after `every` and the number of tokens it took.
+fn parse_every(tokens: &[&str]) -> Option<(Recurrence, usize)> {
+    if tokens.len() >= 2 {
+        if let Ok(r) = format!("every {} {}", tokens[0], tokens[1]).parse::<Recurrence>() {
+            return Some((r, 2));
+        }
+    }
+
+    let r = format!("every {}", tokens.first()?).parse::<Recurrence>().ok()?;
+    // `every daily` is not a phrase
+    if ["daily", "weekly", "monthly", "yearly"].contains(&tokens[0].to_lowercase().as_str()) {
+        return None;
+    }
+
+    Some((r, 1))
+}
@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::dates::{date_to_timestamp, format_due, timestamp_to_date};
+use crate::models::{DBError, DBRow};
+
+
+/// Open tasks due before `now + window`, overdue ones included, earliest
+/// first.
+pub fn due_within(rows: &[DBRow], now: i64, window: i64) -> Vec<DBRow> {
+    let mut due: Vec<DBRow> = rows.iter()
+        .filter(|r| r.current_status().is_open() && r.due.map(|d| d <= now.saturating_add(window)).unwrap_or(false))
+        .cloned()
+        .collect();
+    due.sort_by_key(|r| (r.due, r.id));
+
+    due
+}
+
+/// Subject and body of a reminder about `rows`.
+pub fn format_message(rows: &[DBRow], now: i64) -> (String, String) {
+    let subject = match rows.len() {
+        1 => "1 task due".to_string(),
+        n => format!("{} tasks due", n),
+    };
+
+    let body: Vec<String> = rows.iter()
+        .map(|r| {
+            let due = r.due.unwrap_or_default();
+            // a task due on a date without a time is overdue from the next day on
+            let date = timestamp_to_date(due);
+            let overdue = if date_to_timestamp(date) == due {
+                date < timestamp_to_date(now)
+            } else {
+                due < now
+            };
+            let overdue = if overdue { ", overdue" } else { "" };
+            format!("#{} {} (due {}{})", r.id, r.task, format_due(due), overdue)
+        })
+        .collect();
+
+    (subject, body.join("\n"))
+}
+
+/// Delivers reminders somewhere.
+pub trait Notifier {
+    fn notify(&mut self, rows: &[DBRow], now: i64) -> Result<(), String>;
+}
+
+/// Prints reminders, e.g. for cron to mail them.
+pub struct StdoutNotifier<W: Write> {
+    pub out: W,
+}
+
+impl<W: Write> Notifier for StdoutNotifier<W> {
+    fn notify(&mut self, rows: &[DBRow], now: i64) -> Result<(), String> {
+        let (subject, body) = format_message(rows, now);
+        writeln!(self.out, "{}:\n{}", subject, body).map_err(|e| e.to_string())
+    }
+}
+
+/// Runs a desktop notification command, e.g. `notify-send`, with the
+/// subject and body as its last two arguments.
+pub struct CommandNotifier {
+    pub command: String,
+}
+
+impl Notifier for CommandNotifier {
+    fn notify(&mut self, rows: &[DBRow], now: i64) -> Result<(), String> {
+        let (subject, body) = format_message(rows, now);
+        let (program, args) = split_command(&self.command)?;
+
+        let status = Command::new(program)
+            .args(args)
+            .arg(subject)
+            .arg(body)
+            .status()
+            .map_err(|e| format!("cannot run {}: {}", program, e))?;
+        if !status.success() {
+            return Err(format!("{} failed with {}", program, status));
+        }
+
+        Ok(())
+    }
+}
+
+/// Posts the reminders as JSON to a plain `http://` URL.
+pub struct WebhookNotifier {
+    pub url: String,
+}
+
+impl Notifier for WebhookNotifier {
+    fn notify(&mut self, rows: &[DBRow], now: i64) -> Result<(), String> {
+        let (subject, _) = format_message(rows, now);
+        let body = json!({ "subject": subject, "tasks": rows }).to_string();
+
+        let (host, path) = parse_http_url(&self.url)?;
+        let mut stream = TcpStream::connect(&host)
+            .map_err(|e| format!("cannot connect to {}: {}", host, e))?;
+        stream.set_read_timeout(Some(Duration::from_secs(10)))
+            .map_err(|e| e.to_string())?;
+
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            path, host, body.len(), body,
+        );
+        stream.write_all(request.as_bytes()).map_err(|e| e.to_string())?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).map_err(|e| e.to_string())?;
+        let status = response.split(' ').nth(1).unwrap_or("");
+        if !status.starts_with('2') {
+            return Err(format!("{} answered '{}'", self.url, response.lines().next().unwrap_or("")));
+        }
+
+        Ok(())
+    }
+}
+
+/// Hands a mail to a sendmail compatible program, which reads the message
+/// with its headers from stdin.
+pub struct MailNotifier {
+    pub sendmail: String,
+    pub to: String,
+}
+
+impl Notifier for MailNotifier {
+    fn notify(&mut self, rows: &[DBRow], now: i64) -> Result<(), String> {
+        let (subject, body) = format_message(rows, now);
+        let (program, args) = split_command(&self.sendmail)?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .arg(&self.to)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("cannot run {}: {}", program, e))?;
+
+        let message = format!("To: {}\nSubject: {}\nContent-Type: text/plain; charset=utf-8\n\n{}\n", self.to, subject, body);
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(message.as_bytes()).map_err(|e| e.to_string())?;
+        }
+
+        let status = child.wait().map_err(|e| e.to_string())?;
+        if !status.success() {
+            return Err(format!("{} failed with {}", program, status));
+        }
+
+        Ok(())
+    }
+}
+
+fn split_command(command: &str) -> Result<(&str, Vec<&str>), String> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or("empty command")?;
+
+    Ok((program, parts.collect()))
+}
+
+/// Splits `http://host:port/path` into `host:port` and `/path`.
+fn parse_http_url(url: &str) -> Result<(String, String), String> {
+    let rest = url.strip_prefix("http://")
+        .ok_or_else(|| format!("'{}' is not supported, only http:// URLs are", url))?;
+
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    if host.is_empty() {
+        return Err(format!("'{}' has no host", url));
+    }
+    let host = if host.contains(':') { host.to_string() } else { format!("{}:80", host) };
+
+    Ok((host, path.to_string()))
+}
+
+/// Remembers which reminders went out, per task ID and the due date they
+/// were about, so a task is announced again only when its due date changes.
+pub struct SentLog {
+    path: PathBuf,
+}
+
+impl SentLog {
+    pub fn new(path: PathBuf) -> Self {
+        SentLog { path }
+    }
+
+    pub fn load(&self) -> Result<BTreeMap<u32, i64>, DBError> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| DBError::read(&self.path, e))?;
+
+        serde_json::from_str(&content)
+            .map_err(|e| DBError::read(&self.path, e))
+    }
+
+    /// The rows not announced yet.
+    pub fn unsent(&self, rows: &[DBRow]) -> Result<Vec<DBRow>, DBError> {
+        let sent = self.load()?;
+
+        Ok(rows.iter()
+            .filter(|r| sent.get(&r.id) != r.due.as_ref())
+            .cloned()
+            .collect())
+    }
+
+    /// Records `sent` as announced. Entries of tasks that are no longer
+    /// among `due` are dropped, so a reopened task is announced again.
+    pub fn record(&self, sent: &[DBRow], due: &[DBRow]) -> Result<(), DBError> {
+        let mut log = self.load()?;
+        log.retain(|id, _| due.iter().any(|r| r.id == *id));
+        for r in sent {
+            if let Some(d) = r.due {
+                log.insert(r.id, d);
+            }
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| DBError::write(parent, e))?;
+        }
+
+        let content = serde_json::to_string_pretty(&log)
+            .map_err(|e| DBError::write(&self.path, e))?;
+
+        let temp_path = self.path.with_extension("tmp");
+        fs::write(&temp_path, content)
+            .map_err(|e| DBError::write(&temp_path, e))?;
+        fs::rename(&temp_path, &self.path)
+            .map_err(|e| DBError::write(&self.path, e))
+    }
+}
@@ -0,0 +1,328 @@
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::Local;
+use serde_json::{json, Map, Value};
+
+use crate::bulk::{self, BulkAction, Selection};
+use crate::models::{DBError, DBReader, DBRow, DBWriter};
+use crate::query::{Condition, Expr, Query};
+use crate::server::etag;
+use crate::utils::fnv1a;
+use crate::workflow::Workflow;
+
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const STORAGE_ERROR: i64 = -32000;
+pub const NOT_FOUND: i64 = -32001;
+pub const CONFLICT: i64 = -32002;
+
+/// Notification sent whenever the store content changes, whoever changed it.
+pub const CHANGED_NOTIFICATION: &str = "tasks/changed";
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: &str) -> Self {
+        RpcError { code, message: message.to_string() }
+    }
+
+    fn params(message: &str) -> Self {
+        RpcError::new(INVALID_PARAMS, message)
+    }
+}
+
+impl From<DBError> for RpcError {
+    fn from(e: DBError) -> Self {
+        match e {
+            DBError::IDNotFound => RpcError::new(NOT_FOUND, &e.to_string()),
+            DBError::DuplicateId(_) => RpcError::new(CONFLICT, &e.to_string()),
+            _ => RpcError::new(STORAGE_ERROR, &e.to_string()),
+        }
+    }
+}
+
+/// JSON-RPC 2.0 session over one open store. Requests and responses are
+/// newline delimited JSON.
+pub struct RpcSession<T: DBReader + DBWriter> {
+    repo: Arc<T>,
+    fingerprint: Arc<Mutex<u64>>,
+    workflow: Arc<Workflow>,
+}
+
+impl<T: DBReader + DBWriter> RpcSession<T> {
+    pub fn new(repo: T) -> Self {
+        let session = RpcSession {
+            repo: Arc::new(repo),
+            fingerprint: Arc::new(Mutex::new(0)),
+            workflow: Arc::new(Workflow::default()),
+        };
+        session.changed();
+
+        session
+    }
+
+    /// Checks status changes against `workflow` instead of the default one.
+    pub fn with_workflow(mut self, workflow: Workflow) -> Self {
+        self.workflow = Arc::new(workflow);
+        self
+    }
+
+    /// Handles one input line. Returns the response to write, `None` for
+    /// notifications, and whether the store was changed.
+    pub fn handle_line(&self, line: &str) -> (Option<Value>, bool) {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(e) => return (Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, &e.to_string()))), false),
+        };
+
+        match message {
+            Value::Array(batch) if batch.is_empty() => {
+                (Some(error_response(Value::Null, RpcError::new(INVALID_REQUEST, "empty batch"))), false)
+            }
+            Value::Array(batch) => {
+                let mut responses: Vec<Value> = vec![];
+                let mut changed = false;
+                for m in batch {
+                    let (response, c) = self.handle_message(m);
+                    responses.extend(response);
+                    changed |= c;
+                }
+                let response = if responses.is_empty() { None } else { Some(Value::Array(responses)) };
+                (response, changed)
+            }
+            m => self.handle_message(m),
+        }
+    }
+
+    fn handle_message(&self, message: Value) -> (Option<Value>, bool) {
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(|m| m.as_str());
+
+        let method = match (message.get("jsonrpc").and_then(|v| v.as_str()), method) {
+            (Some("2.0"), Some(m)) => m.to_string(),
+            _ => return (Some(error_response(id.unwrap_or(Value::Null), RpcError::new(INVALID_REQUEST, "not a JSON-RPC 2.0 request"))), false),
+        };
+        let params = match message.get("params") {
+            None | Some(Value::Null) => Map::new(),
+            Some(Value::Object(p)) => p.clone(),
+            Some(_) => return (Some(error_response(id.unwrap_or(Value::Null), RpcError::params("params must be an object"))), false),
+        };
+
+        let result = self.call(&method, params);
+        let changed = result.is_ok() && matches!(method.as_str(), "add" | "update" | "complete" | "delete") && self.changed();
+
+        // requests without an id are notifications and get no answer
+        let response = id.map(|id| match result {
+            Ok(value) => json!({ "jsonrpc": "2.0", "id": id, "result": value }),
+            Err(e) => error_response(id, e),
+        });
+
+        (response, changed)
+    }
+
+    fn call(&self, method: &str, params: Map<String, Value>) -> Result<Value, RpcError> {
+        let repo = self.repo.as_ref();
+
+        match method {
+            "list" => {
+                let query = Query::parse(str_param(&params, "query")?.unwrap_or(""))
+                    .map_err(|e| RpcError::params(&e.to_string()))?;
+                Ok(json!(query.apply(read_all(repo)?)))
+            }
+            "search" => {
+                let text = str_param(&params, "text")?
+                    .ok_or_else(|| RpcError::params("missing 'text'"))?;
+                let query = Query::default().and(Expr::Cond(Condition::Text(text.to_string())));
+                Ok(json!(query.apply(read_all(repo)?)))
+            }
+            "get" => {
+                let id = id_param(&params)?;
+                match repo.read_one(id) {
+                    Ok(Some(r)) => Ok(row_result(&r)),
+                    Ok(None) | Err(DBError::MissingDatabase(_)) => Err(RpcError::from(DBError::IDNotFound)),
+                    Err(e) => Err(e.into()),
+                }
+            }
+            "add" => {
+                let now = Local::now().timestamp();
+                let base = DBRow {
+                    id: repo.get_next_id(),
+                    updatedate: now,
+                    created: Some(now),
+                    ..Default::default()
+                };
+                let r = base.with_fields(params, now).map_err(|e| RpcError::params(&e))?;
+                repo.add(&r)?;
+                Ok(row_result(&r))
+            }
+            "update" => {
+                let id = id_param(&params)?;
+                let current = self.current_row(id, &params)?;
+
+                let mut fields = params;
+                fields.remove("etag");
+                let now = Local::now().timestamp();
+                let mut r = current.with_fields(fields, now).map_err(|e| RpcError::params(&e))?;
+                r.updatedate = now;
+                repo.update(&r)?;
+                Ok(row_result(&r))
+            }
+            "complete" => {
+                let ids: Vec<u32> = match params.get("ids") {
+                    Some(v) => serde_json::from_value(v.clone())
+                        .map_err(|_| RpcError::params("'ids' must be a list of IDs"))?,
+                    None => vec![id_param(&params)?],
+                };
+                let completed = match params.get("completed") {
+                    None => true,
+                    Some(v) => v.as_bool().ok_or_else(|| RpcError::params("'completed' must be a boolean"))?,
+                };
+
+                let action = if completed { BulkAction::Complete } else { BulkAction::Uncomplete };
+                let selection = Selection { ids: Some(ids.into()), ..Default::default() };
+                let outcomes = bulk::apply(repo, &selection, action, &self.workflow)?;
+                Ok(json!(outcomes.iter()
+                    .map(|(id, o)| json!({ "id": id, "outcome": o.to_string() }))
+                    .collect::<Vec<Value>>()))
+            }
+            "delete" => {
+                let id = id_param(&params)?;
+                self.current_row(id, &params)?;
+                repo.delete(id)?;
+                Ok(json!({ "id": id }))
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, &format!("unknown method '{}'", method))),
+        }
+    }
+
+    /// Loads the row a write targets, checking the optional `etag` param.
+    fn current_row(&self, id: u32, params: &Map<String, Value>) -> Result<DBRow, RpcError> {
+        let current = match self.repo.read_one(id) {
+            Ok(Some(r)) => r,
+            Ok(None) | Err(DBError::MissingDatabase(_)) => return Err(DBError::IDNotFound.into()),
+            Err(e) => return Err(e.into()),
+        };
+
+        if let Some(expected) = str_param(params, "etag")? {
+            if expected != etag(&current) {
+                return Err(RpcError::new(CONFLICT, "task was changed by someone else"));
+            }
+        }
+
+        Ok(current)
+    }
+
+    /// Updates the stored fingerprint, returns true when the content differs
+    /// from what was seen last.
+    pub fn changed(&self) -> bool {
+        let current = match self.repo.read_all() {
+            Ok(rows) => fnv1a(json!(rows).to_string().as_bytes()),
+            Err(_) => 0,
+        };
+
+        let mut last = self.fingerprint.lock().unwrap();
+        let changed = *last != current;
+        *last = current;
+
+        changed
+    }
+}
+
+impl<T: DBReader + DBWriter + Send + Sync + 'static> RpcSession<T> {
+    /// Reads requests until the input ends. A background thread checks the
+    /// store every `poll` and sends a change notification when another
+    /// process modified it.
+    pub fn run<R: BufRead, W: Write + Send + 'static>(self, input: R, output: W, poll: Duration) {
+        let output = Arc::new(Mutex::new(output));
+
+        let watcher = RpcSession {
+            repo: Arc::clone(&self.repo),
+            fingerprint: Arc::clone(&self.fingerprint),
+            workflow: Arc::clone(&self.workflow),
+        };
+        let watcher_output = Arc::clone(&output);
+        let done = Arc::new(AtomicBool::new(false));
+        let watcher_done = Arc::clone(&done);
+        thread::spawn(move || loop {
+            thread::sleep(poll);
+            if watcher_done.load(Ordering::Relaxed) {
+                break;
+            }
+            if watcher.changed() {
+                write_line(&watcher_output, &changed_notification());
+            }
+        });
+
+        for line in input.lines() {
+            let line = match line {
+                Ok(l) => l,
+                Err(_) => break,
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (response, changed) = self.handle_line(&line);
+            if let Some(response) = response {
+                write_line(&output, &response);
+            }
+            if changed {
+                write_line(&output, &changed_notification());
+            }
+        }
+
+        // the watcher exits on its next wake up
+        done.store(true, Ordering::Relaxed);
+    }
+}
+
+pub fn changed_notification() -> Value {
+    json!({ "jsonrpc": "2.0", "method": CHANGED_NOTIFICATION, "params": {} })
+}
+
+fn write_line<W: Write>(output: &Mutex<W>, value: &Value) {
+    let mut out = output.lock().unwrap();
+    let _ = writeln!(out, "{}", value);
+    let _ = out.flush();
+}
+
+fn error_response(id: Value, e: RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": e.code, "message": e.message } })
+}
+
+fn row_result(r: &DBRow) -> Value {
+    json!({ "task": r, "etag": etag(r) })
+}
+
+fn read_all<T: DBReader>(repo: &T) -> Result<Vec<DBRow>, RpcError> {
+    match repo.read_all() {
+        Ok(rows) => Ok(rows),
+        Err(DBError::MissingDatabase(_)) => Ok(vec![]),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn id_param(params: &Map<String, Value>) -> Result<u32, RpcError> {
+    params.get("id")
+        .and_then(|v| v.as_u64())
+        .and_then(|v| u32::try_from(v).ok())
+        .ok_or_else(|| RpcError::params("missing or invalid 'id'"))
+}
+
+fn str_param<'a>(params: &'a Map<String, Value>, name: &str) -> Result<Option<&'a str>, RpcError> {
+    match params.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(_) => Err(RpcError::params(&format!("'{}' must be a string", name))),
+    }
+}
@@ -0,0 +1,288 @@
+use chrono::Local;
+use serde_json::{json, Value};
+
+use crate::models::{DBError, DBReader, DBRow, DBWriter};
+use crate::query::Query;
+use crate::utils::fnv1a;
+
+
+/// HTTP request reduced to what the API needs, so routing can be tested
+/// without a socket.
+#[derive(Debug, Clone, Default)]
+pub struct ApiRequest {
+    pub method: String,
+    /// Path including the query string, e.g. `/tasks?q=status:open`.
+    pub url: String,
+    pub if_match: Option<String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl ApiResponse {
+    fn json(status: u16, body: &Value) -> Self {
+        ApiResponse {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: body.to_string(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        ApiResponse::json(status, &json!({ "error": message }))
+    }
+
+    fn row(status: u16, r: &DBRow) -> Self {
+        let mut response = ApiResponse::json(status, &json!(r));
+        response.headers.push(("ETag".to_string(), etag(r)));
+        response
+    }
+
+    fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Status code an error from the store is reported with. A store that
+/// can't be read or written is unavailable, 500 is left for bugs.
+pub fn error_status(e: &DBError) -> u16 {
+    match e {
+        DBError::IDNotFound => 404,
+        DBError::MissingDatabase(_) => 404,
+        DBError::DuplicateId(_) => 409,
+        DBError::ReadError(_) | DBError::WriteError(_) | DBError::PermissionDenied(_) | DBError::CorruptRow(_) => 503,
+    }
+}
+
+/// Strong entity tag of a row, changes whenever any field changes.
+pub fn etag(r: &DBRow) -> String {
+    format!("\"{:016x}\"", fnv1a(json!(r).to_string().as_bytes()))
+}
+
+/// Routes one request against the store:
+///
+/// - `GET /tasks?q=QUERY&sort=KEYS&limit=N`
+/// - `POST /tasks`
+/// - `GET|PUT|PATCH|DELETE /tasks/{id}`, writes honour `If-Match`
+pub fn handle<T: DBReader + DBWriter>(repo: &T, request: &ApiRequest) -> ApiResponse {
+    let (path, query) = match request.url.split_once('?') {
+        Some((p, q)) => (p, q),
+        None => (request.url.as_str(), ""),
+    };
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["tasks"]) => list(repo, query),
+        ("POST", ["tasks"]) => create(repo, &request.body),
+        (_, ["tasks"]) => ApiResponse::error(405, "method not allowed").with_header("Allow", "GET, POST"),
+        (method, ["tasks", id]) => {
+            let id = match id.parse::<u32>() {
+                Ok(id) => id,
+                Err(_) => return ApiResponse::error(404, "not found"),
+            };
+            match method {
+                "GET" => get(repo, id),
+                "PUT" => update(repo, id, request, false),
+                "PATCH" => update(repo, id, request, true),
+                "DELETE" => delete(repo, id, request),
+                _ => ApiResponse::error(405, "method not allowed").with_header("Allow", "GET, PUT, PATCH, DELETE"),
+            }
+        }
+        _ => ApiResponse::error(404, "not found"),
+    }
+}
+
+fn list<T: DBReader>(repo: &T, query: &str) -> ApiResponse {
+    let mut parts: Vec<String> = vec![];
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value);
+        match key {
+            "q" => parts.push(value),
+            "sort" => parts.push(format!("sort:{}", value)),
+            "limit" => parts.push(format!("limit:{}", value)),
+            _ => return ApiResponse::error(400, &format!("unknown parameter '{}'", key)),
+        }
+    }
+
+    let query = match Query::parse(&parts.join(" ")) {
+        Ok(q) => q,
+        Err(e) => return ApiResponse::error(400, &e.to_string()),
+    };
+
+    match repo.read_all() {
+        Ok(rows) => ApiResponse::json(200, &json!(query.apply(rows))),
+        Err(DBError::MissingDatabase(_)) => ApiResponse::json(200, &json!([])),
+        Err(e) => ApiResponse::error(error_status(&e), &e.to_string()),
+    }
+}
+
+fn get<T: DBReader>(repo: &T, id: u32) -> ApiResponse {
+    match repo.read_one(id) {
+        Ok(Some(r)) => ApiResponse::row(200, &r),
+        Ok(None) => ApiResponse::error(404, "id was not found"),
+        Err(e) => ApiResponse::error(error_status(&e), &e.to_string()),
+    }
+}
+
+fn create<T: DBReader + DBWriter>(repo: &T, body: &str) -> ApiResponse {
+    let now = Local::now().timestamp();
+    let base = DBRow {
+        id: repo.get_next_id(),
+        updatedate: now,
+        created: Some(now),
+        ..Default::default()
+    };
+
+    let r = match merge_body(&base, body) {
+        Ok(r) => r,
+        Err(response) => return response,
+    };
+
+    match repo.add(&r) {
+        Ok(()) => ApiResponse::row(201, &r).with_header("Location", &format!("/tasks/{}", r.id)),
+        Err(e) => ApiResponse::error(error_status(&e), &e.to_string()),
+    }
+}
+
+fn update<T: DBReader + DBWriter>(repo: &T, id: u32, request: &ApiRequest, partial: bool) -> ApiResponse {
+    let current = match current_row(repo, id, request) {
+        Ok(r) => r,
+        Err(response) => return response,
+    };
+
+    // PUT replaces every field, PATCH only the given ones
+    let base = if partial {
+        current
+    } else {
+        DBRow { id, ..Default::default() }
+    };
+
+    let mut r = match merge_body(&base, &request.body) {
+        Ok(r) => r,
+        Err(response) => return response,
+    };
+    r.updatedate = Local::now().timestamp();
+
+    match repo.update(&r) {
+        Ok(()) => ApiResponse::row(200, &r),
+        Err(e) => ApiResponse::error(error_status(&e), &e.to_string()),
+    }
+}
+
+fn delete<T: DBReader + DBWriter>(repo: &T, id: u32, request: &ApiRequest) -> ApiResponse {
+    if let Err(response) = current_row(repo, id, request) {
+        return response;
+    }
+
+    match repo.delete(id) {
+        Ok(()) => ApiResponse { status: 204, headers: vec![], body: String::new() },
+        Err(e) => ApiResponse::error(error_status(&e), &e.to_string()),
+    }
+}
+
+/// Loads the row a write targets and checks the `If-Match` precondition.
+fn current_row<T: DBReader>(repo: &T, id: u32, request: &ApiRequest) -> Result<DBRow, ApiResponse> {
+    let current = match repo.read_one(id) {
+        Ok(Some(r)) => r,
+        Ok(None) => return Err(ApiResponse::error(404, "id was not found")),
+        Err(e) => return Err(ApiResponse::error(error_status(&e), &e.to_string())),
+    };
+
+    if let Some(expected) = &request.if_match {
+        let tag = etag(&current);
+        let matches = expected.split(',')
+            .map(|t| t.trim())
+            .any(|t| t == "*" || t == tag);
+        if !matches {
+            return Err(ApiResponse::error(412, "task was changed by someone else")
+                .with_header("ETag", &tag));
+        }
+    }
+
+    Ok(current)
+}
+
+/// Overlays the JSON object in `body` on `base`. The ID and update date are
+/// managed by the server and cannot be changed through the body.
+fn merge_body(base: &DBRow, body: &str) -> Result<DBRow, ApiResponse> {
+    let fields: Value = serde_json::from_str(body)
+        .map_err(|e| ApiResponse::error(400, &format!("invalid JSON: {}", e)))?;
+    let fields = match fields {
+        Value::Object(map) => map,
+        _ => return Err(ApiResponse::error(400, "expected a JSON object")),
+    };
+
+    base.with_fields(fields, Local::now().timestamp()).map_err(|e| ApiResponse::error(422, &e))
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out: Vec<u8> = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok()
+                    .and_then(|h| u8::from_str_radix(h, 16).ok());
+                match hex {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// Answers requests until the server is unblocked.
+pub fn serve<T: DBReader + DBWriter>(repo: &T, server: &tiny_http::Server) {
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        if let Err(e) = request.as_reader().read_to_string(&mut body) {
+            let _ = request.respond(tiny_http::Response::from_string(e.to_string()).with_status_code(400));
+            continue;
+        }
+
+        let api_request = ApiRequest {
+            method: request.method().as_str().to_uppercase(),
+            url: request.url().to_string(),
+            if_match: request.headers().iter()
+                .find(|h| h.field.equiv("If-Match"))
+                .map(|h| h.value.as_str().to_string()),
+            body,
+        };
+
+        let api_response = handle(repo, &api_request);
+
+        let mut response = tiny_http::Response::from_string(api_response.body)
+            .with_status_code(api_response.status);
+        for (name, value) in api_response.headers {
+            if let Ok(h) = tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+                response.add_header(h);
+            }
+        }
+
+        let _ = request.respond(response);
+    }
+}
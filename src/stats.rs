@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::dates::timestamp_to_date;
+use crate::models::{DBError, DBRow};
+
+
+/// Days shown in `Stats::completed_per_day`.
+pub const DAYS: i64 = 14;
+/// Weeks shown in `Stats::completed_per_week`.
+pub const WEEKS: i64 = 8;
+/// Tasks listed in `Stats::oldest_open`.
+pub const OLDEST: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct DayCount {
+    pub date: NaiveDate,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct OpenTask {
+    pub id: u32,
+    pub task: String,
+    pub created: i64,
+    pub age_days: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Stats {
+    pub total: usize,
+    pub open: usize,
+    pub completed: usize,
+    /// Completions on each of the last `DAYS` days, oldest first.
+    pub completed_per_day: Vec<DayCount>,
+    /// Completions in each of the last `WEEKS` ISO weeks, keyed by their Monday.
+    pub completed_per_week: Vec<DayCount>,
+    /// Mean seconds from creation to completion, for tasks that recorded both.
+    pub average_completion_seconds: Option<i64>,
+    pub oldest_open: Vec<OpenTask>,
+    pub tags: BTreeMap<String, usize>,
+    /// Tasks per priority, `none` for tasks without one.
+    pub priorities: BTreeMap<String, usize>,
+    /// Days in a row, up to today, on which at least one task was completed.
+    /// A day without completions yet today doesn't break the streak.
+    pub streak: u32,
+}
+
+pub fn compute(rows: &[DBRow], today: NaiveDate) -> Stats {
+    let done_days: Vec<NaiveDate> = rows.iter()
+        .filter_map(|r| r.done_at())
+        .map(timestamp_to_date)
+        .collect();
+    let count_between = |from: NaiveDate, to: NaiveDate| done_days.iter()
+        .filter(|d| **d >= from && **d <= to)
+        .count();
+
+    let completed_per_day = (0..DAYS).rev()
+        .map(|i| today - Duration::days(i))
+        .map(|d| DayCount { date: d, count: count_between(d, d) })
+        .collect();
+
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let completed_per_week = (0..WEEKS).rev()
+        .map(|i| monday - Duration::weeks(i))
+        .map(|m| DayCount { date: m, count: count_between(m, m + Duration::days(6)) })
+        .collect();
+
+    let durations: Vec<i64> = rows.iter()
+        .filter_map(|r| Some(r.done_at()? - r.created?))
+        .collect();
+    let average_completion_seconds = if durations.is_empty() {
+        None
+    } else {
+        Some(durations.iter().sum::<i64>() / durations.len() as i64)
+    };
+
+    let mut open: Vec<&DBRow> = rows.iter().filter(|r| r.current_status().is_open()).collect();
+    open.sort_by_key(|r| (r.created_at(), r.id));
+    let oldest_open = open.iter()
+        .take(OLDEST)
+        .map(|r| OpenTask {
+            id: r.id,
+            task: r.task.clone(),
+            created: r.created_at(),
+            age_days: (today - timestamp_to_date(r.created_at())).num_days(),
+        })
+        .collect();
+
+    let mut tags: BTreeMap<String, usize> = BTreeMap::new();
+    let mut priorities: BTreeMap<String, usize> = BTreeMap::new();
+    for r in rows {
+        for t in &r.tags {
+            *tags.entry(t.clone()).or_default() += 1;
+        }
+        let priority = r.priority.map(|p| p.to_string()).unwrap_or("none".to_string());
+        *priorities.entry(priority).or_default() += 1;
+    }
+
+    let mut streak = 0;
+    let mut day = if done_days.contains(&today) { today } else { today - Duration::days(1) };
+    while done_days.contains(&day) {
+        streak += 1;
+        day -= Duration::days(1);
+    }
+
+    Stats {
+        total: rows.len(),
+        open: open.len(),
+        completed: rows.iter().filter(|r| r.completed).count(),
+        completed_per_day,
+        completed_per_week,
+        average_completion_seconds,
+        oldest_open,
+        tags,
+        priorities,
+        streak,
+    }
+}
+
+/// Formats seconds as days and hours, e.g. `2d 5h`.
+fn format_span(seconds: i64) -> String {
+    let days = seconds / 86400;
+    let hours = seconds % 86400 / 3600;
+
+    match (days, hours) {
+        (0, 0) => format!("{}m", seconds / 60),
+        (0, h) => format!("{}h", h),
+        (d, h) => format!("{}d {}h", d, h),
+    }
+}
+
+fn bar(count: usize, max: usize) -> String {
+    let width = 30;
+    if max == 0 {
+        return String::new();
+    }
+
+    "#".repeat((count * width).div_ceil(max))
+}
+
+pub fn render_text(stats: &Stats) -> String {
+    let mut out = String::new();
+
+    out.push_str(&format!("Tasks:      {} total, {} open, {} completed\n", stats.total, stats.open, stats.completed));
+    let average = stats.average_completion_seconds.map(format_span).unwrap_or("-".to_string());
+    out.push_str(&format!("Completion: {} on average\n", average));
+    out.push_str(&format!("Streak:     {} day{}\n", stats.streak, if stats.streak == 1 { "" } else { "s" }));
+
+    let max = stats.completed_per_day.iter().map(|d| d.count).max().unwrap_or(0);
+    out.push_str(&format!("\nCompleted per day, last {} days\n", DAYS));
+    for d in &stats.completed_per_day {
+        let line = format!("  {} {:>3} {}", d.date.format("%a %m-%d"), d.count, bar(d.count, max));
+        out.push_str(&format!("{}\n", line.trim_end()));
+    }
+
+    let max = stats.completed_per_week.iter().map(|d| d.count).max().unwrap_or(0);
+    out.push_str(&format!("\nCompleted per week, last {} weeks\n", WEEKS));
+    for d in &stats.completed_per_week {
+        let line = format!("  W{:02} {} {:>3} {}", d.date.iso_week().week(), d.date.format("%m-%d"), d.count, bar(d.count, max));
+        out.push_str(&format!("{}\n", line.trim_end()));
+    }
+
+    if !stats.oldest_open.is_empty() {
+        out.push_str("\nOldest open tasks\n");
+        for t in &stats.oldest_open {
+            out.push_str(&format!("  {:>4} {:>5}d  {}\n", t.id, t.age_days, t.task));
+        }
+    }
+
+    if !stats.tags.is_empty() {
+        out.push_str("\nTags\n");
+        for (tag, count) in &stats.tags {
+            out.push_str(&format!("  {:>4}  +{}\n", count, tag));
+        }
+    }
+
+    out.push_str("\nPriorities\n");
+    for (priority, count) in &stats.priorities {
+        out.push_str(&format!("  {:>4}  {}\n", count, priority));
+    }
+
+    out
+}
+
+pub fn to_json(stats: &Stats) -> Result<String, DBError> {
+    let mut out = serde_json::to_string_pretty(stats)
+        .map_err(|e| DBError::new_write_error(&e.to_string()))?;
+    out.push('\n');
+
+    Ok(out)
+}
@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tempfile::TempDir;
+
+use crate::db_csv::DBCSV;
+use crate::merge::{self, merge_rows, same_row};
+use crate::models::{DBError, DBReader, DBRow, DBWriter};
+
+
+const DB_FILE: &str = "db.csv";
+const BRANCH: &str = "main";
+const REMOTE: &str = "origin";
+
+/// What one `sync` run did.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Local changes were committed.
+    pub committed: bool,
+    /// Changes from the remote were brought in, by fast-forward or merge.
+    pub pulled: bool,
+    /// Both sides had changes and their rows were merged.
+    pub merged: bool,
+    /// Fields both sides changed, decided by the later update.
+    pub conflicts: usize,
+    pub pushed: bool,
+    /// The local store was rewritten with the synced rows.
+    pub updated_store: bool,
+}
+
+/// Git repository holding a copy of the store, used to sync it between
+/// machines through an ordinary git remote.
+pub struct GitSync {
+    dir: PathBuf,
+}
+
+impl GitSync {
+    pub fn new(dir: PathBuf) -> Self {
+        GitSync { dir }
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String, String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .output()
+            .map_err(|e| format!("cannot run git: {}", e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("git {} failed: {}", args[0], stderr.trim()));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Creates the repository on first use.
+    pub fn init(&self) -> Result<(), String> {
+        if self.dir.join(".git").exists() {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("cannot create {}: {}", self.dir.display(), e))?;
+        self.git(&["init", "-q"])?;
+        self.git(&["symbolic-ref", "HEAD", &format!("refs/heads/{}", BRANCH)])?;
+
+        // commits need an identity, fall back to a local one if none is set
+        if self.git(&["config", "user.name"]).is_err() {
+            self.git(&["config", "user.name", "todo"])?;
+        }
+        if self.git(&["config", "user.email"]).is_err() {
+            self.git(&["config", "user.email", "todo@localhost"])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn remote(&self) -> Option<String> {
+        self.git(&["remote", "get-url", REMOTE]).ok()
+    }
+
+    pub fn set_remote(&self, url: &str) -> Result<(), String> {
+        self.init()?;
+
+        match self.remote() {
+            Some(_) => self.git(&["remote", "set-url", REMOTE, url])?,
+            None => self.git(&["remote", "add", REMOTE, url])?,
+        };
+
+        Ok(())
+    }
+
+    /// Commits the store, merges what the remote has row by row and pushes
+    /// the result. The store is rewritten when the remote brought changes.
+    pub fn sync<T: DBReader + DBWriter>(&self, repo: &T) -> Result<SyncReport, String> {
+        self.init()?;
+        let mut report = SyncReport::default();
+
+        let local = read_rows(repo)?;
+        let copy = DBCSV::new(self.dir.join(DB_FILE));
+        copy.write_all(&local).map_err(|e| e.to_string())?;
+
+        self.git(&["add", DB_FILE])?;
+        if !self.git(&["status", "--porcelain"])?.is_empty() {
+            self.git(&["commit", "-q", "-m", "Update tasks"])?;
+            report.committed = true;
+        }
+
+        if self.remote().is_none() {
+            return Ok(report);
+        }
+
+        self.git(&["fetch", "-q", REMOTE])?;
+        let remote_ref = format!("{}/{}", REMOTE, BRANCH);
+
+        if self.git(&["rev-parse", "--verify", "-q", &remote_ref]).is_ok() {
+            let head = self.git(&["rev-parse", "HEAD"])?;
+            let remote_head = self.git(&["rev-parse", &remote_ref])?;
+            let base = self.git(&["merge-base", "HEAD", &remote_ref]).ok();
+
+            if base.as_deref() == Some(remote_head.as_str()) {
+                // the remote has nothing we don't have
+            } else if base.as_deref() == Some(head.as_str()) {
+                self.git(&["merge", "-q", "--ff-only", &remote_ref])?;
+                report.pulled = true;
+            } else {
+                let base_rows = match &base {
+                    Some(b) => self.rows_at(b).map_err(|e| e.to_string())?,
+                    None => vec![],
+                };
+                let theirs = self.rows_at(&remote_ref).map_err(|e| e.to_string())?;
+                let merged = merge_rows(&base_rows, &local, &theirs);
+                report.conflicts = merged.conflicts.len();
+
+                // record the merge without letting git touch the file, the
+                // rows were already merged above
+                let mut args = vec!["merge", "-q", "--no-commit", "-s", "ours"];
+                if base.is_none() {
+                    args.push("--allow-unrelated-histories");
+                }
+                args.push(&remote_ref);
+                self.git(&args)?;
+
+                copy.write_all(&merged.rows).map_err(|e| e.to_string())?;
+                self.git(&["add", DB_FILE])?;
+                self.git(&["commit", "-q", "-m", "Merge tasks from remote"])?;
+                report.pulled = true;
+                report.merged = true;
+            }
+        }
+
+        self.git(&["push", "-q", REMOTE, &format!("HEAD:{}", BRANCH)])?;
+        report.pushed = true;
+
+        let synced = read_rows(&copy)?;
+        let differs = synced.len() != local.len()
+            || synced.iter().zip(&local).any(|(a, b)| !same_row(a, b));
+        if differs {
+            repo.write_all_as(&synced, "sync").map_err(|e| e.to_string())?;
+            report.updated_store = true;
+        }
+
+        Ok(report)
+    }
+
+    /// Rows of the store as committed in `rev`, none when the file did not
+    /// exist yet in that commit. A bad revision or repository is an error,
+    /// merging against it would drop rows.
+    pub(crate) fn rows_at(&self, rev: &str) -> Result<Vec<DBRow>, DBError> {
+        // unlike `show`, lists nothing instead of failing for a missing file
+        let listed = self.git(&["ls-tree", "--name-only", rev, "--", DB_FILE])
+            .map_err(|e| DBError::read(&self.dir, e))?;
+        if listed.is_empty() {
+            return Ok(vec![]);
+        }
+        let content = self.git(&["show", &format!("{}:{}", rev, DB_FILE)])
+            .map_err(|e| DBError::read(&self.dir, e))?;
+
+        let temp_dir = TempDir::new().map_err(|e| DBError::write(&std::env::temp_dir(), e))?;
+        let path = temp_dir.path().join(DB_FILE);
+        fs::write(&path, content).map_err(|e| DBError::write(&path, e))?;
+
+        merge::read_rows(&DBCSV::new(path))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+fn read_rows<T: DBReader>(repo: &T) -> Result<Vec<DBRow>, String> {
+    merge::read_rows(repo).map_err(|e| e.to_string())
+}
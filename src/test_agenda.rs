@@ -0,0 +1,80 @@
+#![cfg(test)]
+
+use chrono::NaiveDate;
+
+use crate::agenda::{agenda, end_of_month, month_counts, render_agenda, render_month};
+use crate::dates::{date_to_timestamp, local_timestamp};
+use crate::models::DBRow;
+
+fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+}
+
+fn today() -> NaiveDate {
+    day(19)
+}
+
+fn rows() -> Vec<DBRow> {
+    let row = |id: u32, task: &str, due: Option<i64>| DBRow {
+        id,
+        task: task.to_string(),
+        due,
+        ..Default::default()
+    };
+
+    vec![
+        row(1, "Pay rent", Some(date_to_timestamp(day(15)))),
+        row(2, "Call bob", Some(local_timestamp(day(19).and_hms_opt(15, 0, 0).unwrap()))),
+        row(3, "Buy milk", Some(date_to_timestamp(day(19)))),
+        row(4, "Write report", Some(date_to_timestamp(day(22)))),
+        row(5, "Plan trip", Some(date_to_timestamp(NaiveDate::from_ymd_opt(2026, 11, 2).unwrap()))),
+        row(6, "Someday", None),
+    ]
+}
+
+#[test]
+fn test_agenda_groups_by_day() {
+    let result = agenda(&rows(), today(), day(25));
+
+    assert_eq!(result.overdue.iter().map(|r| r.id).collect::<Vec<u32>>(), vec![1]);
+    assert_eq!(result.days.len(), 7);
+    assert_eq!(result.days[0].tasks.iter().map(|r| r.id).collect::<Vec<u32>>(), vec![3, 2]);
+    assert!(result.days[1].tasks.is_empty());
+    assert_eq!(result.days[3].tasks[0].id, 4);
+}
+
+#[test]
+fn test_agenda_render() {
+    let out = render_agenda(&agenda(&rows(), today(), day(25)), today());
+
+    assert!(out.starts_with("Overdue\n  #1 Pay rent  (due 2026-10-15)\n"));
+    assert!(out.contains("Today, Mon 19 Oct\n  #3 Buy milk\n  15:00  #2 Call bob\n"));
+    assert!(out.contains("Tomorrow, Tue 20 Oct\n  nothing due\n"));
+    assert!(out.contains("Thu 22 Oct\n  #4 Write report\n"));
+    assert!(!out.contains("Wed 21 Oct"));
+}
+
+#[test]
+fn test_end_of_month_ok() {
+    assert_eq!(end_of_month(today()), day(31));
+    assert_eq!(end_of_month(NaiveDate::from_ymd_opt(2026, 12, 5).unwrap()), NaiveDate::from_ymd_opt(2026, 12, 31).unwrap());
+    assert_eq!(end_of_month(NaiveDate::from_ymd_opt(2028, 2, 1).unwrap()), NaiveDate::from_ymd_opt(2028, 2, 29).unwrap());
+}
+
+#[test]
+fn test_month_grid() {
+    let counts = month_counts(&rows(), today());
+    assert_eq!(counts.get(&day(19)), Some(&2));
+    assert_eq!(counts.values().sum::<usize>(), 4);
+
+    let out = render_month(today(), &counts);
+    let lines: Vec<&str> = out.lines().collect();
+
+    assert_eq!(lines[0], "October 2026");
+    assert!(lines[1].starts_with("  Mo    "));
+    // October 2026 starts on a Thursday
+    assert_eq!(lines[2], format!("{}   1       2       3       4", " ".repeat(24)));
+    assert!(lines[4].contains("  14      15 1    16"));
+    assert!(lines[5].starts_with("[19] 2    20      21      22 1"));
+    assert_eq!(lines.len(), 7);
+}
@@ -0,0 +1,215 @@
+#![cfg(test)]
+
+use tempfile::TempDir;
+
+use crate::audit::{diff, format_changes, format_entry, AuditEntry, AuditLog, FieldChange};
+use crate::backup::Backups;
+use crate::bulk::{apply, BulkAction, Selection};
+use crate::db_csv::DBCSV;
+use crate::models::{DBReader, DBRow, DBWriter, Priority, Status};
+use crate::test_db_csv::row;
+use crate::workflow::Workflow;
+
+struct AuditedDB {
+    _temp_dir: TempDir,
+    db: DBCSV,
+    log: AuditLog,
+}
+
+impl AuditedDB {
+    fn new() -> Self {
+        let temp_dir = TempDir::new().unwrap();
+        let log = AuditLog::new(temp_dir.path().join("audit.jsonl"));
+        let db = DBCSV::new(temp_dir.path().join("test.csv")).with_audit_log(log.clone());
+
+        AuditedDB { _temp_dir: temp_dir, db, log }
+    }
+}
+
+fn change(field: &str, old: Option<&str>, new: Option<&str>) -> FieldChange {
+    FieldChange { field: field.to_string(), old: old.map(|s| s.to_string()), new: new.map(|s| s.to_string()) }
+}
+
+fn operations(entries: &[AuditEntry]) -> Vec<(String, u32)> {
+    entries.iter().map(|e| (e.operation.clone(), e.id)).collect()
+}
+
+#[test]
+fn test_audit_diff_changed_fields() {
+    let before = vec![row(1, 1760857200, "Call bob"), row(2, 1760857200, "Buy milk")];
+    let mut after = before.clone();
+    after[0].priority = Some(Priority::High);
+    after[0].tags = vec!["work".to_string(), "q4".to_string()];
+    after[0].updatedate += 60;
+    after[1].updatedate += 60;
+
+    let result = diff(&before, &after, "update", "alice", 1760860000);
+
+    assert_eq!(result, vec![AuditEntry {
+        at: 1760860000,
+        user: "alice".to_string(),
+        operation: "update".to_string(),
+        id: 1,
+        changes: vec![
+            change("priority", Some(""), Some("high")),
+            change("tags", Some(""), Some("work,q4")),
+        ],
+    }]);
+}
+
+#[test]
+fn test_audit_diff_added_and_deleted() {
+    let before = vec![row(1, 1760857200, "Call bob")];
+    let after = vec![DBRow { priority: Some(Priority::Low), ..row(2, 1760857200, "Buy milk") }];
+
+    let result = diff(&before, &after, "write_all", "alice", 0);
+
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].id, 1);
+    assert!(result[0].changes.iter().all(|c| c.new.is_none()));
+    assert!(result[0].changes.contains(&change("task", Some("Call bob"), None)));
+    assert_eq!(result[1].id, 2);
+    assert!(result[1].changes.iter().all(|c| c.old.is_none()));
+    assert!(result[1].changes.contains(&change("priority", None, Some("low"))));
+    // empty fields of a new row are left out
+    assert!(!result[1].changes.iter().any(|c| c.field == "due"));
+}
+
+#[test]
+fn test_audit_format_entry() {
+    let mut e = AuditEntry {
+        at: 1760857200,
+        user: "alice".to_string(),
+        operation: "update".to_string(),
+        id: 3,
+        changes: vec![change("priority", Some("low"), Some("high")), change("due", Some("1760857200"), Some(""))],
+    };
+    let at = crate::utils::format_timestamp(1760857200, "%Y-%m-%d %H:%M");
+    let due = crate::dates::format_due(1760857200);
+
+    assert_eq!(format_entry(&e), format!("{}  alice  update #3  priority: low -> high, due: {} -> -", at, due));
+
+    e.operation = "delete".to_string();
+    e.changes = vec![change("priority", Some("low"), None), change("task", Some("Call bob"), None)];
+    assert_eq!(format_entry(&e), format!("{}  alice  delete #3  task: Call bob (deleted)", at));
+}
+
+#[test]
+fn test_audit_log_records_writer_operations() {
+    let test_db = AuditedDB::new();
+
+    test_db.db.add(&row(1, 1760857200, "Call bob")).unwrap();
+    test_db.db.add(&row(2, 1760857200, "Buy milk")).unwrap();
+    test_db.db.update(&DBRow { task: "Call bob back".to_string(), ..row(1, 1760857200, "") }).unwrap();
+    test_db.db.set_status(2, Status::Done).unwrap();
+    test_db.db.delete_completed().unwrap();
+    test_db.db.delete(1).unwrap();
+
+    let entries = test_db.log.read_all().unwrap();
+    assert_eq!(operations(&entries), vec![
+        ("add".to_string(), 1),
+        ("add".to_string(), 2),
+        ("update".to_string(), 1),
+        ("set_status".to_string(), 2),
+        ("delete_completed".to_string(), 2),
+        ("delete".to_string(), 1),
+    ]);
+    assert_eq!(entries[2].changes, vec![change("task", Some("Call bob"), Some("Call bob back"))]);
+    assert!(entries[3].changes.contains(&change("status", Some("todo"), Some("done"))));
+    assert!(!entries[0].user.is_empty());
+
+    // the history outlives the rows
+    assert!(test_db.db.read_all().unwrap().is_empty());
+    assert_eq!(test_db.log.history(Some(1), None).unwrap().len(), 3);
+}
+
+#[test]
+fn test_audit_log_skips_unchanged_and_failed_writes() {
+    let test_db = AuditedDB::new();
+    test_db.db.add(&row(1, 1760857200, "Call bob")).unwrap();
+
+    test_db.db.write_all(&[DBRow { updatedate: 1760860000, ..row(1, 1760857200, "Call bob") }]).unwrap();
+    assert!(test_db.db.delete(9).is_err());
+
+    assert_eq!(operations(&test_db.log.read_all().unwrap()), vec![("add".to_string(), 1)]);
+}
+
+#[test]
+fn test_audit_log_names_bulk_operations() {
+    let test_db = AuditedDB::new();
+    test_db.db.add(&row(1, 1760857200, "Call bob")).unwrap();
+    test_db.db.add(&row(2, 1760857200, "Buy milk")).unwrap();
+    let selection = |id: u32| Selection { ids: Some(vec![id].into()), ..Default::default() };
+
+    apply(&test_db.db, &selection(1), BulkAction::Complete, &Workflow::default()).unwrap();
+    apply(&test_db.db, &selection(1), BulkAction::Uncomplete, &Workflow::default()).unwrap();
+    apply(&test_db.db, &selection(2), BulkAction::SetStatus(Status::Cancelled), &Workflow::default()).unwrap();
+    apply(&test_db.db, &selection(2), BulkAction::Delete, &Workflow::default()).unwrap();
+
+    assert_eq!(operations(&test_db.log.read_all().unwrap())[2..], [
+        ("complete".to_string(), 1),
+        ("uncomplete".to_string(), 1),
+        ("set_status".to_string(), 2),
+        ("delete".to_string(), 2),
+    ]);
+}
+
+#[test]
+fn test_audit_log_records_repair() {
+    let test_db = AuditedDB::new();
+    test_db.db.add(&row(1, 1760857200, "Call bob")).unwrap();
+    let path = test_db._temp_dir.path().join("test.csv");
+    let mut content = std::fs::read_to_string(&path).unwrap();
+    content.push_str("2;1760857200;Buy milk;maybe;;;;;;;;;;;;\n");
+    std::fs::write(&path, content).unwrap();
+
+    test_db.db.repair(&Backups::new(test_db._temp_dir.path().join("backups"), 10)).unwrap();
+
+    let entries = test_db.log.read_all().unwrap();
+    assert_eq!(operations(&entries), vec![("add".to_string(), 1), ("repair".to_string(), 2)]);
+    assert_eq!(format_changes(&entries[1]), "line: 3 (bad boolean: completed 'maybe' is not true or false) (deleted)");
+}
+
+#[test]
+fn test_audit_log_history_filters() {
+    let temp_dir = TempDir::new().unwrap();
+    let log = AuditLog::new(temp_dir.path().join("audit.jsonl"));
+    let entry = |id: u32, at: i64| AuditEntry {
+        at,
+        user: "bob".to_string(),
+        operation: "update".to_string(),
+        id,
+        changes: vec![change("task", Some("a"), Some("b"))],
+    };
+
+    assert!(log.history(None, None).unwrap().is_empty());
+    log.append(&[entry(1, 100), entry(2, 200)]).unwrap();
+    log.append(&[entry(1, 300)]).unwrap();
+
+    assert_eq!(log.history(None, None).unwrap().len(), 3);
+    assert_eq!(log.history(Some(1), None).unwrap(), vec![entry(1, 100), entry(1, 300)]);
+    assert_eq!(log.history(None, Some(200)).unwrap(), vec![entry(2, 200), entry(1, 300)]);
+}
+
+#[test]
+fn test_audit_log_broken_line_is_read_error() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("audit.jsonl");
+    std::fs::write(&path, "{}\n").unwrap();
+
+    let err = AuditLog::new(path.clone()).read_all().unwrap_err();
+
+    let context = err.context().unwrap();
+    assert_eq!((context.path.as_deref(), context.line), (Some(path.as_path()), Some(1)));
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn test_audit_log_without_audit_writes_nothing() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DBCSV::new(temp_dir.path().join("test.csv"));
+
+    db.add(&row(1, 1760857200, "Call bob")).unwrap();
+
+    assert!(!temp_dir.path().join("audit.jsonl").exists());
+}
@@ -0,0 +1,146 @@
+#![cfg(test)]
+
+use std::fs;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use tempfile::TempDir;
+
+use crate::audit::AuditLog;
+use crate::backup::Backups;
+use crate::db_csv::DBCSV;
+use crate::models::{DBReader, DBWriter, Status};
+use crate::test_db_csv::row;
+
+struct BackedUpDB {
+    temp_dir: TempDir,
+    db: DBCSV,
+    backups: Backups,
+}
+
+impl BackedUpDB {
+    fn new(keep: usize) -> Self {
+        let temp_dir = TempDir::new().unwrap();
+        let backups = Backups::new(temp_dir.path().join("backups"), keep);
+        let db = DBCSV::new(temp_dir.path().join("test.csv")).with_backups(backups.clone());
+
+        BackedUpDB { temp_dir, db, backups }
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.backups.list().unwrap().into_iter().map(|b| b.name).collect()
+    }
+}
+
+fn at(second: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2026, 10, 19).unwrap().and_hms_opt(9, 30, second).unwrap()
+}
+
+fn ids(db: &DBCSV) -> Vec<u32> {
+    let mut ids: Vec<u32> = db.read_all().unwrap().iter().map(|r| r.id).collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_backup_create_and_list() {
+    let test_db = BackedUpDB::new(10);
+    let db_path = test_db.temp_dir.path().join("test.csv");
+
+    assert!(test_db.backups.create(&db_path, "manual", at(0)).unwrap().is_none());
+    test_db.db.add(&row(1, 1760857200, "Call bob")).unwrap();
+
+    let first = test_db.backups.create(&db_path, "manual", at(0)).unwrap().unwrap();
+    test_db.backups.create(&db_path, "delete", at(0)).unwrap();
+
+    assert_eq!(first.name, "20261019-093000.000-manual.csv");
+    assert_eq!(fs::read_to_string(first.path).unwrap(), fs::read_to_string(&db_path).unwrap());
+    // made within the same millisecond, kept in order
+    assert_eq!(test_db.names(), vec!["20261019-093000.000-manual.csv", "20261019-093000.001-delete.csv"]);
+    assert_eq!(test_db.backups.find("20261019-093000.001-delete.csv").unwrap().unwrap().name, "20261019-093000.001-delete.csv");
+    assert!(test_db.backups.find("../test.csv").unwrap().is_none());
+}
+
+#[test]
+fn test_backup_keeps_newest() {
+    let test_db = BackedUpDB::new(2);
+    let db_path = test_db.temp_dir.path().join("test.csv");
+    test_db.db.add(&row(1, 1760857200, "Call bob")).unwrap();
+
+    for second in 0..4 {
+        test_db.backups.create(&db_path, "manual", at(second)).unwrap();
+    }
+
+    assert_eq!(test_db.names(), vec!["20261019-093002.000-manual.csv", "20261019-093003.000-manual.csv"]);
+}
+
+#[test]
+fn test_backup_before_destructive_operations() {
+    let test_db = BackedUpDB::new(10);
+    test_db.db.add(&row(1, 1760857200, "Call bob")).unwrap();
+    test_db.db.add(&row(2, 1760857200, "Buy milk")).unwrap();
+    test_db.db.set_status(2, Status::Done).unwrap();
+    assert!(test_db.names().is_empty());
+
+    test_db.db.delete_completed().unwrap();
+    // nothing to delete, nothing to back up
+    test_db.db.delete_completed().unwrap();
+    assert!(test_db.db.delete(7).is_err());
+    test_db.db.write_all(&[row(1, 1760857200, "Call bob back")]).unwrap();
+    test_db.db.delete(1).unwrap();
+
+    let operations: Vec<String> = test_db.names().iter()
+        .map(|n| n.split('-').nth(2).unwrap().trim_end_matches(".csv").to_string())
+        .collect();
+    assert_eq!(operations, vec!["delete_completed", "write_all", "delete"]);
+    // the first backup still has the completed task
+    let first = DBCSV::new(test_db.backups.list().unwrap()[0].path.clone());
+    assert_eq!(ids(&first), vec![1, 2]);
+}
+
+#[test]
+fn test_backup_off_when_keeping_none() {
+    let test_db = BackedUpDB::new(0);
+    test_db.db.add(&row(1, 1760857200, "Call bob")).unwrap();
+
+    test_db.db.delete(1).unwrap();
+
+    assert!(test_db.names().is_empty());
+}
+
+#[test]
+fn test_backup_restore() {
+    let test_db = BackedUpDB::new(10);
+    let log = AuditLog::new(test_db.temp_dir.path().join("audit.jsonl"));
+    let db = DBCSV::new(test_db.temp_dir.path().join("test.csv"))
+        .with_backups(test_db.backups.clone())
+        .with_audit_log(log.clone());
+    db.add(&row(1, 1760857200, "Call bob")).unwrap();
+    db.add(&row(2, 1760857200, "Buy milk")).unwrap();
+    db.delete(2).unwrap();
+    let backup = test_db.backups.list().unwrap().remove(0);
+
+    db.restore(&backup.path).unwrap();
+
+    assert_eq!(ids(&db), vec![1, 2]);
+    assert_eq!(test_db.names().len(), 2);
+    assert!(test_db.names()[1].ends_with("-restore.csv"));
+    assert!(!test_db.temp_dir.path().join("test.tmp").exists());
+    let last = log.read_all().unwrap().pop().unwrap();
+    assert_eq!((last.operation.as_str(), last.id), ("restore", 2));
+}
+
+#[test]
+fn test_backup_restore_broken_and_missing_database() {
+    let test_db = BackedUpDB::new(10);
+    let db_path = test_db.temp_dir.path().join("test.csv");
+    test_db.db.add(&row(1, 1760857200, "Call bob")).unwrap();
+    let backup = test_db.backups.create(&db_path, "manual", at(0)).unwrap().unwrap();
+
+    fs::write(&db_path, "garbage\n1;2;3\n").unwrap();
+    test_db.db.restore(&backup.path).unwrap();
+    assert_eq!(ids(&test_db.db), vec![1]);
+
+    fs::remove_file(&db_path).unwrap();
+    test_db.db.restore(&backup.path).unwrap();
+    assert_eq!(ids(&test_db.db), vec![1]);
+}
@@ -0,0 +1,85 @@
+#![cfg(test)]
+
+use crate::board::{lanes, render, wrap, GroupBy, MIN_LANE_WIDTH};
+use crate::models::{DBRow, Priority, Status};
+
+fn rows() -> Vec<DBRow> {
+    let row = |id: u32, task: &str, status: Status, tags: &[&str], priority: Option<Priority>| DBRow {
+        id,
+        task: task.to_string(),
+        completed: status == Status::Done,
+        status,
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        priority,
+        ..Default::default()
+    };
+
+    vec![
+        row(1, "Buy milk", Status::Todo, &["home"], None),
+        row(2, "Write report", Status::InProgress, &["work"], Some(Priority::High)),
+        row(3, "Call bob", Status::Todo, &["work", "home"], Some(Priority::High)),
+        row(4, "Hear back from the bank", Status::Waiting, &[], Some(Priority::Low)),
+        row(5, "Pay rent", Status::Done, &[], None),
+        row(6, "Old idea", Status::Cancelled, &[], None),
+    ]
+}
+
+fn summary(group_by: GroupBy) -> Vec<(String, Vec<u32>)> {
+    lanes(&rows(), group_by).into_iter()
+        .map(|l| (l.title, l.cards.iter().map(|r| r.id).collect()))
+        .collect()
+}
+
+#[test]
+fn test_board_lanes_by_status() {
+    assert_eq!(summary(GroupBy::Status), vec![
+        ("Todo".to_string(), vec![3, 1]),
+        ("In progress".to_string(), vec![2]),
+        ("Waiting".to_string(), vec![4]),
+        ("Done".to_string(), vec![5]),
+    ]);
+}
+
+#[test]
+fn test_board_lanes_by_tag_and_priority() {
+    assert_eq!(summary(GroupBy::Tag), vec![
+        ("+home".to_string(), vec![3, 1]),
+        ("+work".to_string(), vec![2, 3]),
+        ("(no tag)".to_string(), vec![4, 5, 6]),
+    ]);
+    assert_eq!(summary(GroupBy::Priority), vec![
+        ("High".to_string(), vec![2, 3]),
+        ("Medium".to_string(), vec![]),
+        ("Low".to_string(), vec![4]),
+        ("None".to_string(), vec![1, 5, 6]),
+    ]);
+}
+
+#[test]
+fn test_board_render_side_by_side() {
+    let out = render(&lanes(&rows(), GroupBy::Status), 4 * MIN_LANE_WIDTH + 9);
+    let lines: Vec<&str> = out.lines().collect();
+
+    assert!(lines[0].starts_with("Todo (2)"));
+    assert!(lines[0].contains(" | In progress (1)"));
+    assert!(lines[0].ends_with("Done (1)"));
+    assert!(lines.iter().all(|l| l.chars().count() <= 4 * MIN_LANE_WIDTH + 9));
+    assert!(lines[2].contains("| #4 Hear back from "));
+    assert!(lines[3].contains("| the bank !low "));
+}
+
+#[test]
+fn test_board_render_wraps_lanes_to_width() {
+    let out = render(&lanes(&rows(), GroupBy::Status), 2 * MIN_LANE_WIDTH + 3);
+    let titles: Vec<&str> = out.lines().filter(|l| l.contains(" (")).filter(|l| !l.starts_with('#')).collect();
+
+    assert_eq!(titles.len(), 2);
+    assert!(titles[1].starts_with("Waiting (1)"));
+}
+
+#[test]
+fn test_wrap_ok() {
+    assert_eq!(wrap("one two three", 7), vec!["one two", "three"]);
+    assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+    assert_eq!(wrap("", 4), vec![""]);
+}
@@ -0,0 +1,168 @@
+#![cfg(test)]
+
+use chrono::NaiveDate;
+
+use crate::bulk::{apply, parse_id_list, BulkAction, BulkOutcome, IdList, Selection};
+use crate::dates::local_timestamp;
+use crate::models::{DBReader, DBRow, DBWriter, Recurrence, Status};
+use crate::test_db_csv::{row, seeded_db, TestDB};
+use crate::workflow::Workflow;
+
+#[test]
+fn test_parse_id_list_ok() {
+    assert_eq!(parse_id_list("3").unwrap(), IdList(vec![(3, 3)]));
+    assert_eq!(parse_id_list("1,4,7-9").unwrap(), IdList(vec![(1, 1), (4, 4), (7, 9)]));
+    assert_eq!(parse_id_list("2-3, 1 ,3").unwrap(), IdList(vec![(1, 3)]));
+    assert_eq!(parse_id_list("1-4000000000,9").unwrap(), IdList(vec![(1, 4000000000)]));
+}
+
+#[test]
+fn test_id_list_contains_and_ids() {
+    let list = parse_id_list("1,4,7-4000000000").unwrap();
+
+    assert!(list.contains(4) && list.contains(3999999999));
+    assert!(!list.contains(5));
+    assert_eq!(list.ids_up_to(8), vec![1, 4, 7, 8]);
+    // a range past the largest ID keeps its first
+    assert_eq!(parse_id_list("2,9-12").unwrap().ids_up_to(5), vec![2, 9]);
+}
+
+#[test]
+fn test_bulk_wide_range() {
+    let test_db = seeded_db(5);
+    let selection = Selection { ids: Some(parse_id_list("2-4000000000").unwrap()), tag: None, filter: None };
+
+    let result = apply(&test_db.db, &selection, BulkAction::Delete, &Workflow::default()).unwrap();
+
+    assert!(result.iter().all(|(_, o)| *o == BulkOutcome::Deleted));
+    assert_eq!(test_db.db.read_all().unwrap().iter().map(|r| r.id).collect::<Vec<u32>>(), vec![1]);
+}
+
+#[test]
+fn test_parse_id_list_is_err() {
+    assert!(parse_id_list("").is_err());
+    assert!(parse_id_list("1,,2").is_err());
+    assert!(parse_id_list("a").is_err());
+    assert!(parse_id_list("5-2").is_err());
+    assert!(parse_id_list("-3").is_err());
+}
+
+#[test]
+fn test_bulk_complete_reports_each_id() {
+    let test_db = seeded_db(5);
+    let selection = Selection { ids: Some(vec![1, 2, 5, 9].into()), tag: None, filter: None };
+
+    let result = apply(&test_db.db, &selection, BulkAction::Complete, &Workflow::default()).unwrap();
+
+    assert_eq!(result, vec![
+        (1, BulkOutcome::Completed),
+        (2, BulkOutcome::Completed),
+        (5, BulkOutcome::Unchanged),
+        (9, BulkOutcome::NotFound),
+    ]);
+    assert!(test_db.db.read_one(1).unwrap().unwrap().completed);
+    assert!(!test_db.db.read_one(3).unwrap().unwrap().completed);
+}
+
+#[test]
+fn test_bulk_delete_by_tag_ok() {
+    let test_db = seeded_db(5);
+    // as `tag:Work` in a filter
+    let selection = Selection { ids: None, tag: Some("Work".to_string()), filter: None };
+
+    let result = apply(&test_db.db, &selection, BulkAction::Delete, &Workflow::default()).unwrap();
+
+    assert_eq!(result, vec![(2, BulkOutcome::Deleted), (4, BulkOutcome::Deleted)]);
+    assert_eq!(test_db.db.read_all().unwrap().len(), 3);
+}
+
+#[test]
+fn test_bulk_ids_and_tag_are_combined() {
+    let test_db = seeded_db(5);
+    let selection = Selection { ids: Some(vec![1, 2, 3].into()), tag: Some("work".to_string()), filter: None };
+
+    let result = apply(&test_db.db, &selection, BulkAction::Uncomplete, &Workflow::default()).unwrap();
+
+    assert_eq!(result, vec![
+        (1, BulkOutcome::NotFound),
+        (2, BulkOutcome::Unchanged),
+        (3, BulkOutcome::NotFound),
+    ]);
+}
+
+#[test]
+fn test_bulk_set_status_follows_workflow() {
+    let test_db = seeded_db(5);
+    let selection = Selection { ids: Some(vec![1, 5].into()), tag: None, filter: None };
+
+    let result = apply(&test_db.db, &selection, BulkAction::SetStatus(Status::Waiting), &Workflow::default()).unwrap();
+
+    assert_eq!(result, vec![
+        (1, BulkOutcome::Status(Status::Waiting)),
+        (5, BulkOutcome::NotAllowed(Status::Done)),
+    ]);
+    assert_eq!(result[1].1.to_string(), "not allowed from done");
+    assert_eq!(test_db.db.read_one(1).unwrap().unwrap().status, Status::Waiting);
+    assert!(test_db.db.read_one(5).unwrap().unwrap().completed);
+}
+
+#[test]
+fn test_bulk_uncomplete_reopens_cancelled() {
+    let test_db = seeded_db(5);
+    test_db.db.set_status(1, Status::Cancelled).unwrap();
+    let selection = Selection { ids: Some(vec![1, 2].into()), tag: None, filter: None };
+
+    let result = apply(&test_db.db, &selection, BulkAction::Uncomplete, &Workflow::default()).unwrap();
+
+    assert_eq!(result, vec![(1, BulkOutcome::Uncompleted), (2, BulkOutcome::Unchanged)]);
+    assert_eq!(test_db.db.read_one(1).unwrap().unwrap().status, Status::Todo);
+}
+
+#[test]
+fn test_bulk_empty_db_is_err() {
+    let test_db = TestDB::new();
+    let selection = Selection { ids: Some(vec![1].into()), tag: None, filter: None };
+
+    assert!(apply(&test_db.db, &selection, BulkAction::Delete, &Workflow::default()).is_err());
+}
+
+#[test]
+fn test_bulk_complete_recurring_adds_next() {
+    let test_db = seeded_db(5);
+    let due = local_timestamp(NaiveDate::from_ymd_opt(2026, 10, 19).unwrap().and_hms_opt(15, 0, 0).unwrap());
+    let mut rows = test_db.db.read_all().unwrap();
+    let first = rows.iter_mut().find(|r| r.id == 1).unwrap();
+    first.due = Some(due);
+    first.recurrence = Some(Recurrence::Weeks(1));
+    test_db.db.write_all(&rows).unwrap();
+    let selection = Selection { ids: Some(vec![1].into()), tag: None, filter: None };
+
+    let result = apply(&test_db.db, &selection, BulkAction::Complete, &Workflow::default()).unwrap();
+
+    assert_eq!(result, vec![(1, BulkOutcome::Recurred(6))]);
+    assert!(test_db.db.read_one(1).unwrap().unwrap().completed);
+    let next = test_db.db.read_one(6).unwrap().unwrap();
+    assert_eq!(next.task, "task1");
+    assert_eq!(next.current_status(), Status::Todo);
+    assert_eq!(next.recurrence, Some(Recurrence::Weeks(1)));
+    assert_eq!(next.due, Some(local_timestamp(NaiveDate::from_ymd_opt(2026, 10, 26).unwrap().and_hms_opt(15, 0, 0).unwrap())));
+}
+
+#[test]
+fn test_bulk_no_id_left_for_next_occurrence() {
+    let test_db = seeded_db(1);
+    let recurring = DBRow {
+        id: u32::MAX,
+        due: Some(local_timestamp(NaiveDate::from_ymd_opt(2026, 10, 19).unwrap().and_hms_opt(15, 0, 0).unwrap())),
+        recurrence: Some(Recurrence::Weeks(1)),
+        ..row(u32::MAX, 1739126402, "last")
+    };
+    test_db.db.add(&recurring).unwrap();
+    let selection = |id: u32| Selection { ids: Some(vec![id].into()), tag: None, filter: None };
+
+    assert!(apply(&test_db.db, &selection(u32::MAX), BulkAction::Complete, &Workflow::default()).is_err());
+    assert!(!test_db.db.read_one(u32::MAX).unwrap().unwrap().completed);
+    // tasks that don't recur need no new ID
+    let result = apply(&test_db.db, &selection(1), BulkAction::Uncomplete, &Workflow::default()).unwrap();
+    assert_eq!(result, vec![(1, BulkOutcome::Uncompleted)]);
+}
@@ -0,0 +1,106 @@
+#![cfg(test)]
+
+use chrono::NaiveDate;
+
+use crate::burndown::{burndown, render_chart, to_csv, to_json};
+use crate::dates::date_to_timestamp;
+use crate::models::DBRow;
+
+fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+}
+
+/// Timestamp at noon of the given October day.
+fn at(d: u32) -> i64 {
+    date_to_timestamp(day(d)) + 12 * 3600
+}
+
+fn row(id: u32, estimate: Option<u32>, created: u32, done: Option<u32>) -> DBRow {
+    DBRow {
+        id,
+        updatedate: at(created),
+        task: format!("task{}", id),
+        completed: done.is_some(),
+        estimate,
+        created: Some(at(created)),
+        completed_at: done.map(at),
+        ..Default::default()
+    }
+}
+
+fn sprint() -> Vec<DBRow> {
+    vec![
+        row(1, Some(5), 1, Some(2)),
+        row(2, Some(3), 1, None),
+        row(3, Some(2), 3, Some(4)),
+        row(4, None, 1, None),
+    ]
+}
+
+#[test]
+fn test_burndown_series() {
+    let points = burndown(&sprint(), day(1), day(4));
+
+    let series: Vec<(u64, u64)> = points.iter().map(|p| (p.total, p.remaining)).collect();
+    assert_eq!(series, vec![(8, 8), (8, 3), (10, 5), (10, 3)]);
+    assert_eq!(points[0].ideal, 8.0);
+    assert_eq!(points[3].ideal, 0.0);
+}
+
+#[test]
+fn test_burndown_ignores_future_tasks() {
+    let points = burndown(&sprint(), day(1), day(1));
+
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].total, 8);
+}
+
+#[test]
+fn test_burndown_legacy_rows_use_updatedate() {
+    let r = DBRow { id: 1, updatedate: at(2), completed: true, estimate: Some(4), ..Default::default() };
+
+    let points = burndown(&[r], day(1), day(2));
+
+    assert_eq!(points[0].total, 0);
+    assert_eq!(points[1].total, 4);
+    assert_eq!(points[1].remaining, 0);
+}
+
+#[test]
+fn test_burndown_large_estimates_and_last_day() {
+    let rows = [row(1, Some(u32::MAX), 1, None), row(2, Some(5), 1, None)];
+
+    let points = burndown(&rows, day(1), day(1));
+    assert_eq!(points[0].total, u32::MAX as u64 + 5);
+
+    let last = burndown(&rows, NaiveDate::MAX, NaiveDate::MAX);
+    assert_eq!(last[0].remaining, u32::MAX as u64 + 5);
+}
+
+#[test]
+fn test_burndown_chart() {
+    let points = burndown(&sprint(), day(1), day(4));
+
+    let chart = render_chart(&points, 4);
+    let lines: Vec<&str> = chart.lines().collect();
+
+    assert_eq!(lines.len(), 6);
+    assert!(lines[0].starts_with("8 | #"));
+    assert!(lines[1].starts_with("  | # . #"));
+    assert!(lines[2].starts_with("4 | # # # #"));
+    assert!(lines[3].starts_with("  | # # # #"));
+    assert!(lines[5].contains("10-01") && lines[5].contains("10-04"));
+
+    assert!(render_chart(&[], 4).contains("No estimated work"));
+}
+
+#[test]
+fn test_burndown_csv_and_json() {
+    let points = burndown(&sprint(), day(1), day(2));
+
+    assert_eq!(to_csv(&points), "date,total,remaining,ideal\n2026-10-01,8,8,8.00\n2026-10-02,8,3,0.00\n");
+
+    let json: serde_json::Value = serde_json::from_str(&to_json(&points).unwrap()).unwrap();
+    assert_eq!(json[1]["date"], "2026-10-02");
+    assert_eq!(json[1]["remaining"], 3);
+}
@@ -0,0 +1,101 @@
+#![cfg(test)]
+
+use chrono::NaiveDate;
+
+use crate::contexts::{check_dependencies, context_counts, is_blocked, next_actions, normalize, ContextCount};
+use crate::dates::date_to_timestamp;
+use crate::models::{DBRow, Priority, Status};
+
+fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+}
+
+fn row(id: u32, contexts: &[&str]) -> DBRow {
+    DBRow {
+        id,
+        task: format!("task{}", id),
+        contexts: contexts.iter().map(|c| c.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+fn rows() -> Vec<DBRow> {
+    vec![
+        DBRow { priority: Some(Priority::Low), ..row(1, &["phone"]) },
+        DBRow { priority: Some(Priority::High), due: Some(date_to_timestamp(day(25))), ..row(2, &["phone", "office"]) },
+        DBRow { priority: Some(Priority::High), due: Some(date_to_timestamp(day(21))), ..row(3, &["phone"]) },
+        DBRow { depends_on: vec![5], ..row(4, &["phone"]) },
+        DBRow { status: Status::Waiting, ..row(5, &["office"]) },
+        DBRow { snoozed_until: Some(date_to_timestamp(day(22))), ..row(6, &["phone"]) },
+        DBRow { status: Status::Done, completed: true, ..row(7, &["phone"]) },
+        DBRow { depends_on: vec![7, 99], ..row(8, &["home"]) },
+        row(9, &[]),
+    ]
+}
+
+fn ids(rows: &[DBRow]) -> Vec<u32> {
+    rows.iter().map(|r| r.id).collect()
+}
+
+#[test]
+fn test_normalize() {
+    assert_eq!(normalize("@Phone"), "phone");
+    assert_eq!(normalize("office"), "office");
+}
+
+#[test]
+fn test_is_blocked() {
+    let rows = rows();
+
+    assert!(is_blocked(&rows[3], &rows));
+    // done and deleted dependencies don't block
+    assert!(!is_blocked(&rows[7], &rows));
+    assert!(!is_blocked(&rows[0], &rows));
+}
+
+#[test]
+fn test_next_actions_in_context() {
+    let result = next_actions(&rows(), Some("@phone"), day(19));
+
+    assert_eq!(ids(&result), vec![3, 2, 1]);
+}
+
+#[test]
+fn test_next_actions_without_context() {
+    let result = next_actions(&rows(), None, day(19));
+
+    assert_eq!(ids(&result), vec![3, 2, 1, 8, 9]);
+}
+
+#[test]
+fn test_next_actions_after_snooze_and_unblocking() {
+    let mut rows = rows();
+    rows[4].set_status(Status::Done, 0);
+
+    let result = next_actions(&rows, Some("phone"), day(22));
+
+    assert_eq!(ids(&result), vec![3, 2, 1, 4, 6]);
+}
+
+#[test]
+fn test_context_counts() {
+    let result = context_counts(&rows(), day(19));
+
+    assert_eq!(result.keys().collect::<Vec<_>>(), vec!["home", "office", "phone"]);
+    assert_eq!(result["phone"], ContextCount { open: 5, next: 3 });
+    assert_eq!(result["office"], ContextCount { open: 2, next: 1 });
+    assert_eq!(result["home"], ContextCount { open: 1, next: 1 });
+}
+
+#[test]
+fn test_check_dependencies() {
+    let mut rows = rows();
+    rows[2].depends_on = vec![4];
+
+    assert!(check_dependencies(&rows[0], &[2, 3], &rows).is_ok());
+    assert!(check_dependencies(&rows[0], &[], &rows).is_ok());
+    assert!(check_dependencies(&rows[0], &[1], &rows).is_err());
+    assert!(check_dependencies(&rows[0], &[42], &rows).is_err());
+    // 3 waits for 4, which waits for 5
+    assert!(check_dependencies(&rows[4], &[3], &rows).is_err());
+}
@@ -3,20 +3,48 @@
 use std::{fs, path};
 use tempfile::TempDir;
 
-use crate::{db_csv::DBCSV, models::{DBReader, DBRow, DBWriter}};
+use crate::{db_csv::DBCSV, models::{DBError, DBReader, DBRow, DBWriter, Recurrence, Status}};
 
-struct TestDB {
-    _temp_dir: TempDir,
-    _path: path::PathBuf,
-    db: DBCSV,
+pub(crate) struct TestDB {
+    pub(crate) _temp_dir: TempDir,
+    pub(crate) _path: path::PathBuf,
+    pub(crate) db: DBCSV,
 }
 
 impl TestDB {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         let tempdir = TempDir::new().unwrap();
         let db_path = tempdir.path().join("test.csv");
 
-        return TestDB { _temp_dir: tempdir, _path: db_path.to_owned(), db: DBCSV::new(db_path) }
+        TestDB { _temp_dir: tempdir, _path: db_path.to_owned(), db: DBCSV::new(db_path) }
+    }
+}
+
+/// A database with tasks 1 to `count`, the lower IDs updated later. Even tasks
+/// are tagged `work` and the last one is done.
+pub(crate) fn seeded_db(count: u32) -> TestDB {
+    let test_db = TestDB::new();
+
+    let rows: Vec<DBRow> = (1..=count).map(|i| DBRow{
+        id: i,
+        updatedate: 1739126402 - i as i64,
+        task: format!("task{}", i),
+        completed: i == count,
+        tags: if i % 2 == 0 { vec!["work".to_string()] } else { vec![] },
+        ..Default::default()
+    }).collect();
+    test_db.db.write_all(&rows).unwrap();
+
+    test_db
+}
+
+/// A row with only an ID, update date and text.
+pub(crate) fn row(id: u32, updatedate: i64, task: &str) -> DBRow {
+    DBRow {
+        id,
+        updatedate,
+        task: task.to_string(),
+        ..Default::default()
     }
 }
 
@@ -45,6 +73,7 @@ fn test_db_add_is_ok() {
         updatedate: 1739126402,
         task: "test1".to_string(),
         completed: false,
+        ..Default::default()
     };
 
     let result = test_db.db.add(&r1);
@@ -60,6 +89,7 @@ fn test_db_read_one_is_not_found() {
         updatedate: 1739126402,
         task: "test1".to_string(),
         completed: false,
+        ..Default::default()
     };
     test_db.db.add(&r1).unwrap();
 
@@ -78,6 +108,7 @@ fn test_db_read_one_ok() {
         updatedate: 1739126402,
         task: "test1".to_string(),
         completed: false,
+        ..Default::default()
     };
 
     test_db.db.add(&r1).unwrap();
@@ -96,12 +127,14 @@ fn test_db_read_all_ok() {
         updatedate: 1739126402,
         task: "test1".to_string(),
         completed: false,
+        ..Default::default()
     };
     let r2 = DBRow{
         id: 2,
         updatedate: 1739126602,
         task: "test2".to_string(),
         completed: true,
+        ..Default::default()
     };
 
     test_db.db.add(&r1).unwrap();
@@ -112,7 +145,7 @@ fn test_db_read_all_ok() {
 
     let unw = result.unwrap();
     assert!(unw.len() == 2);
-    assert!(unw[1].task == "test1".to_string());
+    assert!(unw[1].task == "test1");
 }
 
 #[test]
@@ -124,6 +157,7 @@ fn test_db_delete_is_ok() {
         updatedate: 1739126402,
         task: "test1".to_string(),
         completed: false,
+        ..Default::default()
     };
     test_db.db.add(&r1).unwrap();
 
@@ -140,11 +174,14 @@ fn test_db_mark_completion_true_is_ok() {
         updatedate: 1739126402,
         task: "test1".to_string(),
         completed: false,
+        ..Default::default()
     };
     test_db.db.add(&r1).unwrap();
 
     assert!(test_db.db.mark_completion(1, true).is_ok());
-    assert!(test_db.db.read_one(1).unwrap().unwrap().completed);
+    let result = test_db.db.read_one(1).unwrap().unwrap();
+    assert!(result.completed);
+    assert!(result.completed_at.is_some());
 }
 
 #[test]
@@ -156,11 +193,53 @@ fn test_db_mark_completion_false_is_ok() {
         updatedate: 1739126402,
         task: "test1".to_string(),
         completed: true,
+        ..Default::default()
     };
     test_db.db.add(&r1).unwrap();
 
     assert!(test_db.db.mark_completion(1, false).is_ok());
-    assert!(!test_db.db.read_one(1).unwrap().unwrap().completed);
+    let result = test_db.db.read_one(1).unwrap().unwrap();
+    assert!(!result.completed);
+    assert!(result.completed_at.is_none());
+}
+
+#[test]
+fn test_db_set_status_ok() {
+    let test_db = TestDB::new();
+
+    let r1 = DBRow{
+        id: 1,
+        updatedate: 1739126402,
+        task: "test1".to_string(),
+        ..Default::default()
+    };
+    test_db.db.add(&r1).unwrap();
+
+    test_db.db.set_status(1, Status::Waiting).unwrap();
+    let result = test_db.db.read_one(1).unwrap().unwrap();
+    assert_eq!(result.status, Status::Waiting);
+    assert!(!result.completed);
+
+    test_db.db.set_status(1, Status::Done).unwrap();
+    let result = test_db.db.read_one(1).unwrap().unwrap();
+    assert_eq!(result.status, Status::Done);
+    assert!(result.completed);
+    assert!(result.completed_at.is_some());
+
+    assert!(matches!(test_db.db.set_status(2, Status::Done), Err(crate::models::DBError::IDNotFound)));
+}
+
+#[test]
+fn test_db_reads_legacy_completed_flag() {
+    let test_db = TestDB::new();
+    fs::write(&test_db._path, "id;updatedate;task;completed\n1;1739126402;test1;true\n2;1739126402;test2;false\n").unwrap();
+
+    let result = test_db.db.read_all().unwrap();
+    let status = |id: u32| result.iter().find(|r| r.id == id).unwrap().status;
+    assert_eq!(status(1), Status::Done);
+    assert_eq!(status(2), Status::Todo);
+
+    assert_eq!(test_db.db.delete_completed().unwrap(), 1);
 }
 
 #[test]
@@ -174,6 +253,7 @@ fn test_db_get_next_id_is_ok() {
         updatedate: 1739126402,
         task: "test1".to_string(),
         completed: true,
+        ..Default::default()
     };
     test_db.db.add(&r1).unwrap();
 
@@ -184,6 +264,7 @@ fn test_db_get_next_id_is_ok() {
         updatedate: 1739126402,
         task: "test3".to_string(),
         completed: true,
+        ..Default::default()
     };
     test_db.db.add(&r3).unwrap();
 
@@ -199,12 +280,14 @@ fn test_delete_completed_ok() {
         updatedate: 1739126402,
         task: "test1".to_string(),
         completed: false,
+        ..Default::default()
     };
     let r2 = DBRow{
         id: 2,
         updatedate: 1739126602,
         task: "test2".to_string(),
         completed: true,
+        ..Default::default()
     };
 
     test_db.db.add(&r1).unwrap();
@@ -226,12 +309,14 @@ fn test_ordering_ok() {
         updatedate: 1739126402,
         task: "test1".to_string(),
         completed: false,
+        ..Default::default()
     };
     let r2 = DBRow{
         id: 2,
         updatedate: 1739126602,
         task: "test2".to_string(),
         completed: true,
+        ..Default::default()
     };
 
     test_db.db.add(&r1).unwrap();
@@ -243,3 +328,191 @@ fn test_ordering_ok() {
     let unw = result.unwrap();
     assert!(unw[0].id == 2)
 }
+
+#[test]
+fn test_db_write_all_ok() {
+    let test_db = TestDB::new();
+
+    let rows = vec![
+        DBRow{
+            id: 1,
+            updatedate: 1739126402,
+            task: "test1".to_string(),
+            completed: false,
+            tags: vec!["work".to_string(), "home".to_string()],
+            ..Default::default()
+        },
+        DBRow{
+            id: 2,
+            updatedate: 1739126602,
+            task: "test2".to_string(),
+            completed: true,
+            ..Default::default()
+        },
+    ];
+
+    assert!(test_db.db.write_all(&rows).is_ok());
+
+    let result = test_db.db.read_all().unwrap();
+    assert!(result.len() == 2);
+    assert!(result[1].tags == vec!["work", "home"]);
+    assert!(result[0].tags.is_empty());
+}
+
+#[test]
+fn test_db_old_schema_is_upgraded() {
+    let test_db = TestDB::new();
+    fs::write(&test_db._path, "id;updatedate;task;completed\n1;1739126402;test1;false\n").unwrap();
+
+    let r2 = DBRow{
+        id: 2,
+        updatedate: 1739126602,
+        task: "test2".to_string(),
+        completed: false,
+        tags: vec!["work".to_string()],
+        ..Default::default()
+    };
+    test_db.db.add(&r2).unwrap();
+
+    let content = fs::read_to_string(&test_db._path).unwrap();
+    assert!(content.starts_with("id;updatedate;task;completed;tags;"));
+
+    let result = test_db.db.read_all().unwrap();
+    assert!(result.len() == 2);
+    assert!(result[0].tags == vec!["work"]);
+}
+
+#[test]
+fn test_db_update_ok() {
+    let test_db = TestDB::new();
+
+    let mut r1 = DBRow{
+        id: 1,
+        updatedate: 1739126402,
+        task: "test1".to_string(),
+        completed: false,
+        ..Default::default()
+    };
+    test_db.db.add(&r1).unwrap();
+
+    r1.task = "changed".to_string();
+    assert!(test_db.db.update(&r1).is_ok());
+    assert!(test_db.db.read_one(1).unwrap().unwrap().task == "changed");
+
+    r1.id = 2;
+    assert!(test_db.db.update(&r1).is_err());
+}
+
+#[test]
+fn test_db_estimate_and_history_roundtrip() {
+    let test_db = TestDB::new();
+
+    let r = DBRow{
+        id: 1,
+        updatedate: 1739126402,
+        task: "test1".to_string(),
+        completed: true,
+        estimate: Some(3),
+        created: Some(1739000000),
+        completed_at: Some(1739126402),
+        snoozed_until: Some(1739200000),
+        ..Default::default()
+    };
+    test_db.db.add(&r).unwrap();
+
+    let result = test_db.db.read_one(1).unwrap().unwrap();
+    assert_eq!(result.estimate, Some(3));
+    assert_eq!(result.snoozed_until, Some(1739200000));
+    assert_eq!(result.created_at(), 1739000000);
+    assert_eq!(result.done_at(), Some(1739126402));
+}
+
+#[test]
+fn test_db_contexts_recurrence_and_dependencies_roundtrip() {
+    let test_db = TestDB::new();
+
+    let r = DBRow{
+        id: 1,
+        updatedate: 1739126402,
+        task: "test1".to_string(),
+        contexts: vec!["phone".to_string(), "office".to_string()],
+        recurrence: Some(Recurrence::Weekday(chrono::Weekday::Mon)),
+        depends_on: vec![3, 4],
+        ..Default::default()
+    };
+    test_db.db.add(&r).unwrap();
+
+    let result = test_db.db.read_one(1).unwrap().unwrap();
+    assert_eq!(result.contexts, vec!["phone", "office"]);
+    assert_eq!(result.recurrence, Some(Recurrence::Weekday(chrono::Weekday::Mon)));
+    assert_eq!(result.depends_on, vec![3, 4]);
+}
+
+#[test]
+fn test_db_get_is_not_found() {
+    let test_db = TestDB::new();
+    assert!(matches!(test_db.db.get(1), Err(DBError::IDNotFound)));
+
+    test_db.db.add(&DBRow{ id: 1, task: "test1".to_string(), notes: "line 1\nline 2; with a delimiter".to_string(), ..Default::default() }).unwrap();
+
+    assert!(matches!(test_db.db.get(2), Err(DBError::IDNotFound)));
+    assert_eq!(test_db.db.get(1).unwrap().notes, "line 1\nline 2; with a delimiter");
+}
+
+#[test]
+fn test_db_missing_database_names_path() {
+    let test_db = TestDB::new();
+
+    match test_db.db.read_all() {
+        Err(DBError::MissingDatabase(path)) => assert_eq!(path, test_db._path),
+        other => panic!("expected MissingDatabase, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_db_corrupt_row_has_context() {
+    let test_db = TestDB::new();
+    test_db.db.add(&DBRow{ id: 1, task: "test1".to_string(), ..Default::default() }).unwrap();
+    let content = fs::read_to_string(&test_db._path).unwrap();
+    fs::write(&test_db._path, format!("{}2;soon;test2\n", content)).unwrap();
+
+    let err = test_db.db.read_all().unwrap_err();
+
+    assert!(matches!(err, DBError::CorruptRow(_)));
+    let context = err.context().unwrap();
+    assert_eq!(context.path.as_deref(), Some(test_db._path.as_path()));
+    assert_eq!(context.line, Some(3));
+    assert!(std::error::Error::source(&err).is_some());
+    assert_eq!(err.exit_code(), 8);
+}
+
+#[test]
+fn test_db_duplicate_id_is_err() {
+    let test_db = TestDB::new();
+    test_db.db.add(&DBRow{ id: 1, task: "test1".to_string(), ..Default::default() }).unwrap();
+
+    let err = test_db.db.add(&DBRow{ id: 1, task: "test2".to_string(), ..Default::default() }).unwrap_err();
+    assert!(matches!(err, DBError::DuplicateId(_)));
+    assert_eq!(err.context().unwrap().id, Some(1));
+
+    let content = fs::read_to_string(&test_db._path).unwrap();
+    let row = content.lines().nth(1).unwrap().to_string();
+    fs::write(&test_db._path, format!("{}{}\n", content, row)).unwrap();
+
+    let err = test_db.db.read_all().unwrap_err();
+    assert!(matches!(err, DBError::DuplicateId(_)));
+    assert_eq!(err.context().unwrap().line, Some(3));
+}
+
+#[test]
+fn test_db_error_permission_denied() {
+    let path = path::Path::new("db.csv");
+    let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+    let other = std::io::Error::other("disk full");
+
+    assert!(matches!(DBError::read(path, denied), DBError::PermissionDenied(_)));
+    assert!(matches!(DBError::write(path, csv::Error::from(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied"))), DBError::PermissionDenied(_)));
+    let err = DBError::write(path, other);
+    assert!(matches!(err, DBError::WriteError(_)));
+    assert_eq!(err.to_string(), "write error: db.csv: disk full");
+}
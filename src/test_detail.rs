@@ -0,0 +1,146 @@
+#![cfg(test)]
+
+use crate::audit::{AuditEntry, FieldChange};
+use crate::detail::{events, render, Event};
+use crate::models::{DBRow, Priority, Status};
+use crate::timelog::TimeEntry;
+use crate::utils::format_timestamp;
+
+const CREATED: i64 = 1760857200;
+
+fn at(timestamp: i64) -> String {
+    format_timestamp(timestamp, "%Y-%m-%d %H:%M")
+}
+
+fn rows() -> Vec<DBRow> {
+    vec![
+        DBRow {
+            id: 1,
+            task: "Buy phone".to_string(),
+            updatedate: CREATED,
+            ..Default::default()
+        },
+        DBRow {
+            id: 2,
+            task: "Call bob".to_string(),
+            updatedate: CREATED + 7200,
+            created: Some(CREATED),
+            tags: vec!["work".to_string()],
+            contexts: vec!["phone".to_string()],
+            priority: Some(Priority::High),
+            depends_on: vec![1, 9],
+            notes: "Number is in the wiki\nAsk about the invoice".to_string(),
+            tracked: Some(1800),
+            ..Default::default()
+        },
+        DBRow {
+            id: 3,
+            task: "Send summary".to_string(),
+            updatedate: CREATED,
+            depends_on: vec![2],
+            ..Default::default()
+        },
+    ]
+}
+
+#[test]
+fn test_detail_events_in_order() {
+    let mut r = rows()[1].clone();
+    r.set_status(Status::Done, CREATED + 7200);
+    let entries = vec![
+        TimeEntry { id: 2, start: CREATED + 600, end: Some(CREATED + 2400) },
+        TimeEntry { id: 1, start: CREATED + 700, end: None },
+        TimeEntry { id: 2, start: CREATED + 3600, end: None },
+    ];
+
+    let result = events(&r, &entries, &[], CREATED + 4200);
+
+    assert_eq!(result, vec![
+        Event { at: CREATED, text: "created".to_string() },
+        Event { at: CREATED + 600, text: "worked 30m".to_string() },
+        Event { at: CREATED + 3600, text: "started the timer".to_string() },
+        Event { at: CREATED + 7200, text: "completed".to_string() },
+    ]);
+}
+
+#[test]
+fn test_detail_events_last_change() {
+    let r = rows()[1].clone();
+
+    let result = events(&r, &[], &[], CREATED + 7200);
+
+    assert_eq!(result, vec![
+        Event { at: CREATED, text: "created".to_string() },
+        Event { at: CREATED + 7200, text: "last changed".to_string() },
+    ]);
+}
+
+#[test]
+fn test_detail_render_all_fields() {
+    let rows = rows();
+    let history = vec![Event { at: CREATED, text: "created".to_string() }];
+
+    let result = render(&rows[1], &rows, &history);
+
+    assert_eq!(result, format!("\
+#2 Call bob
+-----------
+Status:        todo, blocked
+Priority:      high
+Tags:          +work
+Contexts:      @phone
+Due:           -
+Recurrence:    -
+Estimate:      -
+Time logged:   30m
+Snoozed until: -
+Depends on:    #1 Buy phone (todo), #9 (deleted)
+Blocks:        #3 Send summary (todo)
+Created:       {}
+Updated:       {}
+Completed:     -
+
+Notes
+  Number is in the wiki
+  Ask about the invoice
+
+History
+  {}  created
+", at(CREATED), at(CREATED + 7200), at(CREATED)));
+}
+
+#[test]
+fn test_detail_render_without_notes() {
+    let rows = rows();
+
+    let result = render(&rows[0], &rows, &[]);
+
+    assert!(result.contains("Status:        todo\n"));
+    assert!(result.contains("\nNotes\n  -\n"));
+}
+
+#[test]
+fn test_detail_events_from_audit_log() {
+    let r = rows()[1].clone();
+    let entry = |at: i64, id: u32, changes: Vec<FieldChange>| AuditEntry {
+        at,
+        user: "alice".to_string(),
+        operation: "update".to_string(),
+        id,
+        changes,
+    };
+    let audit = vec![
+        entry(CREATED, 2, vec![FieldChange { field: "task".to_string(), old: None, new: Some("Call bob".to_string()) }]),
+        entry(CREATED + 60, 1, vec![]),
+        entry(CREATED + 7200, 2, vec![FieldChange { field: "priority".to_string(), old: Some(String::new()), new: Some("high".to_string()) }]),
+    ];
+    let entries = vec![TimeEntry { id: 2, start: CREATED + 600, end: Some(CREATED + 2400) }];
+
+    let result = events(&r, &entries, &audit, CREATED + 7200);
+
+    assert_eq!(result, vec![
+        Event { at: CREATED, text: "created by alice".to_string() },
+        Event { at: CREATED + 600, text: "worked 30m".to_string() },
+        Event { at: CREATED + 7200, text: "alice: priority: - -> high".to_string() },
+    ]);
+}
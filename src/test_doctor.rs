@@ -0,0 +1,117 @@
+#![cfg(test)]
+
+use std::fs;
+
+use crate::backup::Backups;
+use crate::doctor::{check_fields, format_problem, Problem, ProblemKind};
+use crate::models::{DBError, DBReader, DBRow, DBWriter};
+use crate::test_db_csv::TestDB;
+
+const HEADER: [&str; 5] = ["id", "updatedate", "task", "completed", "due"];
+
+fn check(line: &str) -> Option<(ProblemKind, Option<u32>)> {
+    let fields: Vec<&str> = line.split(';').collect();
+    check_fields(&HEADER, &fields, 2).map(|p| (p.kind, p.id))
+}
+
+/// A database with a good row followed by `lines`.
+fn db_with(lines: &[&str]) -> TestDB {
+    let test_db = TestDB::new();
+    test_db.db.add(&DBRow{ id: 1, updatedate: 1760857200, task: "test1".to_string(), ..Default::default() }).unwrap();
+
+    let mut content = fs::read_to_string(&test_db._path).unwrap();
+    for line in lines {
+        content.push_str(line);
+        content.push('\n');
+    }
+    fs::write(&test_db._path, content).unwrap();
+
+    test_db
+}
+
+#[test]
+fn test_doctor_check_fields() {
+    assert_eq!(check("1;1760857200;test;false;"), None);
+    assert_eq!(check("1;1760857200;test;true;1760857200"), None);
+    assert_eq!(check("1,1760857200,test,false,"), Some((ProblemKind::Delimiter, None)));
+    assert_eq!(check("1;1760857200;test;with;false;"), Some((ProblemKind::Delimiter, Some(1))));
+    assert_eq!(check("x;1760857200;test;false;"), Some((ProblemKind::Unparsable, None)));
+    assert_eq!(check("1;1760857200;test;yes;"), Some((ProblemKind::BadBoolean, Some(1))));
+    assert_eq!(check("1;;test;false;"), Some((ProblemKind::BadTimestamp, Some(1))));
+    assert_eq!(check("1;1760857200;test;false;soon"), Some((ProblemKind::BadTimestamp, Some(1))));
+    assert_eq!(check("1;1760857200;test;false;99999999999999999"), Some((ProblemKind::BadTimestamp, Some(1))));
+}
+
+#[test]
+fn test_doctor_format_problem() {
+    let mut p = Problem { line: 3, id: Some(2), kind: ProblemKind::BadTimestamp, message: "due 'soon' is not a timestamp".to_string() };
+    assert_eq!(format_problem(&p), "line 3 (#2): bad timestamp: due 'soon' is not a timestamp");
+
+    p.id = None;
+    assert_eq!(format_problem(&p), "line 3: bad timestamp: due 'soon' is not a timestamp");
+}
+
+#[test]
+fn test_doctor_read_lenient_collects_problems() {
+    let test_db = db_with(&[
+        "2,1760857200,test2,false",
+        "3;1760857200;test3;maybe;;;;;;;;;;;;",
+        "4;1760857200;test4;false;;soon;;;;;;;;;;",
+        "1;1760857200;again;false;;;;;;;;;;;;",
+        "5;1760857200;test5;false;;;urgent;;;;;;;;;",
+        "6;1760857200;test6;false;;;;;;;;;;;;",
+    ]);
+
+    assert!(test_db.db.read_all().is_err());
+    let result = test_db.db.read_lenient().unwrap();
+
+    assert_eq!(result.rows.iter().map(|r| r.id).collect::<Vec<u32>>(), vec![1, 6]);
+    let problems: Vec<(u64, ProblemKind)> = result.problems.iter().map(|p| (p.line, p.kind)).collect();
+    assert_eq!(problems, vec![
+        (3, ProblemKind::Delimiter),
+        (4, ProblemKind::BadBoolean),
+        (5, ProblemKind::BadTimestamp),
+        (6, ProblemKind::DuplicateId),
+        (7, ProblemKind::Unparsable),
+    ]);
+    assert_eq!(result.problems[3].message, "ID 1 is also used on line 2");
+}
+
+#[test]
+fn test_doctor_next_id_skips_broken_rows() {
+    let test_db = db_with(&["2;1760857200;test2;maybe;;;;;;;;;;;;"]);
+
+    assert_eq!(test_db.db.get_next_id(), 3);
+}
+
+#[test]
+fn test_doctor_repair() {
+    let test_db = db_with(&["2;1760857200;test2;maybe;;;;;;;;;;;;", "3;1760857200;test3;false;;;;;;;;;;;;"]);
+    let before = fs::read_to_string(&test_db._path).unwrap();
+    // even with automatic backups off
+    let backups = Backups::new(test_db._temp_dir.path().join("backups"), 0);
+
+    let (result, backup) = test_db.db.repair(&backups).unwrap();
+
+    assert_eq!(result.problems.len(), 1);
+    let backup = backup.unwrap();
+    assert!(backup.name.ends_with("-repair.csv"));
+    assert_eq!(backups.list().unwrap(), vec![backup.clone()]);
+    assert_eq!(fs::read_to_string(&backup.path).unwrap(), before);
+    assert_eq!(test_db.db.read_all().unwrap().iter().map(|r| r.id).collect::<Vec<u32>>(), vec![1, 3]);
+    assert!(test_db.db.read_lenient().unwrap().is_healthy());
+}
+
+#[test]
+fn test_doctor_repair_leaves_healthy_database() {
+    let test_db = db_with(&[]);
+    let backups = Backups::new(test_db._temp_dir.path().join("backups"), 10);
+
+    let (result, backup) = test_db.db.repair(&backups).unwrap();
+    assert!(result.is_healthy());
+    assert!(backup.is_none());
+    assert!(backups.list().unwrap().is_empty());
+
+    let missing = TestDB::new();
+    assert!(matches!(missing.db.repair(&backups), Err(DBError::MissingDatabase(_))));
+}
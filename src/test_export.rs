@@ -0,0 +1,87 @@
+#![cfg(test)]
+
+use crate::export::{export, ExportFormat};
+use crate::dates::format_due;
+use crate::models::{DBRow, Priority};
+
+fn rows() -> Vec<DBRow> {
+    vec![
+        DBRow{
+            id: 1,
+            updatedate: 1739126402,
+            task: "buy milk, eggs".to_string(),
+            completed: false,
+            priority: Some(Priority::High),
+            due: Some(1739126402),
+            ..Default::default()
+        },
+        DBRow{
+            id: 2,
+            updatedate: 1739126602,
+            task: "test2".to_string(),
+            completed: true,
+            tags: vec!["work".to_string(), "home".to_string()],
+            created: Some(1739126402),
+            ..Default::default()
+        },
+    ]
+}
+
+#[test]
+fn test_export_todotxt_ok() {
+    let result = export(&rows(), ExportFormat::TodoTxt).unwrap();
+    let lines: Vec<&str> = result.lines().collect();
+
+    assert!(lines[0].starts_with("(A) buy milk, eggs due:2025-02-"));
+    assert!(lines[0].ends_with(" id:1"));
+    assert!(lines[1].starts_with("x 2025-02-"));
+    // completion date, then creation date
+    assert_eq!(lines[1].split(' ').nth(2).unwrap().len(), 10);
+    assert!(lines[1].ends_with(" test2 +work +home id:2"));
+}
+
+#[test]
+fn test_export_ics_ok() {
+    let result = export(&rows(), ExportFormat::Ics).unwrap();
+
+    assert!(result.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(result.ends_with("END:VCALENDAR\r\n"));
+    assert_eq!(result.matches("BEGIN:VTODO").count(), 2);
+    assert!(result.contains("SUMMARY:buy milk\\, eggs\r\n"));
+    assert!(result.contains("CATEGORIES:work,home\r\n"));
+    assert!(result.contains("DUE:20250209T184002Z\r\nPRIORITY:1\r\n"));
+    assert!(result.contains("STATUS:COMPLETED\r\nCOMPLETED:20250209T184322Z\r\n"));
+}
+
+#[test]
+fn test_export_ics_folds_long_lines() {
+    let mut v = rows();
+    v[0].task = "a".repeat(200);
+
+    let result = export(&v, ExportFormat::Ics).unwrap();
+
+    assert!(result.split("\r\n").all(|l| l.len() <= 75));
+    assert!(result.contains("\r\n aaa"));
+}
+
+#[test]
+fn test_export_json_roundtrip_ok() {
+    let result = export(&rows(), ExportFormat::Json).unwrap();
+    let parsed: Vec<DBRow> = serde_json::from_str(&result).unwrap();
+
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[0].task, "buy milk, eggs");
+    assert!(parsed[1].completed);
+    assert_eq!(parsed[1].tags, vec!["work", "home"]);
+}
+
+#[test]
+fn test_export_markdown_ok() {
+    let result = export(&rows(), ExportFormat::Markdown).unwrap();
+
+    let expected = format!(
+        "- [ ] buy milk, eggs !high (due {}) (#1)\n- [x] test2 +work +home (#2)\n",
+        format_due(1739126402),
+    );
+    assert_eq!(result, expected);
+}
@@ -0,0 +1,171 @@
+#![cfg(test)]
+
+use tempfile::TempDir;
+
+use crate::db_csv::DBCSV;
+use crate::merge::{merge_files, merge_rows, same_row, Conflict, Side};
+use crate::models::{DBError, DBRow, DBWriter, Priority, Status};
+use crate::test_db_csv::row;
+
+fn tasks(rows: &[DBRow]) -> Vec<(u32, String)> {
+    rows.iter().map(|r| (r.id, r.task.clone())).collect()
+}
+
+#[test]
+fn test_merge_one_sided_changes() {
+    let base = vec![row(1, 10, "a"), row(2, 10, "b")];
+    let ours = vec![row(1, 20, "a ours"), row(2, 10, "b")];
+    let theirs = vec![row(1, 10, "a"), row(2, 30, "b theirs"), row(3, 30, "c")];
+
+    let result = merge_rows(&base, &ours, &theirs).rows;
+
+    assert_eq!(tasks(&result), vec![
+        (1, "a ours".to_string()),
+        (2, "b theirs".to_string()),
+        (3, "c".to_string()),
+    ]);
+}
+
+#[test]
+fn test_merge_both_changed_later_wins() {
+    let base = vec![row(1, 10, "a"), row(2, 10, "b")];
+    let ours = vec![row(1, 20, "a ours"), row(2, 40, "b ours")];
+    let theirs = vec![row(1, 30, "a theirs"), row(2, 30, "b theirs")];
+
+    let result = merge_rows(&base, &ours, &theirs).rows;
+
+    assert_eq!(tasks(&result), vec![
+        (1, "a theirs".to_string()),
+        (2, "b ours".to_string()),
+    ]);
+}
+
+#[test]
+fn test_merge_deletions() {
+    let base = vec![row(1, 10, "a"), row(2, 10, "b"), row(3, 10, "c")];
+    // 1 deleted by us, 2 deleted by them but changed by us, 3 deleted by both
+    let ours = vec![row(2, 20, "b ours")];
+    let theirs = vec![row(1, 10, "a")];
+
+    let result = merge_rows(&base, &ours, &theirs).rows;
+
+    assert_eq!(tasks(&result), vec![(2, "b ours".to_string())]);
+}
+
+#[test]
+fn test_merge_without_base_keeps_both_sides() {
+    let result = merge_rows(&[], &[row(1, 10, "a")], &[row(2, 10, "b")]).rows;
+
+    assert_eq!(result.len(), 2);
+}
+
+#[test]
+fn test_same_row_compares_fields() {
+    assert!(same_row(&row(1, 10, "a"), &row(1, 10, "a")));
+    assert!(!same_row(&row(1, 10, "a"), &row(1, 10, "b")));
+}
+
+#[test]
+fn test_merge_fields_independently() {
+    let base = vec![row(1, 10, "a")];
+    let ours = vec![DBRow { completed: true, ..row(1, 20, "a") }];
+    let theirs = vec![DBRow { priority: Some(Priority::High), ..row(1, 30, "a theirs") }];
+
+    let result = merge_rows(&base, &ours, &theirs);
+
+    assert!(result.conflicts.is_empty());
+    let r = &result.rows[0];
+    assert!(r.completed);
+    assert_eq!(r.task, "a theirs");
+    assert_eq!(r.priority, Some(Priority::High));
+    assert_eq!(r.updatedate, 30);
+}
+
+#[test]
+fn test_merge_reports_field_conflicts() {
+    let base = vec![row(1, 10, "a")];
+    let ours = vec![row(1, 40, "a ours")];
+    let theirs = vec![row(1, 30, "a theirs")];
+
+    let result = merge_rows(&base, &ours, &theirs);
+
+    assert_eq!(result.rows[0].task, "a ours");
+    assert_eq!(result.conflicts, vec![Conflict::Field {
+        id: 1,
+        field: "task".to_string(),
+        ours: "\"a ours\"".to_string(),
+        theirs: "\"a theirs\"".to_string(),
+        took: Side::Ours,
+    }]);
+    assert!(result.conflicts[0].to_string().starts_with("#1 task: "));
+}
+
+#[test]
+fn test_merge_takes_status_fields_together() {
+    let base = vec![row(1, 10, "a")];
+    let ours = vec![DBRow { completed: true, status: Status::Done, completed_at: Some(20), ..row(1, 20, "a") }];
+    let theirs = vec![DBRow { status: Status::Cancelled, ..row(1, 30, "a") }];
+
+    let result = merge_rows(&base, &ours, &theirs);
+
+    let r = &result.rows[0];
+    assert_eq!((r.status, r.completed, r.completed_at), (Status::Cancelled, false, None));
+    assert_eq!(result.conflicts, vec![Conflict::Field {
+        id: 1,
+        field: "status".to_string(),
+        ours: "\"done\"".to_string(),
+        theirs: "\"cancelled\"".to_string(),
+        took: Side::Theirs,
+    }]);
+
+    // one side alone changing the status takes all three fields from it
+    let result = merge_rows(&base, &ours, &[DBRow { priority: Some(Priority::Low), ..row(1, 30, "a") }]);
+    let r = &result.rows[0];
+    assert_eq!((r.status, r.completed, r.completed_at), (Status::Done, true, Some(20)));
+    assert!(result.conflicts.is_empty());
+}
+
+#[test]
+fn test_merge_reports_deleted_but_changed() {
+    let base = vec![row(1, 10, "a")];
+    let theirs = vec![row(1, 20, "a theirs")];
+
+    let result = merge_rows(&base, &[], &theirs);
+
+    assert_eq!(tasks(&result.rows), vec![(1, "a theirs".to_string())]);
+    assert_eq!(result.conflicts, vec![Conflict::DeletedChanged { id: 1, deleted_by: Side::Ours }]);
+}
+
+#[test]
+fn test_merge_id_collision_renumbers_theirs() {
+    let base = vec![row(1, 10, "a")];
+    let ours = vec![row(1, 10, "a"), row(2, 20, "b ours")];
+    let theirs = vec![row(1, 10, "a"), row(2, 20, "b theirs"), row(3, 20, "c")];
+
+    let result = merge_rows(&base, &ours, &theirs);
+
+    assert_eq!(tasks(&result.rows), vec![
+        (1, "a".to_string()),
+        (2, "b ours".to_string()),
+        (3, "c".to_string()),
+        (4, "b theirs".to_string()),
+    ]);
+    assert_eq!(result.conflicts, vec![Conflict::IdCollision { id: 2, new_id: 4 }]);
+}
+
+#[test]
+fn test_merge_files_missing_base_ok() {
+    let tempdir = TempDir::new().unwrap();
+    let ours = tempdir.path().join("db.csv");
+    let theirs = tempdir.path().join("db (conflicted copy).csv");
+    DBCSV::new(ours.clone()).write_all(&[row(1, 10, "a"), row(2, 10, "b")]).unwrap();
+    DBCSV::new(theirs.clone()).write_all(&[row(1, 20, "a"), row(3, 10, "c")]).unwrap();
+
+    let result = merge_files(&tempdir.path().join("none.csv"), &ours, &theirs).unwrap();
+
+    assert_eq!(result.rows.len(), 3);
+    assert_eq!(result.rows[0].updatedate, 20);
+    assert!(result.conflicts.is_empty());
+
+    assert!(matches!(merge_files(&ours, &ours, &tempdir.path().join("none.csv")), Err(DBError::MissingDatabase(_))));
+}
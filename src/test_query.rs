@@ -0,0 +1,255 @@
+#![cfg(test)]
+
+use chrono::NaiveDate;
+
+use crate::dates::{date_to_timestamp, parse_date};
+use crate::models::{DBRow, Priority, Status};
+use crate::query::{parse_sort, Cmp, Condition, Expr, ParseError, Query, SortField, SortKey};
+
+fn today() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()
+}
+
+fn parse(input: &str) -> Query {
+    Query::parse_with_today(input, today()).unwrap()
+}
+
+fn rows() -> Vec<DBRow> {
+    let day = |d: u32| Some(date_to_timestamp(NaiveDate::from_ymd_opt(2026, 10, d).unwrap()));
+
+    vec![
+        DBRow{
+            id: 1,
+            updatedate: 1739126402,
+            task: "Buy milk".to_string(),
+            completed: false,
+            tags: vec!["home".to_string()],
+            due: day(20),
+            priority: Some(Priority::Low),
+            ..Default::default()
+        },
+        DBRow{
+            id: 2,
+            updatedate: 1739126502,
+            task: "Write report".to_string(),
+            completed: false,
+            tags: vec!["work".to_string()],
+            due: day(18),
+            priority: Some(Priority::High),
+            ..Default::default()
+        },
+        DBRow{
+            id: 3,
+            updatedate: 1739126602,
+            task: "Call bob".to_string(),
+            completed: true,
+            tags: vec!["work".to_string()],
+            ..Default::default()
+        },
+    ]
+}
+
+fn ids_in(query: &str, rows: Vec<DBRow>) -> Vec<u32> {
+    parse(query).apply(rows).iter().map(|r| r.id).collect()
+}
+
+fn ids(query: &str) -> Vec<u32> {
+    parse(query).apply(rows()).iter().map(|r| r.id).collect()
+}
+
+#[test]
+fn test_parse_date_ok() {
+    assert_eq!(parse_date("today", today()).unwrap(), today());
+    assert_eq!(parse_date("Tomorrow", today()).unwrap(), NaiveDate::from_ymd_opt(2026, 10, 20).unwrap());
+    assert_eq!(parse_date("2026-11-01", today()).unwrap(), NaiveDate::from_ymd_opt(2026, 11, 1).unwrap());
+    assert!(parse_date("11/01/2026", today()).is_err());
+}
+
+#[test]
+fn test_parse_date_relative_ok() {
+    let day = |d: u32| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+
+    assert_eq!(parse_date("3d", today()).unwrap(), day(22));
+    assert_eq!(parse_date("+2w", today()).unwrap(), NaiveDate::from_ymd_opt(2026, 11, 2).unwrap());
+    // 2026-10-19 is a Monday, a weekday means the next one
+    assert_eq!(parse_date("fri", today()).unwrap(), day(23));
+    assert_eq!(parse_date("Monday", today()).unwrap(), day(26));
+    assert!(parse_date("d", today()).is_err());
+    assert!(parse_date("3x", today()).is_err());
+    // too far ahead for a date
+    assert!(parse_date("3000000000000000000w", today()).is_err());
+    assert!(parse_date("18446744073709551615d", today()).is_err());
+}
+
+#[test]
+fn test_query_parse_ast_ok() {
+    let q = parse("status:open tag:work");
+
+    assert_eq!(q.filter, Some(Expr::And(
+        Box::new(Expr::Cond(Condition::Open)),
+        Box::new(Expr::Cond(Condition::Tag("work".to_string()))),
+    )));
+    assert!(q.sort.is_empty());
+    assert_eq!(q.limit, None);
+}
+
+#[test]
+fn test_query_parse_context_ok() {
+    assert_eq!(parse("@phone").filter, Some(Expr::Cond(Condition::Context("phone".to_string()))));
+    assert_eq!(parse("context:@office").filter, Some(Expr::Cond(Condition::Context("office".to_string()))));
+    assert_eq!(parse("ctx:home").filter, Some(Expr::Cond(Condition::Context("home".to_string()))));
+}
+
+#[test]
+fn test_query_parse_comparisons_ok() {
+    let q = parse("due<2026-11-01");
+    assert_eq!(q.filter, Some(Expr::Cond(Condition::Due(Cmp::Lt, NaiveDate::from_ymd_opt(2026, 11, 1).unwrap()))));
+
+    let q = parse("priority>=medium");
+    assert_eq!(q.filter, Some(Expr::Cond(Condition::Priority(Cmp::Ge, Priority::Medium))));
+}
+
+#[test]
+fn test_query_empty_matches_all() {
+    assert_eq!(parse("").filter, None);
+    assert_eq!(ids(""), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_query_filters_ok() {
+    assert_eq!(ids("status:open"), vec![1, 2]);
+    assert_eq!(ids("status:done"), vec![3]);
+    assert_eq!(ids("tag:work"), vec![2, 3]);
+    assert_eq!(ids("tag:work status:open"), vec![2]);
+    assert_eq!(ids("tag:home or status:done"), vec![1, 3]);
+    assert_eq!(ids("not tag:work"), vec![1]);
+    assert_eq!(ids("-tag:work"), vec![1]);
+    assert_eq!(ids("due<today"), vec![2]);
+    assert_eq!(ids("due<=tomorrow"), vec![1, 2]);
+    assert_eq!(ids("due:none"), vec![3]);
+    assert_eq!(ids("priority>low"), vec![2]);
+    assert_eq!(ids("id:1,3"), vec![1, 3]);
+}
+
+#[test]
+fn test_query_status_ok() {
+    let mut rows = rows();
+    rows[0].status = Status::Cancelled;
+    rows[1].status = Status::Waiting;
+
+    assert_eq!(ids_in("status:open", rows.clone()), vec![2]);
+    assert_eq!(ids_in("status:waiting", rows.clone()), vec![2]);
+    assert_eq!(ids_in("is:cancelled", rows.clone()), vec![1]);
+    // rows that only set the completion flag count as done
+    assert_eq!(ids_in("status:done", rows.clone()), vec![3]);
+    assert_eq!(ids_in("sort:status", rows), vec![2, 3, 1]);
+}
+
+#[test]
+fn test_query_text_is_case_insensitive() {
+    assert_eq!(ids("\"buy MILK\""), vec![1]);
+    assert_eq!(ids("bob"), vec![3]);
+    assert_eq!(ids("text:report"), vec![2]);
+}
+
+#[test]
+fn test_query_precedence_and_parens() {
+    assert_eq!(ids("status:open and tag:home or tag:work"), vec![1, 2, 3]);
+    assert_eq!(ids("status:open (tag:home or tag:work)"), vec![1, 2]);
+    assert_eq!(ids("not (tag:home or tag:work)"), Vec::<u32>::new());
+}
+
+#[test]
+fn test_query_snoozed_ok() {
+    let day = |d: u32| Some(date_to_timestamp(NaiveDate::from_ymd_opt(2026, 10, d).unwrap()));
+    let mut rows = rows();
+    rows[0].snoozed_until = day(25);
+    // a snooze ending today is over
+    rows[1].snoozed_until = day(19);
+
+    assert_eq!(ids_in("snoozed:yes", rows.clone()), vec![1]);
+    assert_eq!(ids_in("snoozed:no", rows.clone()), vec![2, 3]);
+    assert_eq!(ids_in("snoozed:any", rows), vec![1, 2, 3]);
+
+    assert!(parse("snoozed:any").include_snoozed);
+    assert!(parse("-snoozed:yes").include_snoozed);
+    assert!(!parse("status:open").include_snoozed);
+    assert!(Query::parse_with_today("snoozed:maybe", today()).is_err());
+    assert!(Query::parse_with_today("not snoozed:any", today()).is_err());
+}
+
+#[test]
+fn test_query_sort_and_limit_ok() {
+    let q = parse("status:open sort:-priority limit:1");
+    assert_eq!(q.sort, vec![SortKey { field: SortField::Priority, descending: true }]);
+    assert_eq!(q.limit, Some(1));
+
+    assert_eq!(ids("sort:due"), vec![2, 1, 3]);
+    assert_eq!(ids("sort:-due"), vec![1, 2, 3]);
+    assert_eq!(ids("sort:id limit:2"), vec![1, 2]);
+    assert_eq!(ids("sort:status,-id"), vec![2, 1, 3]);
+}
+
+#[test]
+fn test_parse_sort_is_err() {
+    assert!(parse_sort("due,-priority").is_ok());
+    assert!(parse_sort("colour").is_err());
+}
+
+#[test]
+fn test_query_parse_errors_point_at_problem() {
+    let cases = [
+        ("status:maybe", 7, "unknown status"),
+        ("tag:work colour:red", 9, "unknown field 'colour'"),
+        ("due<soon", 4, "not a valid date"),
+        ("(tag:work", 0, "missing closing ')'"),
+        ("tag:work)", 8, "unexpected ')'"),
+        ("\"milk", 0, "unterminated quote"),
+        ("tag:work or", 9, "expected a condition after 'or'"),
+        ("tag:", 4, "missing value"),
+        ("tag<work", 3, "only supports ':'"),
+        ("limit:x", 6, "not a valid limit"),
+    ];
+
+    for (input, position, message) in cases {
+        let err = Query::parse_with_today(input, today()).unwrap_err();
+        assert_eq!(err.position, position, "{}", input);
+        assert!(err.message.contains(message), "{}: {}", input, err.message);
+    }
+}
+
+#[test]
+fn test_query_parse_errors_point_into_multibyte_and_quoted_words() {
+    let cases = [
+        ("a\"x\"éé:", 9, "missing value"),
+        ("status:\"é\"x", 8, "unknown status"),
+        ("ta\"g\"<work", 5, "only supports ':'"),
+        ("tag:é -\"c\"olour:red", 9, "unknown field"),
+    ];
+
+    for (input, position, message) in cases {
+        let err = Query::parse_with_today(input, today()).unwrap_err();
+        assert_eq!(err.position, position, "{}", input);
+        assert!(err.message.contains(message), "{}: {}", input, err.message);
+    }
+
+    let err = Query::parse_with_today("a\"x\"éé:", today()).unwrap_err();
+    assert_eq!(err.to_string(), "missing value for 'axéé'\n  a\"x\"éé:\n         ^");
+    let inside = ParseError { message: "bad".to_string(), query: "é:".to_string(), position: 1 };
+    assert_eq!(inside.to_string(), "bad\n  é:\n  ^");
+}
+
+#[test]
+fn test_query_parse_error_display_has_caret() {
+    let err = Query::parse_with_today("tag:work colour:red", today()).unwrap_err();
+
+    assert_eq!(err.to_string(), "unknown field 'colour'\n  tag:work colour:red\n           ^");
+}
+
+#[test]
+fn test_query_from_args_quotes_phrases() {
+    let args = vec!["status:open".to_string(), "buy milk".to_string()];
+    let q = Query::from_args(&args).unwrap();
+
+    assert_eq!(q.apply(rows()).len(), 1);
+}
@@ -0,0 +1,223 @@
+#![cfg(test)]
+
+use chrono::{NaiveDate, NaiveDateTime, Weekday};
+
+use crate::dates::{date_to_timestamp, local_timestamp};
+use crate::models::{Priority, Recurrence};
+use crate::quickadd::{parse, QuickAdd};
+
+fn day(m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, m, d).unwrap()
+}
+
+/// Monday 2026-10-19, 09:30.
+fn now() -> NaiveDateTime {
+    day(10, 19).and_hms_opt(9, 30, 0).unwrap()
+}
+
+fn at(date: NaiveDate, h: u32, m: u32) -> Option<i64> {
+    Some(local_timestamp(date.and_hms_opt(h, m, 0).unwrap()))
+}
+
+fn quick(line: &str) -> QuickAdd {
+    let words: Vec<String> = line.split(' ').map(|w| w.to_string()).collect();
+    parse(&words, now()).unwrap()
+}
+
+#[test]
+fn test_quickadd_full_line() {
+    let result = quick("Call bob tomorrow 3pm +work !high @phone every monday");
+
+    assert_eq!(result, QuickAdd {
+        task: "Call bob".to_string(),
+        tags: vec!["work".to_string()],
+        contexts: vec!["phone".to_string()],
+        priority: Some(Priority::High),
+        due: at(day(10, 20), 15, 0),
+        recurrence: Some(Recurrence::Weekday(Weekday::Mon)),
+    });
+}
+
+#[test]
+fn test_quickadd_plain_text_is_unchanged() {
+    let result = quick("Write the report");
+
+    assert_eq!(result, QuickAdd { task: "Write the report".to_string(), ..Default::default() });
+}
+
+#[test]
+fn test_quickadd_text_keeps_its_order() {
+    assert_eq!(quick("Buy +home milk and @shop eggs").task, "Buy milk and eggs");
+}
+
+#[test]
+fn test_quickadd_words_in_one_argument() {
+    let result = parse(&["Pay rent +home tomorrow".to_string()], now()).unwrap();
+
+    assert_eq!(result.task, "Pay rent");
+    assert_eq!(result.tags, vec!["home"]);
+    assert_eq!(result.due, Some(date_to_timestamp(day(10, 20))));
+}
+
+#[test]
+fn test_quickadd_tags_and_contexts() {
+    let result = quick("Plan +work +q4 @Office @home-office");
+
+    assert_eq!(result.tags, vec!["work", "q4"]);
+    assert_eq!(result.contexts, vec!["office", "home-office"]);
+}
+
+#[test]
+fn test_quickadd_bare_markers_stay_text() {
+    let result = quick("Answer @ and + questions !");
+
+    assert_eq!(result.task, "Answer @ and + questions !");
+    assert!(result.tags.is_empty());
+    assert!(result.contexts.is_empty());
+    assert_eq!(result.priority, None);
+}
+
+#[test]
+fn test_quickadd_priority() {
+    assert_eq!(quick("Fix bug !high").priority, Some(Priority::High));
+    assert_eq!(quick("Fix bug !m").priority, Some(Priority::Medium));
+    assert_eq!(quick("Fix bug !low").priority, Some(Priority::Low));
+    assert_eq!(quick("Fix bug !urgent").task, "Fix bug !urgent");
+}
+
+#[test]
+fn test_quickadd_relative_dates() {
+    assert_eq!(quick("Pay rent today").due, Some(date_to_timestamp(day(10, 19))));
+    assert_eq!(quick("Pay rent tonight").due, Some(date_to_timestamp(day(10, 19))));
+    assert_eq!(quick("Pay rent tomorrow").due, Some(date_to_timestamp(day(10, 20))));
+    assert_eq!(quick("Pay rent in 3 days").due, Some(date_to_timestamp(day(10, 22))));
+    assert_eq!(quick("Pay rent in 1 day").due, Some(date_to_timestamp(day(10, 20))));
+    assert_eq!(quick("Pay rent in 2 weeks").due, Some(date_to_timestamp(day(11, 2))));
+    assert_eq!(quick("Pay rent next week").due, Some(date_to_timestamp(day(10, 26))));
+    // too far ahead for a date, so just text
+    assert_eq!(quick("Pay rent in 3000000000000000000 weeks").due, None);
+    assert_eq!(quick("Pay rent in 3000000000000000000 weeks").task, "Pay rent in 3000000000000000000 weeks");
+}
+
+#[test]
+fn test_quickadd_weekdays() {
+    assert_eq!(quick("Pay rent friday").due, Some(date_to_timestamp(day(10, 23))));
+    assert_eq!(quick("Pay rent next friday").due, Some(date_to_timestamp(day(10, 23))));
+    // today is a monday, so monday means next week's
+    assert_eq!(quick("Pay rent monday").due, Some(date_to_timestamp(day(10, 26))));
+    // short names are too likely words
+    assert_eq!(quick("Fix the sun roof").due, None);
+}
+
+#[test]
+fn test_quickadd_connectors() {
+    let result = quick("Meet ann on friday at 10:30");
+    assert_eq!(result.task, "Meet ann");
+    assert_eq!(result.due, at(day(10, 23), 10, 30));
+
+    let result = quick("Submit form by 2026-11-05");
+    assert_eq!(result.task, "Submit form");
+    assert_eq!(result.due, Some(date_to_timestamp(day(11, 5))));
+
+    // kept when nothing follows that they connect
+    assert_eq!(quick("Look at the map").task, "Look at the map");
+    assert_eq!(quick("Read book on cooking").task, "Read book on cooking");
+}
+
+#[test]
+fn test_quickadd_times() {
+    assert_eq!(quick("Call bob 3pm").due, at(day(10, 19), 15, 0));
+    assert_eq!(quick("Call bob at 3:30pm").due, at(day(10, 19), 15, 30));
+    assert_eq!(quick("Call bob 12am tomorrow").due, at(day(10, 20), 0, 0));
+    assert_eq!(quick("Call bob 12pm").due, at(day(10, 19), 12, 0));
+    assert_eq!(quick("Call bob 18:45").due, at(day(10, 19), 18, 45));
+    // numbers alone are no times
+    assert_eq!(quick("Buy 3 apples").task, "Buy 3 apples");
+    assert_eq!(quick("Buy 3 apples").due, None);
+    assert_eq!(quick("Sleep 13pm").task, "Sleep 13pm");
+}
+
+#[test]
+fn test_quickadd_due_prefix() {
+    assert_eq!(quick("Pay rent due:3d").due, Some(date_to_timestamp(day(10, 22))));
+    assert_eq!(quick("Pay rent due friday").due, Some(date_to_timestamp(day(10, 23))));
+
+    let words = vec!["Pay".to_string(), "due:someday".to_string()];
+    assert!(parse(&words, now()).is_err());
+}
+
+#[test]
+fn test_quickadd_only_first_date_is_taken() {
+    let result = quick("Move meeting from tomorrow to friday");
+
+    assert_eq!(result.task, "Move meeting from to friday");
+    assert_eq!(result.due, Some(date_to_timestamp(day(10, 20))));
+}
+
+#[test]
+fn test_quickadd_recurrences() {
+    assert_eq!(quick("Water plants every day").recurrence, Some(Recurrence::Days(1)));
+    assert_eq!(quick("Water plants every 3 days").recurrence, Some(Recurrence::Days(3)));
+    assert_eq!(quick("Review every 2 weeks").recurrence, Some(Recurrence::Weeks(2)));
+    assert_eq!(quick("Pay rent every month").recurrence, Some(Recurrence::Months(1)));
+    assert_eq!(quick("Renew every year").recurrence, Some(Recurrence::Years(1)));
+    assert_eq!(quick("Gym every friday").recurrence, Some(Recurrence::Weekday(Weekday::Fri)));
+    assert_eq!(quick("Water plants every day").task, "Water plants");
+
+    let result = quick("Give every penny");
+    assert_eq!(result.task, "Give every penny");
+    assert_eq!(result.recurrence, None);
+}
+
+#[test]
+fn test_quickadd_recurrence_sets_due() {
+    assert_eq!(quick("Gym every friday").due, Some(date_to_timestamp(day(10, 23))));
+    // today is a monday
+    assert_eq!(quick("Standup every monday").due, Some(date_to_timestamp(day(10, 19))));
+    assert_eq!(quick("Water plants every day").due, Some(date_to_timestamp(day(10, 19))));
+    assert_eq!(quick("Gym every friday at 7am").due, at(day(10, 23), 7, 0));
+    assert_eq!(quick("Pay rent every month on 2026-11-01").due, Some(date_to_timestamp(day(11, 1))));
+}
+
+#[test]
+fn test_quickadd_empty_task_is_err() {
+    let words = vec!["tomorrow".to_string(), "+work".to_string()];
+
+    assert!(parse(&words, now()).is_err());
+    assert!(parse(&[], now()).is_err());
+}
+
+#[test]
+fn test_recurrence_parse_and_display() {
+    let cases = [
+        ("every day", Recurrence::Days(1), "every day"),
+        ("daily", Recurrence::Days(1), "every day"),
+        ("every 2 weeks", Recurrence::Weeks(2), "every 2 weeks"),
+        ("weekly", Recurrence::Weeks(1), "every week"),
+        ("3 months", Recurrence::Months(3), "every 3 months"),
+        ("yearly", Recurrence::Years(1), "every year"),
+        ("every Monday", Recurrence::Weekday(Weekday::Mon), "every monday"),
+    ];
+
+    for (input, expected, shown) in cases {
+        let r: Recurrence = input.parse().unwrap();
+        assert_eq!(r, expected, "{}", input);
+        assert_eq!(r.to_string(), shown);
+        assert_eq!(shown.parse::<Recurrence>().unwrap(), r);
+    }
+    assert!("every mon".parse::<Recurrence>().is_err());
+    assert!("every 0 days".parse::<Recurrence>().is_err());
+    assert!("sometimes".parse::<Recurrence>().is_err());
+}
+
+#[test]
+fn test_recurrence_next() {
+    assert_eq!(Recurrence::Days(2).next(day(10, 19)), Some(day(10, 21)));
+    assert_eq!(Recurrence::Weeks(1).next(day(10, 19)), Some(day(10, 26)));
+    assert_eq!(Recurrence::Months(1).next(day(10, 31)), Some(day(11, 30)));
+    assert_eq!(Recurrence::Years(1).next(day(10, 19)), Some(NaiveDate::from_ymd_opt(2027, 10, 19).unwrap()));
+    assert_eq!(Recurrence::Weekday(Weekday::Fri).next(day(10, 19)), Some(day(10, 23)));
+    assert_eq!(Recurrence::Weekday(Weekday::Mon).next(day(10, 19)), Some(day(10, 26)));
+    assert_eq!(Recurrence::Years(1000000000).next(day(10, 19)), None);
+    assert_eq!(Recurrence::Days(u32::MAX).next(day(10, 19)), None);
+}
@@ -0,0 +1,156 @@
+#![cfg(all(test, unix))]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::thread;
+
+use tempfile::TempDir;
+
+use crate::models::DBRow;
+use crate::remind::{
+    due_within, format_message, CommandNotifier, MailNotifier, Notifier, SentLog, StdoutNotifier, WebhookNotifier,
+};
+
+const NOW: i64 = 1_800_000_000;
+const DAY: i64 = 86400;
+
+fn rows() -> Vec<DBRow> {
+    let row = |id: u32, due: Option<i64>, completed: bool| DBRow {
+        id,
+        updatedate: NOW - DAY,
+        task: format!("task{}", id),
+        completed,
+        due,
+        ..Default::default()
+    };
+
+    vec![
+        row(1, Some(NOW + DAY / 2), false),
+        row(2, Some(NOW - DAY), false),
+        row(3, Some(NOW + 3 * DAY), false),
+        row(4, Some(NOW - DAY), true),
+        row(5, None, false),
+    ]
+}
+
+/// Executable shell script in `dir` running `body`.
+fn script(dir: &TempDir, body: &str) -> String {
+    let path = dir.path().join("script.sh");
+    fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn test_remind_due_within() {
+    let due = due_within(&rows(), NOW, DAY);
+
+    let ids: Vec<u32> = due.iter().map(|r| r.id).collect();
+    assert_eq!(ids, vec![2, 1]);
+}
+
+#[test]
+fn test_remind_message() {
+    let (subject, body) = format_message(&due_within(&rows(), NOW, DAY), NOW);
+
+    assert_eq!(subject, "2 tasks due");
+    let lines: Vec<&str> = body.lines().collect();
+    assert!(lines[0].starts_with("#2 task2 (due ") && lines[0].ends_with(", overdue)"));
+    assert!(lines[1].starts_with("#1 task1 (due ") && !lines[1].contains("overdue"));
+}
+
+#[test]
+fn test_remind_sent_log_skips_repeats() {
+    let tempdir = TempDir::new().unwrap();
+    let log = SentLog::new(tempdir.path().join("reminders.json"));
+    let due = due_within(&rows(), NOW, DAY);
+
+    assert_eq!(log.unsent(&due).unwrap().len(), 2);
+    log.record(&due, &due).unwrap();
+    assert!(log.unsent(&due).unwrap().is_empty());
+
+    // a new due date is announced again
+    let mut moved = due.clone();
+    moved[0].due = Some(NOW + 600);
+    assert_eq!(log.unsent(&moved).unwrap().len(), 1);
+
+    // tasks that are no longer due are forgotten
+    log.record(&[], &due[..1]).unwrap();
+    assert_eq!(log.load().unwrap().len(), 1);
+}
+
+#[test]
+fn test_remind_stdout_notifier() {
+    let mut notifier = StdoutNotifier { out: Vec::new() };
+
+    notifier.notify(&rows()[..1], NOW).unwrap();
+
+    let out = String::from_utf8(notifier.out).unwrap();
+    assert!(out.starts_with("1 task due:\n#1 task1"));
+}
+
+#[test]
+fn test_remind_command_notifier() {
+    let tempdir = TempDir::new().unwrap();
+    let out = tempdir.path().join("out");
+    let mut notifier = CommandNotifier {
+        command: format!("{} --urgency=low", script(&tempdir, &format!("echo \"$@\" > {}", out.display()))),
+    };
+
+    notifier.notify(&rows()[..1], NOW).unwrap();
+
+    let args = fs::read_to_string(&out).unwrap();
+    assert!(args.starts_with("--urgency=low 1 task due #1 task1"));
+
+    let mut failing = CommandNotifier { command: script(&tempdir, "exit 1") };
+    assert!(failing.notify(&rows()[..1], NOW).is_err());
+}
+
+#[test]
+fn test_remind_mail_notifier() {
+    let tempdir = TempDir::new().unwrap();
+    let out = tempdir.path().join("mail");
+    let mut notifier = MailNotifier {
+        sendmail: format!("{} -i", script(&tempdir, &format!("echo \"$@\" > {0}; cat >> {0}", out.display()))),
+        to: "me@example.com".to_string(),
+    };
+
+    notifier.notify(&rows()[..1], NOW).unwrap();
+
+    let mail = fs::read_to_string(&out).unwrap();
+    assert!(mail.starts_with("-i me@example.com\nTo: me@example.com\nSubject: 1 task due\n"));
+    assert!(mail.contains("\n\n#1 task1"));
+}
+
+#[test]
+fn test_remind_webhook_notifier() {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hooks/todo", server.server_addr());
+
+    let handle = thread::spawn(move || {
+        let mut request = server.recv().unwrap();
+        let mut body = String::new();
+        request.as_reader().read_to_string(&mut body).unwrap();
+        let received = (request.method().to_string(), request.url().to_string(), body);
+        request.respond(tiny_http::Response::empty(204)).unwrap();
+        received
+    });
+
+    let mut notifier = WebhookNotifier { url };
+    notifier.notify(&rows()[..2], NOW).unwrap();
+
+    let (method, path, body) = handle.join().unwrap();
+    assert_eq!(method, "POST");
+    assert_eq!(path, "/hooks/todo");
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["subject"], "2 tasks due");
+    assert_eq!(json["tasks"][1]["id"], 2);
+}
+
+#[test]
+fn test_remind_webhook_rejects_https() {
+    let mut notifier = WebhookNotifier { url: "https://example.com/hook".to_string() };
+
+    assert!(notifier.notify(&rows()[..1], NOW).is_err());
+}
@@ -0,0 +1,187 @@
+#![cfg(test)]
+
+use std::io::{Cursor, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::db_csv::DBCSV;
+use crate::models::{DBReader, DBWriter};
+use crate::rpc::{RpcSession, CHANGED_NOTIFICATION, CONFLICT, INVALID_PARAMS, METHOD_NOT_FOUND, NOT_FOUND, PARSE_ERROR};
+use crate::test_db_csv::{seeded_db, TestDB};
+
+fn session(test_db: &TestDB) -> RpcSession<DBCSV> {
+    RpcSession::new(DBCSV::new(test_db._path.clone()))
+}
+
+fn call(session: &RpcSession<DBCSV>, method: &str, params: Value) -> Value {
+    let line = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+    session.handle_line(&line).0.unwrap()
+}
+
+fn error_code(response: &Value) -> i64 {
+    response["error"]["code"].as_i64().unwrap()
+}
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_rpc_list_and_search_ok() {
+    let test_db = seeded_db(3);
+    let session = session(&test_db);
+
+    let response = call(&session, "list", json!({ "query": "status:open sort:id" }));
+    let ids: Vec<u64> = response["result"].as_array().unwrap().iter()
+        .map(|r| r["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![1, 2]);
+
+    let response = call(&session, "search", json!({ "text": "TASK3" }));
+    assert_eq!(response["result"][0]["id"], 3);
+}
+
+#[test]
+fn test_rpc_get_and_not_found() {
+    let test_db = seeded_db(3);
+    let session = session(&test_db);
+
+    let response = call(&session, "get", json!({ "id": 2 }));
+    assert_eq!(response["result"]["task"]["tags"], json!(["work"]));
+    assert!(response["result"]["etag"].is_string());
+
+    let response = call(&session, "get", json!({ "id": 99 }));
+    assert_eq!(error_code(&response), NOT_FOUND);
+}
+
+#[test]
+fn test_rpc_add_update_delete_ok() {
+    let test_db = seeded_db(3);
+    let session = session(&test_db);
+
+    let response = call(&session, "add", json!({ "task": "new", "priority": "high" }));
+    assert_eq!(response["result"]["task"]["id"], 4);
+
+    let etag = response["result"]["etag"].clone();
+    let response = call(&session, "update", json!({ "id": 4, "task": "renamed", "etag": etag }));
+    assert_eq!(response["result"]["task"]["task"], "renamed");
+    assert_eq!(test_db.db.read_one(4).unwrap().unwrap().task, "renamed");
+
+    let response = call(&session, "delete", json!({ "id": 4 }));
+    assert_eq!(response["result"]["id"], 4);
+    assert!(test_db.db.read_one(4).unwrap().is_none());
+}
+
+#[test]
+fn test_rpc_stale_etag_is_conflict() {
+    let test_db = seeded_db(3);
+    let session = session(&test_db);
+
+    let response = call(&session, "update", json!({ "id": 1, "task": "x", "etag": "\"0\"" }));
+
+    assert_eq!(error_code(&response), CONFLICT);
+    assert_eq!(test_db.db.read_one(1).unwrap().unwrap().task, "task1");
+}
+
+#[test]
+fn test_rpc_duplicate_id_is_conflict() {
+    let test_db = seeded_db(3);
+    let session = session(&test_db);
+    let mut content = std::fs::read_to_string(&test_db._path).unwrap();
+    content.push_str("1;1739126402;again;false;todo;;;;;;;;;;;\n");
+    std::fs::write(&test_db._path, content).unwrap();
+
+    assert_eq!(error_code(&call(&session, "list", json!({}))), CONFLICT);
+}
+
+#[test]
+fn test_rpc_complete_ok() {
+    let test_db = seeded_db(3);
+    let session = session(&test_db);
+
+    let response = call(&session, "complete", json!({ "ids": [1, 3, 9] }));
+    assert_eq!(response["result"], json!([
+        { "id": 1, "outcome": "completed" },
+        { "id": 3, "outcome": "unchanged" },
+        { "id": 9, "outcome": "not found" },
+    ]));
+
+    call(&session, "complete", json!({ "id": 1, "completed": false }));
+    assert!(!test_db.db.read_one(1).unwrap().unwrap().completed);
+}
+
+#[test]
+fn test_rpc_errors() {
+    let test_db = seeded_db(3);
+    let session = session(&test_db);
+
+    assert_eq!(error_code(&session.handle_line("{nope").0.unwrap()), PARSE_ERROR);
+    assert_eq!(error_code(&call(&session, "explode", json!({}))), METHOD_NOT_FOUND);
+    assert_eq!(error_code(&call(&session, "get", json!({ "id": "one" }))), INVALID_PARAMS);
+    assert_eq!(error_code(&call(&session, "list", json!({ "query": "status:maybe" }))), INVALID_PARAMS);
+    assert_eq!(error_code(&call(&session, "add", json!({ "task": "" }))), INVALID_PARAMS);
+    assert_eq!(error_code(&call(&session, "add", json!({ "task": "x", "due": 99999999999999999i64 }))), INVALID_PARAMS);
+    assert_eq!(error_code(&call(&session, "update", json!({ "id": 1, "snoozed_until": -99999999999999999i64 }))), INVALID_PARAMS);
+    // nothing out of range was stored
+    assert!(test_db.db.read_all().is_ok());
+}
+
+#[test]
+fn test_rpc_batch_and_notifications() {
+    let test_db = seeded_db(3);
+    let session = session(&test_db);
+
+    let line = json!([
+        { "jsonrpc": "2.0", "id": 1, "method": "get", "params": { "id": 1 } },
+        { "jsonrpc": "2.0", "method": "add", "params": { "task": "quiet" } },
+    ]).to_string();
+    let (response, changed) = session.handle_line(&line);
+
+    assert_eq!(response.unwrap().as_array().unwrap().len(), 1);
+    assert!(changed);
+    assert_eq!(test_db.db.read_one(4).unwrap().unwrap().task, "quiet");
+}
+
+#[test]
+fn test_rpc_detects_external_changes() {
+    let test_db = seeded_db(3);
+    let session = session(&test_db);
+
+    assert!(!session.changed());
+    test_db.db.delete(2).unwrap();
+    assert!(session.changed());
+    assert!(!session.changed());
+}
+
+#[test]
+fn test_rpc_run_writes_responses_and_notifications() {
+    let test_db = seeded_db(3);
+    let session = session(&test_db);
+    let output = SharedBuffer::default();
+
+    let input = [
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "complete", "params": { "id": 1 } }).to_string(),
+        String::new(),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "get", "params": { "id": 1 } }).to_string(),
+    ].join("\n");
+    session.run(Cursor::new(input), output.clone(), Duration::from_secs(60));
+
+    let written = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    let lines: Vec<Value> = written.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["id"], 1);
+    assert_eq!(lines[1]["method"], CHANGED_NOTIFICATION);
+    assert_eq!(lines[2]["result"]["task"]["completed"], true);
+}
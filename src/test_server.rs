@@ -0,0 +1,194 @@
+#![cfg(test)]
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::thread;
+
+use serde_json::Value;
+
+use crate::db_csv::DBCSV;
+use crate::models::{DBError, DBReader, DBRow, ErrorContext};
+use crate::server::{error_status, etag, handle, serve, ApiRequest};
+use crate::test_db_csv::{seeded_db, TestDB};
+
+fn request(method: &str, url: &str, body: &str) -> ApiRequest {
+    ApiRequest {
+        method: method.to_string(),
+        url: url.to_string(),
+        if_match: None,
+        body: body.to_string(),
+    }
+}
+
+/// Sends one raw HTTP/1.1 request and returns status, headers and body.
+fn http(addr: &str, method: &str, path: &str, headers: &[(&str, &str)], body: &str) -> (u16, String, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let mut raw = format!("{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Length: {}\r\n", method, path, addr, body.len());
+    for (name, value) in headers {
+        raw.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    raw.push_str("\r\n");
+    raw.push_str(body);
+    stream.write_all(raw.as_bytes()).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+
+    (status, head.to_string(), body.to_string())
+}
+
+fn header(head: &str, name: &str) -> String {
+    head.lines()
+        .find_map(|l| l.split_once(": ").filter(|(n, _)| n.eq_ignore_ascii_case(name)))
+        .map(|(_, v)| v.to_string())
+        .unwrap()
+}
+
+#[test]
+fn test_server_list_with_filter() {
+    let test_db = seeded_db(3);
+
+    let response = handle(&test_db.db, &request("GET", "/tasks?q=status%3Aopen&sort=id", ""));
+    assert_eq!(response.status, 200);
+
+    let rows: Vec<DBRow> = serde_json::from_str(&response.body).unwrap();
+    assert_eq!(rows.iter().map(|r| r.id).collect::<Vec<u32>>(), vec![1, 2]);
+
+    let response = handle(&test_db.db, &request("GET", "/tasks?q=tag:work+or+id:3&limit=1", ""));
+    let rows: Vec<DBRow> = serde_json::from_str(&response.body).unwrap();
+    assert_eq!(rows.len(), 1);
+}
+
+#[test]
+fn test_server_list_errors() {
+    let test_db = seeded_db(3);
+
+    assert_eq!(handle(&test_db.db, &request("GET", "/tasks?q=colour:red", "")).status, 400);
+    assert_eq!(handle(&test_db.db, &request("GET", "/tasks?x=1", "")).status, 400);
+    assert_eq!(handle(&test_db.db, &request("DELETE", "/tasks", "")).status, 405);
+    assert_eq!(handle(&test_db.db, &request("GET", "/nothing", "")).status, 404);
+    assert_eq!(handle(&test_db.db, &request("GET", "/tasks/abc", "")).status, 404);
+}
+
+#[test]
+fn test_server_empty_db() {
+    let test_db = TestDB::new();
+
+    let response = handle(&test_db.db, &request("GET", "/tasks", ""));
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, "[]");
+    assert_eq!(handle(&test_db.db, &request("GET", "/tasks/1", "")).status, 404);
+}
+
+#[test]
+fn test_server_create_validation() {
+    let test_db = seeded_db(3);
+
+    assert_eq!(handle(&test_db.db, &request("POST", "/tasks", "not json")).status, 400);
+    assert_eq!(handle(&test_db.db, &request("POST", "/tasks", "[1]")).status, 400);
+    assert_eq!(handle(&test_db.db, &request("POST", "/tasks", r#"{"completed": true}"#)).status, 422);
+    assert_eq!(handle(&test_db.db, &request("POST", "/tasks", r#"{"task": "x", "completed": "yes"}"#)).status, 422);
+    assert_eq!(handle(&test_db.db, &request("POST", "/tasks", r#"{"task": "x", "due": 99999999999999999}"#)).status, 422);
+    assert_eq!(handle(&test_db.db, &request("PATCH", "/tasks/1", r#"{"created": 99999999999999999}"#)).status, 422);
+    assert_eq!(test_db.db.read_all().unwrap().len(), 3);
+}
+
+#[test]
+fn test_server_error_status() {
+    let context = || ErrorContext::default();
+
+    assert_eq!(error_status(&DBError::IDNotFound), 404);
+    assert_eq!(error_status(&DBError::MissingDatabase("db.csv".into())), 404);
+    assert_eq!(error_status(&DBError::DuplicateId(context())), 409);
+    assert_eq!(error_status(&DBError::CorruptRow(context())), 503);
+    assert_eq!(error_status(&DBError::PermissionDenied(context())), 503);
+    assert_eq!(error_status(&DBError::WriteError(context())), 503);
+}
+
+#[test]
+fn test_server_put_replaces_and_patch_merges() {
+    let test_db = seeded_db(3);
+
+    let response = handle(&test_db.db, &request("PATCH", "/tasks/2", r#"{"completed": true}"#));
+    assert_eq!(response.status, 200);
+    let r = test_db.db.read_one(2).unwrap().unwrap();
+    assert!(r.completed);
+    assert_eq!(r.tags, vec!["work"]);
+
+    let response = handle(&test_db.db, &request("PUT", "/tasks/2", r#"{"task": "new", "id": 99}"#));
+    assert_eq!(response.status, 200);
+    let r = test_db.db.read_one(2).unwrap().unwrap();
+    assert_eq!(r.task, "new");
+    assert!(!r.completed);
+    assert!(r.tags.is_empty());
+
+    assert_eq!(handle(&test_db.db, &request("PUT", "/tasks/42", r#"{"task": "x"}"#)).status, 404);
+}
+
+#[test]
+fn test_server_if_match() {
+    let test_db = seeded_db(3);
+    let current = etag(&test_db.db.read_one(1).unwrap().unwrap());
+
+    let mut stale = request("PATCH", "/tasks/1", r#"{"task": "x"}"#);
+    stale.if_match = Some("\"0000000000000000\"".to_string());
+    let response = handle(&test_db.db, &stale);
+    assert_eq!(response.status, 412);
+    assert_eq!(response.header("ETag"), Some(current.as_str()));
+
+    let mut fresh = request("PATCH", "/tasks/1", r#"{"task": "x"}"#);
+    fresh.if_match = Some(current.clone());
+    let response = handle(&test_db.db, &fresh);
+    assert_eq!(response.status, 200);
+    assert_ne!(response.header("ETag"), Some(current.as_str()));
+
+    let mut any = request("DELETE", "/tasks/1", "");
+    any.if_match = Some("*".to_string());
+    assert_eq!(handle(&test_db.db, &any).status, 204);
+}
+
+#[test]
+fn test_server_over_http() {
+    let test_db = seeded_db(3);
+    let server = Arc::new(tiny_http::Server::http("127.0.0.1:0").unwrap());
+    let addr = server.server_addr().to_ip().unwrap().to_string();
+
+    let path = test_db._path.clone();
+    let handle = {
+        let server = Arc::clone(&server);
+        thread::spawn(move || serve(&DBCSV::new(path), &server))
+    };
+
+    let (status, head, body) = http(&addr, "POST", "/tasks", &[], r#"{"task": "from http", "tags": ["api"]}"#);
+    assert_eq!(status, 201);
+    assert_eq!(header(&head, "Location"), "/tasks/4");
+    let created: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(created["task"], "from http");
+    let tag = header(&head, "ETag");
+
+    let (status, head, _) = http(&addr, "GET", "/tasks/4", &[], "");
+    assert_eq!(status, 200);
+    assert_eq!(header(&head, "ETag"), tag);
+
+    let (status, _, body) = http(&addr, "GET", "/tasks?q=tag:api", &[], "");
+    assert_eq!(status, 200);
+    assert_eq!(serde_json::from_str::<Vec<DBRow>>(&body).unwrap().len(), 1);
+
+    let (status, _, _) = http(&addr, "DELETE", "/tasks/4", &[("If-Match", "\"stale\"")], "");
+    assert_eq!(status, 412);
+
+    let (status, _, body) = http(&addr, "DELETE", "/tasks/4", &[("If-Match", &tag)], "");
+    assert_eq!(status, 204);
+    assert!(body.is_empty());
+
+    let (status, _, _) = http(&addr, "GET", "/tasks/4", &[], "");
+    assert_eq!(status, 404);
+
+    server.unblock();
+    handle.join().unwrap();
+    assert_eq!(test_db.db.read_all().unwrap().len(), 3);
+}
@@ -0,0 +1,107 @@
+#![cfg(test)]
+
+use chrono::NaiveDate;
+
+use crate::dates::date_to_timestamp;
+use crate::models::{DBRow, Priority};
+use crate::stats::{compute, render_text, to_json, DAYS, WEEKS};
+
+fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+}
+
+/// Timestamp at noon of the given October day.
+fn at(d: u32) -> i64 {
+    date_to_timestamp(day(d)) + 12 * 3600
+}
+
+fn row(id: u32, created: u32, done: Option<u32>) -> DBRow {
+    DBRow {
+        id,
+        updatedate: at(done.unwrap_or(created)),
+        task: format!("task{}", id),
+        completed: done.is_some(),
+        created: Some(at(created)),
+        completed_at: done.map(at),
+        ..Default::default()
+    }
+}
+
+fn rows() -> Vec<DBRow> {
+    vec![
+        row(1, 1, Some(3)),
+        row(2, 2, Some(17)),
+        row(3, 5, Some(18)),
+        DBRow { tags: vec!["work".to_string()], priority: Some(Priority::High), ..row(4, 2, None) },
+        DBRow { tags: vec!["work".to_string(), "home".to_string()], ..row(5, 10, None) },
+        row(6, 12, Some(19)),
+    ]
+}
+
+#[test]
+fn test_stats_counts() {
+    let stats = compute(&rows(), day(19));
+
+    assert_eq!((stats.total, stats.open, stats.completed), (6, 2, 4));
+    assert_eq!(stats.tags["work"], 2);
+    assert_eq!(stats.tags["home"], 1);
+    assert_eq!(stats.priorities["high"], 1);
+    assert_eq!(stats.priorities["none"], 5);
+}
+
+#[test]
+fn test_stats_completed_per_day_and_week() {
+    let stats = compute(&rows(), day(19));
+
+    assert_eq!(stats.completed_per_day.len(), DAYS as usize);
+    let last = stats.completed_per_day.last().unwrap();
+    assert_eq!((last.date, last.count), (day(19), 1));
+    assert_eq!(stats.completed_per_day.iter().map(|d| d.count).sum::<usize>(), 3);
+
+    assert_eq!(stats.completed_per_week.len(), WEEKS as usize);
+    // 2026-10-19 is a Monday
+    let last = stats.completed_per_week.last().unwrap();
+    assert_eq!((last.date, last.count), (day(19), 1));
+    assert_eq!(stats.completed_per_week[WEEKS as usize - 2].count, 2);
+}
+
+#[test]
+fn test_stats_average_and_oldest() {
+    let stats = compute(&rows(), day(19));
+
+    // 2, 15, 13 and 7 days
+    assert_eq!(stats.average_completion_seconds, Some(37 * 86400 / 4));
+    let oldest: Vec<(u32, i64)> = stats.oldest_open.iter().map(|t| (t.id, t.age_days)).collect();
+    assert_eq!(oldest, vec![(4, 17), (5, 9)]);
+}
+
+#[test]
+fn test_stats_streak() {
+    assert_eq!(compute(&rows(), day(19)).streak, 3);
+    // nothing completed today yet, the streak still counts
+    assert_eq!(compute(&rows(), day(20)).streak, 3);
+    assert_eq!(compute(&rows(), day(21)).streak, 0);
+}
+
+#[test]
+fn test_stats_empty() {
+    let stats = compute(&[], day(19));
+
+    assert_eq!(stats.total, 0);
+    assert_eq!(stats.average_completion_seconds, None);
+    assert!(render_text(&stats).contains("0 total"));
+}
+
+#[test]
+fn test_stats_render() {
+    let stats = compute(&rows(), day(19));
+
+    let text = render_text(&stats);
+    assert!(text.contains("6 total, 2 open, 4 completed"));
+    assert!(text.contains("Streak:     3 days"));
+    assert!(text.contains("+work"));
+
+    let json: serde_json::Value = serde_json::from_str(&to_json(&stats).unwrap()).unwrap();
+    assert_eq!(json["open"], 2);
+    assert_eq!(json["completed_per_day"][13]["date"], "2026-10-19");
+}
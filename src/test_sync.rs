@@ -0,0 +1,148 @@
+#![cfg(test)]
+
+use std::process::Command;
+
+use tempfile::TempDir;
+
+use crate::models::{DBError, DBReader, DBWriter};
+use crate::sync::GitSync;
+use crate::test_db_csv::{row, TestDB};
+
+/// Bare repository standing in for the shared remote.
+fn bare_remote(dir: &TempDir) -> String {
+    let path = dir.path().join("remote.git");
+    let status = Command::new("git")
+        .args(["init", "-q", "--bare"])
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    path.to_string_lossy().to_string()
+}
+
+fn machine(dir: &TempDir, name: &str, remote: &str) -> GitSync {
+    let git = GitSync::new(dir.path().join(name));
+    git.set_remote(remote).unwrap();
+    git
+}
+
+fn task(test_db: &TestDB, id: u32) -> Option<String> {
+    test_db.db.read_one(id).unwrap().map(|r| r.task)
+}
+
+#[test]
+fn test_sync_without_remote_commits_locally() {
+    let dir = TempDir::new().unwrap();
+    let test_db = TestDB::new();
+    test_db.db.write_all(&[row(1, 10, "a")]).unwrap();
+
+    let git = GitSync::new(dir.path().join("sync"));
+    let report = git.sync(&test_db.db).unwrap();
+
+    assert!(report.committed);
+    assert!(!report.pushed);
+    assert!(git.remote().is_none());
+
+    // nothing changed, nothing to commit
+    assert!(!git.sync(&test_db.db).unwrap().committed);
+}
+
+#[test]
+fn test_sync_pulls_into_empty_store() {
+    let dir = TempDir::new().unwrap();
+    let remote = bare_remote(&dir);
+
+    let laptop = TestDB::new();
+    laptop.db.write_all(&[row(1, 10, "a"), row(2, 10, "b")]).unwrap();
+    machine(&dir, "laptop", &remote).sync(&laptop.db).unwrap();
+
+    let desktop = TestDB::new();
+    let report = machine(&dir, "desktop", &remote).sync(&desktop.db).unwrap();
+
+    assert!(report.pulled);
+    assert!(report.updated_store);
+    assert_eq!(desktop.db.read_all().unwrap().len(), 2);
+}
+
+#[test]
+fn test_sync_merges_concurrent_changes_by_row() {
+    let dir = TempDir::new().unwrap();
+    let remote = bare_remote(&dir);
+
+    let laptop = TestDB::new();
+    let laptop_git = machine(&dir, "laptop", &remote);
+    laptop.db.write_all(&[row(1, 10, "a"), row(2, 10, "b"), row(3, 10, "c")]).unwrap();
+    laptop_git.sync(&laptop.db).unwrap();
+
+    let desktop = TestDB::new();
+    let desktop_git = machine(&dir, "desktop", &remote);
+    desktop_git.sync(&desktop.db).unwrap();
+
+    // both edit the same textual region of the file
+    laptop.db.update(&row(1, 20, "a laptop")).unwrap();
+    laptop.db.update(&row(3, 30, "c laptop")).unwrap();
+    laptop_git.sync(&laptop.db).unwrap();
+
+    desktop.db.update(&row(2, 20, "b desktop")).unwrap();
+    desktop.db.update(&row(3, 40, "c desktop")).unwrap();
+    desktop.db.add(&row(4, 40, "d")).unwrap();
+    let report = desktop_git.sync(&desktop.db).unwrap();
+
+    assert!(report.merged);
+    assert_eq!(task(&desktop, 1).unwrap(), "a laptop");
+    assert_eq!(task(&desktop, 2).unwrap(), "b desktop");
+    assert_eq!(task(&desktop, 3).unwrap(), "c desktop");
+    assert_eq!(task(&desktop, 4).unwrap(), "d");
+
+    let report = laptop_git.sync(&laptop.db).unwrap();
+    assert!(report.pulled && !report.merged);
+    assert_eq!(laptop.db.read_all().unwrap().len(), 4);
+    assert_eq!(task(&laptop, 2).unwrap(), "b desktop");
+}
+
+#[test]
+fn test_sync_propagates_deletions() {
+    let dir = TempDir::new().unwrap();
+    let remote = bare_remote(&dir);
+
+    let laptop = TestDB::new();
+    let laptop_git = machine(&dir, "laptop", &remote);
+    laptop.db.write_all(&[row(1, 10, "a"), row(2, 10, "b")]).unwrap();
+    laptop_git.sync(&laptop.db).unwrap();
+
+    let desktop = TestDB::new();
+    let desktop_git = machine(&dir, "desktop", &remote);
+    desktop_git.sync(&desktop.db).unwrap();
+
+    laptop.db.delete(1).unwrap();
+    laptop_git.sync(&laptop.db).unwrap();
+    desktop.db.update(&row(2, 20, "b desktop")).unwrap();
+    desktop_git.sync(&desktop.db).unwrap();
+
+    assert!(task(&desktop, 1).is_none());
+    assert_eq!(task(&desktop, 2).unwrap(), "b desktop");
+}
+
+#[test]
+fn test_sync_rows_at_missing_file_and_bad_revision() {
+    let dir = TempDir::new().unwrap();
+    let git = GitSync::new(dir.path().join("sync"));
+    git.init().unwrap();
+    let status = Command::new("git")
+        .arg("-C").arg(git.dir())
+        .args(["commit", "-q", "--allow-empty", "-m", "Empty"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // the store wasn't committed yet
+    assert!(git.rows_at("HEAD").unwrap().is_empty());
+
+    let test_db = TestDB::new();
+    test_db.db.write_all(&[row(1, 10, "a")]).unwrap();
+    git.sync(&test_db.db).unwrap();
+    assert_eq!(git.rows_at("HEAD").unwrap().len(), 1);
+
+    assert!(matches!(git.rows_at("origin/main"), Err(DBError::ReadError(_))));
+}
@@ -0,0 +1,96 @@
+#![cfg(test)]
+
+use tempfile::TempDir;
+
+use crate::timelog::{format_duration, parse_duration, TimeEntry, TimeLog};
+
+fn time_log(tempdir: &TempDir) -> TimeLog {
+    TimeLog::new(tempdir.path().join("timelog.csv"))
+}
+
+#[test]
+fn test_timelog_start_stop_ok() {
+    let tempdir = TempDir::new().unwrap();
+    let log = time_log(&tempdir);
+
+    assert!(log.start(1, 1000).unwrap().is_none());
+    assert_eq!(log.active().unwrap().unwrap().id, 1);
+
+    let stopped = log.stop(1600).unwrap().unwrap();
+    assert_eq!(stopped, TimeEntry { id: 1, start: 1000, end: Some(1600) });
+    assert!(log.active().unwrap().is_none());
+    assert!(log.stop(1700).unwrap().is_none());
+}
+
+#[test]
+fn test_timelog_single_active_timer() {
+    let tempdir = TempDir::new().unwrap();
+    let log = time_log(&tempdir);
+
+    log.start(1, 1000).unwrap();
+    let stopped = log.start(2, 1300).unwrap().unwrap();
+    assert_eq!(stopped.id, 1);
+    assert_eq!(stopped.seconds(9999), 300);
+
+    // starting the running task again changes nothing
+    assert!(log.start(2, 1400).unwrap().is_none());
+
+    let entries = log.read_all().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries.iter().filter(|e| e.end.is_none()).count(), 1);
+}
+
+#[test]
+fn test_timelog_totals_include_running_timer() {
+    let tempdir = TempDir::new().unwrap();
+    let log = time_log(&tempdir);
+
+    log.log(1, 5400, 1000).unwrap();
+    log.log(1, 600, 2000).unwrap();
+    log.start(2, 3000).unwrap();
+
+    let totals = log.totals(3060).unwrap();
+    assert_eq!(totals[&1], 6000);
+    assert_eq!(totals[&2], 60);
+}
+
+#[test]
+fn test_timelog_seconds_between_clips() {
+    let e = TimeEntry { id: 1, start: 100, end: Some(500) };
+
+    assert_eq!(e.seconds_between(200, 300, 0), 100);
+    assert_eq!(e.seconds_between(600, 700, 0), 0);
+    assert_eq!(e.seconds_between(i64::MIN, i64::MAX, 0), 400);
+}
+
+#[test]
+fn test_parse_duration_ok() {
+    assert_eq!(parse_duration("1h30m").unwrap(), 5400);
+    assert_eq!(parse_duration("45m").unwrap(), 2700);
+    assert_eq!(parse_duration("2H").unwrap(), 7200);
+    assert_eq!(parse_duration("90s").unwrap(), 90);
+    assert_eq!(parse_duration("20").unwrap(), 1200);
+    assert_eq!(parse_duration("1d12h").unwrap(), 129600);
+}
+
+#[test]
+fn test_parse_duration_err() {
+    assert!(parse_duration("").is_err());
+    assert!(parse_duration("1x").is_err());
+    assert!(parse_duration("h").is_err());
+    assert!(parse_duration("1h30").is_err());
+    assert!(parse_duration("0m").is_err());
+    // too long to count in seconds
+    assert!(parse_duration("999999999999999999").is_err());
+    assert!(parse_duration("9223372036854775807s1s").is_err());
+    assert!(parse_duration("99999999999999999d").is_err());
+}
+
+#[test]
+fn test_format_duration() {
+    assert_eq!(format_duration(5400), "1h30m");
+    assert_eq!(format_duration(7200), "2h");
+    assert_eq!(format_duration(2700), "45m");
+    assert_eq!(format_duration(30), "30s");
+    assert_eq!(format_duration(3660), "1h01m");
+}
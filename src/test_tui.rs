@@ -0,0 +1,176 @@
+#![cfg(test)]
+
+use tui::backend::TestBackend;
+use tui::Terminal;
+
+use crate::db_csv::DBCSV;
+use crate::models::{DBReader, DBRow, DBWriter};
+use crate::test_db_csv::{seeded_db, TestDB};
+use crate::tui::{App, Key, Mode};
+
+fn app(test_db: &TestDB) -> App<DBCSV> {
+    App::new(DBCSV::new(test_db._path.clone()))
+}
+
+fn press(app: &mut App<DBCSV>, keys: &str) {
+    for c in keys.chars() {
+        app.handle_key(Key::Char(c));
+    }
+}
+
+fn screen(app: &App<DBCSV>) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(100, 10)).unwrap();
+    terminal.draw(|f| app.draw(f)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let mut out = String::new();
+    for y in 0..buffer.area.height {
+        for x in 0..buffer.area.width {
+            out.push_str(&buffer.get(x, y).symbol);
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+#[test]
+fn test_tui_navigation_is_clamped() {
+    let test_db = seeded_db(3);
+    let mut app = app(&test_db);
+
+    assert_eq!(app.selected_row().unwrap().id, 1);
+    app.handle_key(Key::Down);
+    assert_eq!(app.selected_row().unwrap().id, 2);
+    app.handle_key(Key::End);
+    app.handle_key(Key::Down);
+    assert_eq!(app.selected_row().unwrap().id, 3);
+    app.handle_key(Key::Up);
+    app.handle_key(Key::PageUp);
+    assert_eq!(app.selected_row().unwrap().id, 1);
+}
+
+#[test]
+fn test_tui_toggle_writes_store() {
+    let test_db = seeded_db(3);
+    let mut app = app(&test_db);
+
+    press(&mut app, " ");
+
+    assert!(test_db.db.read_one(1).unwrap().unwrap().completed);
+    assert_eq!(app.selected_row().unwrap().id, 1);
+}
+
+#[test]
+fn test_tui_add_and_edit_ok() {
+    let test_db = seeded_db(3);
+    let mut app = app(&test_db);
+
+    press(&mut app, "anew task");
+    assert_eq!(app.mode, Mode::Add);
+    app.handle_key(Key::Enter);
+
+    assert_eq!(app.mode, Mode::Normal);
+    assert_eq!(test_db.db.read_one(4).unwrap().unwrap().task, "new task");
+    assert_eq!(app.selected_row().unwrap().id, 4);
+
+    press(&mut app, "e");
+    assert_eq!(app.input, "new task");
+    app.handle_key(Key::Backspace);
+    press(&mut app, "!");
+    app.handle_key(Key::Enter);
+
+    assert_eq!(test_db.db.read_one(4).unwrap().unwrap().task, "new tas!");
+}
+
+#[test]
+fn test_tui_delete_needs_confirmation() {
+    let test_db = seeded_db(3);
+    let mut app = app(&test_db);
+
+    press(&mut app, "dn");
+    assert!(test_db.db.read_one(1).unwrap().is_some());
+    assert_eq!(app.mode, Mode::Normal);
+
+    press(&mut app, "dy");
+    assert!(test_db.db.read_one(1).unwrap().is_none());
+    assert_eq!(app.visible().len(), 2);
+}
+
+#[test]
+fn test_tui_filter_and_search() {
+    let test_db = seeded_db(3);
+    let mut app = app(&test_db);
+
+    press(&mut app, "fstatus:open");
+    app.handle_key(Key::Enter);
+    assert_eq!(app.visible().len(), 2);
+
+    press(&mut app, "/TASK2");
+    assert_eq!(app.mode, Mode::Search);
+    assert_eq!(app.visible().len(), 1);
+    app.handle_key(Key::Enter);
+    assert_eq!(app.search, "TASK2");
+
+    press(&mut app, "/");
+    app.handle_key(Key::Esc);
+    assert_eq!(app.visible().len(), 2);
+}
+
+#[test]
+fn test_tui_invalid_filter_keeps_prompt() {
+    let test_db = seeded_db(3);
+    let mut app = app(&test_db);
+
+    press(&mut app, "fstatus:maybe");
+    app.handle_key(Key::Enter);
+
+    assert_eq!(app.mode, Mode::Filter);
+    assert!(app.message.contains("unknown status"));
+    assert_eq!(app.visible().len(), 3);
+}
+
+#[test]
+fn test_tui_reload_picks_up_external_changes() {
+    let test_db = seeded_db(3);
+    let mut app = app(&test_db);
+    app.handle_key(Key::Down);
+
+    let r = DBRow{
+        id: 9,
+        updatedate: 1739126999,
+        task: "from elsewhere".to_string(),
+        ..Default::default()
+    };
+    test_db.db.add(&r).unwrap();
+    app.reload();
+
+    assert_eq!(app.visible().len(), 4);
+    assert_eq!(app.selected_row().unwrap().id, 2);
+}
+
+#[test]
+fn test_tui_renders_rows_and_prompt() {
+    let test_db = seeded_db(3);
+    let mut app = app(&test_db);
+
+    let out = screen(&app);
+    assert!(out.contains("Tasks (3/3)"));
+    assert!(out.contains("task2 +work"));
+    assert!(out.contains("[X]"));
+    assert!(out.contains("q quit"));
+
+    press(&mut app, "ahello");
+    let out = screen(&app);
+    assert!(out.contains("Add: hello"));
+}
+
+#[test]
+fn test_tui_quit() {
+    let test_db = seeded_db(3);
+    let mut app = app(&test_db);
+
+    press(&mut app, "q");
+
+    assert!(app.quit);
+}
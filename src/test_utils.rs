@@ -2,12 +2,12 @@
 
 use chrono::{TimeZone, Utc};
 
-use crate::utils::{unix_to_datetime, get_db_storage_path};
+use crate::utils::{format_timestamp, unix_to_datetime, get_db_storage_path};
 
 #[test]
 fn test_unix_to_datetime_conversion() {
     let timestamp = 1739120530;
-    let result = unix_to_datetime(timestamp);
+    let result = unix_to_datetime(timestamp).unwrap();
 
     let utc_result = result.with_timezone(&Utc);
     let utc_expected = Utc.timestamp_opt(timestamp, 0).unwrap();
@@ -15,6 +15,13 @@ fn test_unix_to_datetime_conversion() {
     assert_eq!(utc_expected, utc_result);
 }
 
+#[test]
+fn test_unix_to_datetime_out_of_range() {
+    assert!(unix_to_datetime(99999999999999999).is_none());
+    assert_eq!(format_timestamp(99999999999999999, "%Y-%m-%d"), "99999999999999999");
+    assert_eq!(format_timestamp(0, "%Y"), unix_to_datetime(0).unwrap().format("%Y").to_string());
+}
+
 #[test]
 fn test_db_storage_path_partial() {
     let expected = if cfg!(target_os="windows") { 
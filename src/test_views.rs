@@ -0,0 +1,95 @@
+#![cfg(test)]
+
+use tempfile::TempDir;
+
+use crate::models::{Column, DBError};
+use crate::query::Query;
+use crate::views::{builtin_views, View, ViewStore};
+
+fn view(query: &str) -> View {
+    View {
+        query: query.to_string(),
+        sort: Some("due,-priority".to_string()),
+        limit: Some(5),
+        columns: vec![Column::Id, Column::Task],
+    }
+}
+
+#[test]
+fn test_views_load_missing_file_is_empty() {
+    let tempdir = TempDir::new().unwrap();
+    let store = ViewStore::new(tempdir.path().join("views.json"));
+
+    assert!(store.load().unwrap().is_empty());
+}
+
+#[test]
+fn test_views_save_and_get_ok() {
+    let tempdir = TempDir::new().unwrap();
+    let store = ViewStore::new(tempdir.path().join("views.json"));
+
+    store.save("work", view("tag:work")).unwrap();
+
+    let result = store.get("work").unwrap().unwrap();
+    assert_eq!(result, view("tag:work"));
+    assert_eq!(result.query_string(), "tag:work sort:due,-priority limit:5");
+    assert!(Query::parse(&result.query_string()).is_ok());
+}
+
+#[test]
+fn test_views_save_overwrites() {
+    let tempdir = TempDir::new().unwrap();
+    let store = ViewStore::new(tempdir.path().join("views.json"));
+
+    store.save("work", view("tag:work")).unwrap();
+    store.save("work", view("tag:office")).unwrap();
+
+    assert_eq!(store.load().unwrap().len(), 1);
+    assert_eq!(store.get("work").unwrap().unwrap().query, "tag:office");
+}
+
+#[test]
+fn test_views_delete_ok() {
+    let tempdir = TempDir::new().unwrap();
+    let store = ViewStore::new(tempdir.path().join("views.json"));
+
+    store.save("work", view("tag:work")).unwrap();
+
+    assert!(store.delete("work").is_ok());
+    assert!(store.get("work").unwrap().is_none());
+    assert!(matches!(store.delete("work"), Err(DBError::IDNotFound)));
+}
+
+#[test]
+fn test_views_builtins_are_read_only() {
+    let tempdir = TempDir::new().unwrap();
+    let store = ViewStore::new(tempdir.path().join("views.json"));
+
+    assert_eq!(store.get("incomplete").unwrap().unwrap().query, "status:open");
+    assert_eq!(store.get("done").unwrap().unwrap().query, "status:done");
+    assert!(store.save("done", view("tag:x")).is_err());
+    assert!(store.delete("incomplete").is_err());
+    assert_eq!(builtin_views().len(), 3);
+}
+
+#[test]
+fn test_views_invalid_name_is_err() {
+    let tempdir = TempDir::new().unwrap();
+    let store = ViewStore::new(tempdir.path().join("views.json"));
+
+    assert!(store.save("", view("tag:x")).is_err());
+    assert!(store.save("my view", view("tag:x")).is_err());
+    assert!(store.save("-x", view("tag:x")).is_err());
+}
+
+#[test]
+fn test_views_missing_fields_use_defaults() {
+    let tempdir = TempDir::new().unwrap();
+    let path = tempdir.path().join("views.json");
+    std::fs::write(&path, r#"{"work": {"query": "tag:work"}}"#).unwrap();
+
+    let result = ViewStore::new(path).get("work").unwrap().unwrap();
+
+    assert_eq!(result.sort, None);
+    assert_eq!(result.columns.len(), 5);
+}
@@ -0,0 +1,45 @@
+#![cfg(test)]
+
+use std::fs;
+
+use tempfile::TempDir;
+
+use crate::models::Status;
+use crate::workflow::Workflow;
+
+#[test]
+fn test_workflow_default_transitions() {
+    let workflow = Workflow::default();
+
+    assert!(workflow.allows(Status::Todo, Status::InProgress));
+    assert!(workflow.allows(Status::Waiting, Status::Done));
+    assert!(workflow.allows(Status::Done, Status::Todo));
+    assert!(workflow.allows(Status::Done, Status::Done));
+    assert!(!workflow.allows(Status::Done, Status::Waiting));
+    assert!(!workflow.allows(Status::Cancelled, Status::Done));
+}
+
+#[test]
+fn test_workflow_check_lists_allowed() {
+    let err = Workflow::default().check(Status::Done, Status::Waiting).unwrap_err();
+
+    assert_eq!(err, "cannot go from done to waiting, allowed: todo");
+}
+
+#[test]
+fn test_workflow_load_ok() {
+    let tempdir = TempDir::new().unwrap();
+    let path = tempdir.path().join("workflow.json");
+
+    assert_eq!(Workflow::load(&path).unwrap(), Workflow::default());
+
+    fs::write(&path, r#"{"todo": ["in-progress"], "in-progress": ["done"]}"#).unwrap();
+    let workflow = Workflow::load(&path).unwrap();
+
+    assert!(workflow.allows(Status::Todo, Status::InProgress));
+    assert!(!workflow.allows(Status::Todo, Status::Done));
+    assert!(workflow.check(Status::Done, Status::Todo).unwrap_err().contains("cannot change"));
+
+    fs::write(&path, r#"{"todo": ["later"]}"#).unwrap();
+    assert!(Workflow::load(&path).is_err());
+}
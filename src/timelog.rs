@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::models::{DBError, DBRow};
+
+
+/// One stretch of work on a task, `end` is empty while the timer runs.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct TimeEntry {
+    pub id: u32,
+    pub start: i64,
+    pub end: Option<i64>,
+}
+
+impl TimeEntry {
+    /// Seconds spent, a running entry counts up to `now`.
+    pub fn seconds(&self, now: i64) -> i64 {
+        self.end.unwrap_or(now).saturating_sub(self.start).max(0)
+    }
+
+    /// Seconds spent between `from` and `to`.
+    pub fn seconds_between(&self, from: i64, to: i64, now: i64) -> i64 {
+        let start = self.start.max(from);
+        let end = self.end.unwrap_or(now).min(to);
+        end.saturating_sub(start).max(0)
+    }
+}
+
+/// Time entries of all tasks, kept in their own file next to the database
+/// and keyed by the task ID. At most one entry is running at a time.
+pub struct TimeLog {
+    path: PathBuf,
+}
+
+impl TimeLog {
+    pub fn new(path: PathBuf) -> Self {
+        TimeLog { path }
+    }
+
+    pub fn read_all(&self) -> Result<Vec<TimeEntry>, DBError> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(b';')
+            .from_path(&self.path)
+            .map_err(|e| DBError::read(&self.path, e))?;
+
+        reader.deserialize()
+            .map(|r| r.map_err(|e| DBError::read(&self.path, e)))
+            .collect()
+    }
+
+    fn write_all(&self, entries: &[TimeEntry]) -> Result<(), DBError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| DBError::write(parent, e))?;
+        }
+
+        let temp_path = self.path.with_extension("tmp");
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(b';')
+            .from_path(&temp_path)
+            .map_err(|e| DBError::write(&temp_path, e))?;
+
+        for e in entries {
+            writer.serialize(e)
+                .map_err(|e| DBError::write(&temp_path, e))?;
+        }
+        writer.flush()
+            .map_err(|e| DBError::write(&temp_path, e))?;
+
+        fs::rename(&temp_path, &self.path)
+            .map_err(|e| DBError::write(&self.path, e))
+    }
+
+    pub fn active(&self) -> Result<Option<TimeEntry>, DBError> {
+        Ok(self.read_all()?.into_iter().find(|e| e.end.is_none()))
+    }
+
+    /// Starts a timer on task `id`. A timer running on another task is
+    /// stopped first and returned.
+    pub fn start(&self, id: u32, now: i64) -> Result<Option<TimeEntry>, DBError> {
+        let mut entries = self.read_all()?;
+
+        let stopped = stop_running(&mut entries, now);
+        if let Some(s) = &stopped {
+            if s.id == id {
+                // already running on this task, keep it going
+                return Ok(None);
+            }
+        }
+
+        entries.push(TimeEntry { id, start: now, end: None });
+        self.write_all(&entries)?;
+
+        Ok(stopped)
+    }
+
+    /// Stops the running timer, `None` when there is none.
+    pub fn stop(&self, now: i64) -> Result<Option<TimeEntry>, DBError> {
+        let mut entries = self.read_all()?;
+
+        let stopped = stop_running(&mut entries, now);
+        if stopped.is_some() {
+            self.write_all(&entries)?;
+        }
+
+        Ok(stopped)
+    }
+
+    /// Records `seconds` of work on task `id` that ended `now`.
+    pub fn log(&self, id: u32, seconds: i64, now: i64) -> Result<TimeEntry, DBError> {
+        let mut entries = self.read_all()?;
+
+        let entry = TimeEntry { id, start: now.saturating_sub(seconds), end: Some(now) };
+        entries.push(entry.clone());
+        self.write_all(&entries)?;
+
+        Ok(entry)
+    }
+
+    /// Seconds spent per task ID.
+    pub fn totals(&self, now: i64) -> Result<BTreeMap<u32, i64>, DBError> {
+        let mut totals: BTreeMap<u32, i64> = BTreeMap::new();
+        for e in self.read_all()? {
+            let total = totals.entry(e.id).or_default();
+            *total = total.saturating_add(e.seconds(now));
+        }
+
+        Ok(totals)
+    }
+
+    /// Fills `DBRow::tracked` of the given rows.
+    pub fn annotate(&self, rows: &mut [DBRow], now: i64) -> Result<(), DBError> {
+        let totals = self.totals(now)?;
+        for r in rows {
+            r.tracked = totals.get(&r.id).copied();
+        }
+
+        Ok(())
+    }
+}
+
+fn stop_running(entries: &mut [TimeEntry], now: i64) -> Option<TimeEntry> {
+    let running = entries.iter_mut().find(|e| e.end.is_none())?;
+    running.end = Some(now.max(running.start));
+
+    Some(running.clone())
+}
+
+/// Parses durations like `1h30m`, `45m`, `2d` or `90s`. A bare number is
+/// taken as minutes.
+pub fn parse_duration(value: &str) -> Result<i64, String> {
+    let invalid = || format!("'{}' is not a valid duration, expected e.g. 1h30m or 45m", value);
+    let value_lower = value.trim().to_lowercase();
+
+    if let Ok(minutes) = value_lower.parse::<i64>() {
+        return match minutes > 0 {
+            true => minutes.checked_mul(60).ok_or_else(invalid),
+            false => Err(invalid()),
+        };
+    }
+
+    let mut total: i64 = 0;
+    let mut number = String::new();
+    for c in value_lower.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            'd' => 86400,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        let n: i64 = number.parse().map_err(|_| invalid())?;
+        total = n.checked_mul(unit)
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or_else(invalid)?;
+        number.clear();
+    }
+
+    if !number.is_empty() || total <= 0 {
+        return Err(invalid());
+    }
+
+    Ok(total)
+}
+
+/// Formats seconds as `1h30m`, `45m` or `30s`.
+pub fn format_duration(seconds: i64) -> String {
+    let hours = seconds / 3600;
+    let minutes = seconds % 3600 / 60;
+
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h{:02}m", h, m),
+    }
+}
@@ -0,0 +1,406 @@
+use chrono::Local;
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use tui::Frame;
+
+use crate::dates::format_due;
+use crate::models::{DBError, DBReader, DBRow, DBWriter};
+use crate::query::Query;
+
+
+/// Key presses the app reacts to, independent of the terminal library so the
+/// state can be driven from tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Enter,
+    Esc,
+    Backspace,
+    Char(char),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Add,
+    Edit(u32),
+    Filter,
+    Search,
+    ConfirmDelete(u32),
+}
+
+const PAGE: usize = 10;
+
+/// Full-screen task list state. Rendering and key handling are kept apart
+/// from the terminal so the whole app runs headless against a `TestBackend`.
+pub struct App<T: DBReader + DBWriter> {
+    repo: T,
+    rows: Vec<DBRow>,
+    pub mode: Mode,
+    pub input: String,
+    pub filter: Query,
+    pub filter_text: String,
+    pub search: String,
+    pub message: String,
+    pub quit: bool,
+    selected: usize,
+}
+
+impl<T: DBReader + DBWriter> App<T> {
+    pub fn new(repo: T) -> Self {
+        let mut app = App {
+            repo,
+            rows: vec![],
+            mode: Mode::Normal,
+            input: String::new(),
+            filter: Query::default(),
+            filter_text: String::new(),
+            search: String::new(),
+            message: String::new(),
+            quit: false,
+            selected: 0,
+        };
+        app.reload();
+
+        app
+    }
+
+    /// Reads the store again, keeping the selection on the same task. Called
+    /// periodically so changes made by other processes show up.
+    pub fn reload(&mut self) {
+        let selected_id = self.selected_row().map(|r| r.id);
+
+        match self.repo.read_all() {
+            Ok(rows) => self.rows = rows,
+            Err(DBError::MissingDatabase(_)) => self.rows = vec![],
+            Err(e) => self.message = e.to_string(),
+        }
+
+        let visible = self.visible();
+        self.selected = selected_id
+            .and_then(|id| visible.iter().position(|r| r.id == id))
+            .unwrap_or(self.selected)
+            .min(visible.len().saturating_sub(1));
+    }
+
+    /// Rows passing the filter and search, in display order.
+    pub fn visible(&self) -> Vec<DBRow> {
+        let search = self.search.to_lowercase();
+
+        self.filter.apply(self.rows.clone())
+            .into_iter()
+            .filter(|r| search.is_empty() || r.task.to_lowercase().contains(&search))
+            .collect()
+    }
+
+    pub fn selected_row(&self) -> Option<DBRow> {
+        self.visible().into_iter().nth(self.selected)
+    }
+
+    pub fn handle_key(&mut self, key: Key) {
+        match self.mode.clone() {
+            Mode::Normal => self.handle_normal(key),
+            Mode::ConfirmDelete(id) => {
+                self.mode = Mode::Normal;
+                match key {
+                    Key::Char('y') | Key::Char('Y') => self.delete(id),
+                    _ => self.message = "Delete cancelled.".to_string(),
+                }
+            }
+            mode => self.handle_input(mode, key),
+        }
+    }
+
+    fn handle_normal(&mut self, key: Key) {
+        let count = self.visible().len();
+        self.message.clear();
+
+        match key {
+            Key::Char('q') | Key::Esc => self.quit = true,
+            Key::Down | Key::Char('j') => self.select(self.selected as i64 + 1),
+            Key::Up | Key::Char('k') => self.select(self.selected as i64 - 1),
+            Key::PageDown => self.select((self.selected + PAGE) as i64),
+            Key::PageUp => self.select(self.selected as i64 - PAGE as i64),
+            Key::Home | Key::Char('g') => self.select(0),
+            Key::End | Key::Char('G') => self.select(count as i64 - 1),
+            Key::Char(' ') | Key::Char('x') => self.toggle(),
+            Key::Char('a') => self.start_input(Mode::Add, String::new()),
+            Key::Char('e') | Key::Enter => {
+                if let Some(r) = self.selected_row() {
+                    self.start_input(Mode::Edit(r.id), r.task);
+                }
+            }
+            Key::Char('d') => {
+                if let Some(r) = self.selected_row() {
+                    self.message = format!("Delete #{} '{}'? (y/n)", r.id, r.task);
+                    self.mode = Mode::ConfirmDelete(r.id);
+                }
+            }
+            Key::Char('f') => self.start_input(Mode::Filter, self.filter_text.clone()),
+            Key::Char('/') => self.start_input(Mode::Search, self.search.clone()),
+            Key::Char('r') => self.reload(),
+            _ => (),
+        }
+    }
+
+    fn handle_input(&mut self, mode: Mode, key: Key) {
+        match key {
+            Key::Esc => {
+                if mode == Mode::Search {
+                    self.search.clear();
+                }
+                self.mode = Mode::Normal;
+                self.message.clear();
+            }
+            Key::Enter => self.submit(mode),
+            Key::Backspace => {
+                self.input.pop();
+            }
+            Key::Char(c) => self.input.push(c),
+            _ => (),
+        }
+
+        // search narrows the list while typing
+        if self.mode == Mode::Search {
+            self.search = self.input.clone();
+            self.select(0);
+        }
+    }
+
+    fn start_input(&mut self, mode: Mode, input: String) {
+        self.mode = mode;
+        self.input = input;
+    }
+
+    fn submit(&mut self, mode: Mode) {
+        let input = self.input.trim().to_string();
+
+        match mode {
+            Mode::Add if !input.is_empty() => self.add(input),
+            Mode::Edit(id) if !input.is_empty() => self.edit(id, input),
+            Mode::Filter => match Query::parse(&input) {
+                Ok(q) => {
+                    self.filter = q;
+                    self.filter_text = input;
+                    self.select(0);
+                }
+                Err(e) => {
+                    // stay in filter mode so the query can be fixed
+                    self.message = e.message;
+                    return;
+                }
+            },
+            _ => (),
+        }
+
+        self.mode = Mode::Normal;
+    }
+
+    fn select(&mut self, index: i64) {
+        let count = self.visible().len() as i64;
+        self.selected = index.clamp(0, (count - 1).max(0)) as usize;
+    }
+
+    fn toggle(&mut self) {
+        let (id, completed) = match self.selected_row() {
+            Some(r) => (r.id, r.completed),
+            None => return,
+        };
+
+        match self.repo.mark_completion(id, !completed) {
+            Ok(()) => self.message = format!("#{} marked as {}.", id, if completed { "open" } else { "done" }),
+            Err(e) => self.message = e.to_string(),
+        }
+        self.reload();
+    }
+
+    fn add(&mut self, task: String) {
+        let now = Local::now().timestamp();
+        let r = DBRow {
+            id: self.repo.get_next_id(),
+            updatedate: now,
+            created: Some(now),
+            task,
+            ..Default::default()
+        };
+
+        match self.repo.add(&r) {
+            Ok(()) => self.message = format!("#{} added.", r.id),
+            Err(e) => self.message = e.to_string(),
+        }
+        self.reload();
+
+        if let Some(i) = self.visible().iter().position(|v| v.id == r.id) {
+            self.selected = i;
+        }
+    }
+
+    fn edit(&mut self, id: u32, task: String) {
+        let mut r = match self.rows.iter().find(|r| r.id == id) {
+            Some(r) => r.clone(),
+            None => return,
+        };
+        r.task = task;
+        r.updatedate = Local::now().timestamp();
+
+        match self.repo.update(&r) {
+            Ok(()) => self.message = format!("#{} updated.", id),
+            Err(e) => self.message = e.to_string(),
+        }
+        self.reload();
+    }
+
+    fn delete(&mut self, id: u32) {
+        match self.repo.delete(id) {
+            Ok(()) => self.message = format!("#{} deleted.", id),
+            Err(e) => self.message = e.to_string(),
+        }
+        self.reload();
+    }
+
+    pub fn draw<B: Backend>(&self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(1), Constraint::Length(1)])
+            .split(f.size());
+
+        let visible = self.visible();
+        let rows: Vec<Row> = visible.iter()
+            .map(|r| {
+                let style = if !r.current_status().is_open() {
+                    Style::default().add_modifier(Modifier::DIM)
+                } else {
+                    Style::default()
+                };
+                let tags: String = r.tags.iter().map(|t| format!(" +{}", t)).collect();
+                Row::new(vec![
+                    Cell::from(r.id.to_string()),
+                    Cell::from(r.current_status().marker()),
+                    Cell::from(r.due.map(format_due).unwrap_or_default()),
+                    Cell::from(format!("{}{}", r.task, tags)),
+                ]).style(style)
+            })
+            .collect();
+
+        let mut title = format!(" Tasks ({}/{}) ", visible.len(), self.rows.len());
+        if !self.filter_text.is_empty() {
+            title.push_str(&format!("filter: {} ", self.filter_text));
+        }
+        if !self.search.is_empty() {
+            title.push_str(&format!("search: {} ", self.search));
+        }
+
+        let widths = [Constraint::Length(4), Constraint::Length(3), Constraint::Length(16), Constraint::Percentage(100)];
+        let table = Table::new(rows)
+            .header(Row::new(vec!["ID", "", "Due", "Task"]).style(Style::default().add_modifier(Modifier::BOLD)))
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let mut state = TableState::default();
+        if !visible.is_empty() {
+            state.select(Some(self.selected));
+        }
+        f.render_stateful_widget(table, chunks[0], &mut state);
+
+        let prompt = match &self.mode {
+            Mode::Add => Some("Add: "),
+            Mode::Edit(_) => Some("Edit: "),
+            Mode::Filter => Some("Filter: "),
+            Mode::Search => Some("Search: "),
+            _ => None,
+        };
+        let line = match prompt {
+            Some(p) => Spans::from(vec![
+                Span::styled(p, Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(self.input.clone()),
+            ]),
+            None => Spans::from(Span::raw(self.message.clone())),
+        };
+        f.render_widget(Paragraph::new(line), chunks[1]);
+
+        let help = match (&self.mode, prompt) {
+            (Mode::ConfirmDelete(_), _) => "y confirm  any other key cancels",
+            (_, Some(_)) => "enter accept  esc cancel",
+            _ => "j/k move  space toggle  a add  e edit  d delete  f filter  / search  r reload  q quit",
+        };
+        f.render_widget(Paragraph::new(Span::styled(help, Style::default().add_modifier(Modifier::DIM))), chunks[2]);
+
+        if let Some(p) = prompt {
+            let x = chunks[1].x + (p.len() + self.input.chars().count()) as u16;
+            f.set_cursor(x.min(chunks[1].right().saturating_sub(1)), chunks[1].y);
+        }
+    }
+}
+
+/// Runs the app in the terminal until the user quits.
+#[cfg(unix)]
+pub fn run<T: DBReader + DBWriter>(repo: T) -> std::io::Result<()> {
+    use std::io;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use termion::input::TermRead;
+    use termion::raw::IntoRawMode;
+    use termion::screen::AlternateScreen;
+    use tui::backend::TermionBackend;
+    use tui::Terminal;
+
+    const RELOAD_EVERY: Duration = Duration::from_secs(1);
+
+    let stdout = io::stdout().into_raw_mode()?;
+    let stdout = AlternateScreen::from(stdout);
+    let mut terminal = Terminal::new(TermionBackend::new(stdout))?;
+    let mut keys = termion::async_stdin().keys();
+
+    let mut app = App::new(repo);
+    let mut last_reload = Instant::now();
+
+    while !app.quit {
+        terminal.draw(|f| app.draw(f))?;
+
+        match keys.next() {
+            Some(Ok(k)) => {
+                let key = match k {
+                    termion::event::Key::Up => Some(Key::Up),
+                    termion::event::Key::Down => Some(Key::Down),
+                    termion::event::Key::PageUp => Some(Key::PageUp),
+                    termion::event::Key::PageDown => Some(Key::PageDown),
+                    termion::event::Key::Home => Some(Key::Home),
+                    termion::event::Key::End => Some(Key::End),
+                    termion::event::Key::Esc => Some(Key::Esc),
+                    termion::event::Key::Backspace => Some(Key::Backspace),
+                    termion::event::Key::Char('\n') => Some(Key::Enter),
+                    termion::event::Key::Char(c) => Some(Key::Char(c)),
+                    termion::event::Key::Ctrl('c') => {
+                        app.quit = true;
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some(key) = key {
+                    app.handle_key(key);
+                }
+            }
+            _ => thread::sleep(Duration::from_millis(50)),
+        }
+
+        // other processes may have changed the store, skip while typing
+        if last_reload.elapsed() >= RELOAD_EVERY {
+            if matches!(app.mode, Mode::Normal) {
+                app.reload();
+            }
+            last_reload = Instant::now();
+        }
+    }
+
+    terminal.show_cursor()
+}
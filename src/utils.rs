@@ -2,9 +2,18 @@ use std::{env, path::PathBuf};
 
 use chrono::{Local, TimeZone, Utc};
 
-pub fn unix_to_datetime(timestamp: i64) -> chrono::DateTime<Local> {
-    Utc.timestamp_opt(timestamp, 0).unwrap()
-    .with_timezone(&Local)
+/// `None` for timestamps too far in the past or future for a date.
+pub fn unix_to_datetime(timestamp: i64) -> Option<chrono::DateTime<Local>> {
+    Utc.timestamp_opt(timestamp, 0).single()
+        .map(|dt| dt.with_timezone(&Local))
+}
+
+/// Formats a timestamp in local time, one without a date as the number.
+pub fn format_timestamp(timestamp: i64, format: &str) -> String {
+    match unix_to_datetime(timestamp) {
+        Some(dt) => dt.format(format).to_string(),
+        None => timestamp.to_string(),
+    }
 }
 
 pub fn get_db_storage_path() -> PathBuf {
@@ -14,3 +23,14 @@ pub fn get_db_storage_path() -> PathBuf {
         PathBuf::from(env::var("HOME").unwrap()).join(".local/share").join("todo-app").join("db.csv")
     }
 }
+
+/// 64-bit FNV-1a hash, stable across runs and platforms unlike the std hasher.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::models::{Column, DBError, DEFAULT_COLUMNS};
+
+
+/// Named query with its display settings, run with `todo <name>`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct View {
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(default = "default_columns")]
+    pub columns: Vec<Column>,
+}
+
+fn default_columns() -> Vec<Column> {
+    DEFAULT_COLUMNS.to_vec()
+}
+
+impl View {
+    /// Query text including the stored sort and limit clauses.
+    pub fn query_string(&self) -> String {
+        let mut parts = vec![self.query.clone()];
+        if let Some(sort) = &self.sort {
+            parts.push(format!("sort:{}", sort));
+        }
+        if let Some(limit) = self.limit {
+            parts.push(format!("limit:{}", limit));
+        }
+
+        parts.join(" ").trim().to_string()
+    }
+}
+
+/// Views that are always available and cannot be changed. Their names
+/// must not be command names, `todo <name>` would run the command.
+pub fn builtin_views() -> BTreeMap<String, View> {
+    let view = |query: &str| View {
+        query: query.to_string(),
+        sort: None,
+        limit: None,
+        columns: default_columns(),
+    };
+
+    BTreeMap::from([
+        ("done".to_string(), view("status:done")),
+        ("incomplete".to_string(), view("status:open")),
+        ("snoozed".to_string(), view("snoozed:yes")),
+    ])
+}
+
+/// Saved views kept in a JSON file next to the database.
+pub struct ViewStore {
+    path: PathBuf,
+}
+
+impl ViewStore {
+    pub fn new(path: PathBuf) -> Self {
+        ViewStore { path }
+    }
+
+    /// User views, without the built-in ones.
+    pub fn load(&self) -> Result<BTreeMap<String, View>, DBError> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| DBError::read(&self.path, e))?;
+
+        serde_json::from_str(&content)
+            .map_err(|e| DBError::read(&self.path, e))
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<View>, DBError> {
+        if let Some(v) = builtin_views().remove(name) {
+            return Ok(Some(v));
+        }
+
+        Ok(self.load()?.remove(name))
+    }
+
+    pub fn save(&self, name: &str, view: View) -> Result<(), DBError> {
+        validate_name(name).map_err(|e| DBError::new_write_error(&e))?;
+        if builtin_views().contains_key(name) {
+            return Err(DBError::new_write_error(&format!("'{}' is a built-in view", name)));
+        }
+
+        let mut views = self.load()?;
+        views.insert(name.to_string(), view);
+        self.store(&views)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), DBError> {
+        if builtin_views().contains_key(name) {
+            return Err(DBError::new_write_error(&format!("'{}' is a built-in view", name)));
+        }
+
+        let mut views = self.load()?;
+        if views.remove(name).is_none() {
+            return Err(DBError::new_idnotfound_error());
+        }
+        self.store(&views)
+    }
+
+    fn store(&self, views: &BTreeMap<String, View>) -> Result<(), DBError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| DBError::write(parent, e))?;
+        }
+
+        let content = serde_json::to_string_pretty(views)
+            .map_err(|e| DBError::write(&self.path, e))?;
+
+        let temp_path = self.path.with_extension("tmp");
+        fs::write(&temp_path, content)
+            .map_err(|e| DBError::write(&temp_path, e))?;
+        fs::rename(&temp_path, &self.path)
+            .map_err(|e| DBError::write(&self.path, e))
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('-')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+
+    if !valid {
+        return Err(format!("'{}' is not a valid view name, use letters, digits, '-' and '_'", name));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::models::{DBError, Status};
+
+
+/// Status changes the commands allow, keyed by the status a task is in.
+/// Staying in the same status is always allowed.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Workflow {
+    pub transitions: BTreeMap<Status, Vec<Status>>,
+}
+
+impl Default for Workflow {
+    /// Open tasks move freely, closed ones can only be reopened.
+    fn default() -> Self {
+        use Status::*;
+
+        Workflow {
+            transitions: BTreeMap::from([
+                (Todo, vec![InProgress, Waiting, Done, Cancelled]),
+                (InProgress, vec![Todo, Waiting, Done, Cancelled]),
+                (Waiting, vec![Todo, InProgress, Done, Cancelled]),
+                (Done, vec![Todo]),
+                (Cancelled, vec![Todo]),
+            ]),
+        }
+    }
+}
+
+impl Workflow {
+    /// Reads the transitions from a JSON file such as
+    /// `{"todo": ["in-progress", "done"], "in-progress": ["done"]}`. A
+    /// status left out of the file cannot be left. Without a file the
+    /// default workflow applies.
+    pub fn load(path: &Path) -> Result<Workflow, DBError> {
+        if !path.exists() {
+            return Ok(Workflow::default());
+        }
+
+        let content = fs::read_to_string(path)
+            .map_err(|e| DBError::read(path, e))?;
+        let transitions = serde_json::from_str(&content)
+            .map_err(|e| DBError::read(path, e))?;
+
+        Ok(Workflow { transitions })
+    }
+
+    pub fn allows(&self, from: Status, to: Status) -> bool {
+        from == to || self.transitions.get(&from).map(|t| t.contains(&to)).unwrap_or(false)
+    }
+
+    /// Explains a refused change, listing where the task can go instead.
+    pub fn check(&self, from: Status, to: Status) -> Result<(), String> {
+        if self.allows(from, to) {
+            return Ok(());
+        }
+
+        let allowed: Vec<String> = self.transitions.get(&from)
+            .map(|t| t.iter().map(|s| s.to_string()).collect())
+            .unwrap_or_default();
+        match allowed.len() {
+            0 => Err(format!("a {} task cannot change its status", from)),
+            _ => Err(format!("cannot go from {} to {}, allowed: {}", from, to, allowed.join(", "))),
+        }
+    }
+}
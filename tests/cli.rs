@@ -0,0 +1,239 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use tempfile::TempDir;
+
+/// Runs the binary against a database of its own.
+struct Cli {
+    home: TempDir,
+}
+
+impl Cli {
+    fn new() -> Self {
+        Cli { home: TempDir::new().unwrap() }
+    }
+
+    fn db_path(&self) -> PathBuf {
+        if cfg!(target_os = "windows") {
+            self.home.path().join("todo-app").join("db.csv")
+        } else {
+            self.home.path().join(".local/share").join("todo-app").join("db.csv")
+        }
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_todo"))
+            .args(args)
+            .env("HOME", self.home.path())
+            .env("APPDATA", self.home.path())
+            .env("TODO_USER", "tester")
+            .output()
+            .unwrap()
+    }
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+#[test]
+fn test_cli_success_exits_zero() {
+    let cli = Cli::new();
+
+    assert_eq!(cli.run(&["add", "Call", "bob"]).status.code(), Some(0));
+    assert_eq!(cli.run(&["list"]).status.code(), Some(0));
+    assert_eq!(cli.run(&["show", "1"]).status.code(), Some(0));
+}
+
+#[test]
+fn test_cli_invalid_input_exits_two() {
+    let cli = Cli::new();
+
+    assert_eq!(cli.run(&["frobnicate"]).status.code(), Some(2));
+    assert_eq!(cli.run(&["list", "due<someday"]).status.code(), Some(2));
+    assert_eq!(cli.run(&["add", "--due", "someday", "Call", "bob"]).status.code(), Some(2));
+}
+
+#[test]
+fn test_cli_missing_task_exits_six() {
+    let cli = Cli::new();
+    cli.run(&["add", "Call", "bob"]);
+
+    let output = cli.run(&["show", "7"]);
+
+    assert_eq!(output.status.code(), Some(6));
+    assert!(stderr(&output).contains("task #7 was not found"));
+    assert_eq!(cli.run(&["snooze", "7", "tomorrow"]).status.code(), Some(6));
+    assert_eq!(cli.run(&["view", "delete", "nope"]).status.code(), Some(6));
+}
+
+#[test]
+fn test_cli_missing_database_exits_five() {
+    let cli = Cli::new();
+
+    let output = cli.run(&["delete-completed"]);
+
+    assert_eq!(output.status.code(), Some(5));
+    assert!(stderr(&output).starts_with("error: no database at "));
+    assert!(stderr(&output).contains(&cli.db_path().display().to_string()));
+    // a task that cannot exist yet is not found
+    assert_eq!(cli.run(&["show", "1"]).status.code(), Some(6));
+}
+
+#[test]
+fn test_cli_unreadable_database_exits_three() {
+    let cli = Cli::new();
+    fs::create_dir_all(cli.db_path()).unwrap();
+
+    let output = cli.run(&["list"]);
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr(&output).contains(&cli.db_path().display().to_string()));
+    assert!(!stderr(&output).contains("panicked"));
+}
+
+#[test]
+fn test_cli_corrupt_row_exits_eight() {
+    let cli = Cli::new();
+    cli.run(&["add", "Call", "bob"]);
+    let content = fs::read_to_string(cli.db_path()).unwrap();
+    fs::write(cli.db_path(), format!("{}not-a-number;x;y\n", content)).unwrap();
+
+    let output = cli.run(&["list"]);
+
+    assert_eq!(output.status.code(), Some(8));
+    assert!(stderr(&output).contains(&format!("{}:3: ", cli.db_path().display())));
+    assert!(!stderr(&output).contains("panicked"));
+}
+
+#[test]
+fn test_cli_duplicate_id_exits_nine() {
+    let cli = Cli::new();
+    cli.run(&["add", "Call", "bob"]);
+    let content = fs::read_to_string(cli.db_path()).unwrap();
+    let row = content.lines().nth(1).unwrap().to_string();
+    fs::write(cli.db_path(), format!("{}{}\n", content, row)).unwrap();
+
+    let output = cli.run(&["list"]);
+
+    assert_eq!(output.status.code(), Some(9));
+    assert!(stderr(&output).contains("ID 1 is also used on line 2"));
+}
+
+#[test]
+fn test_cli_failed_write_exits_four() {
+    let cli = Cli::new();
+    cli.run(&["add", "Call", "bob"]);
+    // the rewrite goes through a temp file next to the database
+    fs::create_dir(cli.db_path().with_extension("tmp")).unwrap();
+
+    let output = cli.run(&["note", "1", "Number", "is", "in", "the", "wiki"]);
+
+    assert_eq!(output.status.code(), Some(4));
+    assert!(stderr(&output).starts_with("error: write error: "));
+}
+
+#[test]
+fn test_cli_verbose_explains_errors() {
+    let cli = Cli::new();
+
+    let quiet = cli.run(&["delete-completed"]);
+    let verbose = cli.run(&["--verbose", "delete-completed"]);
+
+    assert_eq!(verbose.status.code(), quiet.status.code());
+    assert!(!stderr(&quiet).contains("exit code"));
+    assert!(stderr(&verbose).contains("exit code 5"));
+}
+
+#[test]
+fn test_cli_doctor_reports_and_fixes() {
+    let cli = Cli::new();
+    cli.run(&["add", "Call", "bob"]);
+    let content = fs::read_to_string(cli.db_path()).unwrap();
+    fs::write(cli.db_path(), format!("{}not-a-number;x;y\n", content)).unwrap();
+
+    let report = cli.run(&["doctor"]);
+    assert_eq!(report.status.code(), Some(8));
+    assert!(String::from_utf8_lossy(&report.stdout).contains("line 3: wrong delimiter: 3 fields instead of 16"));
+
+    let fix = cli.run(&["repair", "--fix"]);
+    assert_eq!(fix.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&fix.stdout).contains("-repair.csv"));
+    assert!(String::from_utf8_lossy(&cli.run(&["backup", "list"]).stdout).contains("-repair.csv"));
+    assert_eq!(cli.run(&["doctor"]).status.code(), Some(0));
+    assert_eq!(cli.run(&["list"]).status.code(), Some(0));
+}
+
+#[test]
+fn test_cli_backup_and_restore() {
+    let cli = Cli::new();
+    cli.run(&["add", "Call", "bob"]);
+    cli.run(&["delete", "1"]);
+
+    let list = cli.run(&["backup", "list"]);
+    let name = String::from_utf8_lossy(&list.stdout).lines()
+        .find(|l| l.ends_with("1 rows"))
+        .map(|l| l.split('\t').next().unwrap().to_string())
+        .unwrap();
+
+    assert_eq!(cli.run(&["backup", "restore", &name]).status.code(), Some(0));
+    assert_eq!(cli.run(&["show", "1"]).status.code(), Some(0));
+    assert_eq!(cli.run(&["backup", "restore", "nope"]).status.code(), Some(6));
+}
+
+#[test]
+fn test_cli_report_time_at_the_limits() {
+    let cli = Cli::new();
+    cli.run(&["add", "Call", "bob"]);
+
+    assert_eq!(cli.run(&["report", "time", "--until", "+262142-12-31"]).status.code(), Some(2));
+
+    cli.run(&["log", "1", "9223372036854775807s"]);
+    cli.run(&["log", "1", "9223372036854775807s"]);
+    assert_eq!(cli.run(&["report", "time"]).status.code(), Some(0));
+}
+
+#[test]
+fn test_cli_burndown_at_the_limits() {
+    let cli = Cli::new();
+    cli.run(&["add", "Call", "bob"]);
+    cli.run(&["add", "Buy", "milk"]);
+    cli.run(&["estimate", "1", "4294967295"]);
+    cli.run(&["estimate", "2", "5"]);
+
+    assert_eq!(cli.run(&["report", "burndown"]).status.code(), Some(0));
+    assert_eq!(cli.run(&["report", "burndown", "--to=-262143-01-05"]).status.code(), Some(2));
+}
+
+#[test]
+fn test_cli_runs_builtin_views() {
+    let cli = Cli::new();
+    cli.run(&["add", "Call", "bob"]);
+    cli.run(&["complete", "1"]);
+
+    for name in ["done", "incomplete", "snoozed"] {
+        let output = cli.run(&[name]);
+        assert_eq!(output.status.code(), Some(0), "{}: {}", name, stderr(&output));
+    }
+    assert!(String::from_utf8_lossy(&cli.run(&["done"]).stdout).contains("Call bob"));
+}
+
+#[test]
+fn test_cli_history_user_from_config() {
+    let cli = Cli::new();
+    fs::create_dir_all(cli.db_path().parent().unwrap()).unwrap();
+    fs::write(cli.db_path().with_file_name("config.json"), r#"{"user": "alice"}"#).unwrap();
+
+    let add = Command::new(env!("CARGO_BIN_EXE_todo"))
+        .args(["add", "Call", "bob"])
+        .env("HOME", cli.home.path())
+        .env("APPDATA", cli.home.path())
+        .env_remove("TODO_USER")
+        .output()
+        .unwrap();
+    assert_eq!(add.status.code(), Some(0));
+
+    let history = String::from_utf8_lossy(&cli.run(&["history"]).stdout).to_string();
+    assert!(history.contains("  alice  add #1"), "{}", history);
+}